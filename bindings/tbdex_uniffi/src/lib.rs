@@ -38,10 +38,14 @@ use crate::{
     resources::{
         balance::Balance,
         offering::{data::Offering as OfferingData, Offering},
+        payment_form::{
+            payment_details_from_form, payment_form_from_schema, validate_payment_form,
+        },
     },
 };
 use errors::Result;
 use tbdex::{
+    errors::FieldError as FieldErrorData,
     http::{ErrorDetail as ErrorDetailData, ErrorResponseBody as ErrorResponseBodyData},
     http_client::exchanges::GetExchangeIdsQueryParams as GetExchangeIdsQueryParamsData,
     messages::{
@@ -62,6 +66,9 @@ use tbdex::{
     },
     resources::{
         balance::{Balance as BalanceData, BalanceData as BalanceDataData},
+        payment_form::{
+            FieldType as FormFieldType, FormField as FormFieldData, PaymentForm as PaymentFormData,
+        },
         ResourceKind, ResourceMetadata as ResourceMetadataData,
     },
 };
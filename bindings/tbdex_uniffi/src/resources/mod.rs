@@ -1,2 +1,3 @@
 pub mod balance;
 pub mod offering;
+pub mod payment_form;
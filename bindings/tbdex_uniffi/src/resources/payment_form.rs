@@ -0,0 +1,28 @@
+use crate::errors::Result;
+use std::collections::HashMap;
+use tbdex::{
+    errors::FieldError,
+    resources::payment_form::{self, PaymentForm},
+};
+
+pub fn payment_form_from_schema(json_serialized_schema: String) -> Result<PaymentForm> {
+    let schema = serde_json::from_str::<serde_json::Value>(&json_serialized_schema)?;
+    Ok(PaymentForm::from_schema(&schema)?)
+}
+
+pub fn validate_payment_form(
+    json_serialized_schema: String,
+    values: HashMap<String, String>,
+) -> Result<Vec<FieldError>> {
+    let schema = serde_json::from_str::<serde_json::Value>(&json_serialized_schema)?;
+    Ok(payment_form::validate_form(&schema, values)?)
+}
+
+pub fn payment_details_from_form(
+    json_serialized_schema: String,
+    values: HashMap<String, String>,
+) -> Result<String> {
+    let schema = serde_json::from_str::<serde_json::Value>(&json_serialized_schema)?;
+    let payment_details = payment_form::payment_details_from_form(&schema, values)?;
+    Ok(serde_json::to_string(&payment_details.into_value())?)
+}
@@ -0,0 +1,30 @@
+use crate::errors::{map_err, Result};
+use std::collections::HashMap;
+use tbdex::resources::payment_form::{self, PaymentForm};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+pub fn payment_form_from_schema(schema_json: &str) -> Result<String> {
+    let schema = serde_json::from_str(schema_json).map_err(|e| map_err(e.into()))?;
+    let form = PaymentForm::from_schema(&schema).map_err(map_err)?;
+    serde_json::to_string(&form).map_err(|e| map_err(e.into()))
+}
+
+#[wasm_bindgen]
+pub fn validate_payment_form(schema_json: &str, values_json: &str) -> Result<String> {
+    let schema = serde_json::from_str(schema_json).map_err(|e| map_err(e.into()))?;
+    let values = serde_json::from_str::<HashMap<String, String>>(values_json)
+        .map_err(|e| map_err(e.into()))?;
+    let field_errors = payment_form::validate_form(&schema, values).map_err(map_err)?;
+    serde_json::to_string(&field_errors).map_err(|e| map_err(e.into()))
+}
+
+#[wasm_bindgen]
+pub fn payment_details_from_form(schema_json: &str, values_json: &str) -> Result<String> {
+    let schema = serde_json::from_str(schema_json).map_err(|e| map_err(e.into()))?;
+    let values = serde_json::from_str::<HashMap<String, String>>(values_json)
+        .map_err(|e| map_err(e.into()))?;
+    let payment_details =
+        payment_form::payment_details_from_form(&schema, values).map_err(map_err)?;
+    serde_json::to_string(&payment_details.into_value()).map_err(|e| map_err(e.into()))
+}
@@ -0,0 +1,165 @@
+package tbdex.sdk.resources
+
+import tbdex.sdk.Json
+import tbdex.sdk.TbdexException
+import tbdex.sdk.rust.FieldErrorData as RustCoreFieldError
+import tbdex.sdk.rust.FormFieldData as RustCoreFormField
+import tbdex.sdk.rust.FormFieldType as RustCoreFormFieldType
+import tbdex.sdk.rust.paymentDetailsFromForm as rustCorePaymentDetailsFromForm
+import tbdex.sdk.rust.paymentFormFromSchema as rustCorePaymentFormFromSchema
+import tbdex.sdk.rust.validatePaymentForm as rustCoreValidatePaymentForm
+
+/**
+ * The kind of value a form field takes, named after the JSON schema type it comes from.
+ */
+enum class FormFieldType {
+    STRING,
+    NUMBER,
+    INTEGER,
+    BOOLEAN;
+
+    companion object {
+        internal fun fromRustCore(rustCore: RustCoreFormFieldType): FormFieldType {
+            return when (rustCore) {
+                RustCoreFormFieldType.STRING -> STRING
+                RustCoreFormFieldType.NUMBER -> NUMBER
+                RustCoreFormFieldType.INTEGER -> INTEGER
+                RustCoreFormFieldType.BOOLEAN -> BOOLEAN
+            }
+        }
+    }
+}
+
+/**
+ * A single input of a [PaymentForm].
+ *
+ * @property name The property the field fills in, with those of nested objects named by their path, e.g. `address.postalCode`.
+ * @property title The schema's title for the property, or its name if it has none.
+ * @property description Optional description of the field.
+ * @property fieldType The kind of value the field takes.
+ * @property required Whether the field must be filled in.
+ * @property options The values allowed by the schema's `enum`, for rendering as a choice.
+ * @property pattern A regular expression the value must match.
+ * @property minLength Optional minimum length of the value.
+ * @property maxLength Optional maximum length of the value.
+ * @property defaultValue The schema's default, for pre-filling the field.
+ */
+data class FormField(
+    val name: String,
+    val title: String,
+    val description: String? = null,
+    val fieldType: FormFieldType,
+    val required: Boolean,
+    val options: List<String>? = null,
+    val pattern: String? = null,
+    val minLength: ULong? = null,
+    val maxLength: ULong? = null,
+    val defaultValue: String? = null
+) {
+    companion object {
+        internal fun fromRustCore(rustCore: RustCoreFormField): FormField {
+            return FormField(
+                rustCore.name,
+                rustCore.title,
+                rustCore.description,
+                FormFieldType.fromRustCore(rustCore.fieldType),
+                rustCore.required,
+                rustCore.options,
+                rustCore.pattern,
+                rustCore.minLength,
+                rustCore.maxLength,
+                rustCore.defaultValue
+            )
+        }
+    }
+}
+
+/**
+ * A form field whose input does not satisfy the payment details schema.
+ *
+ * @property field The name of the form field.
+ * @property reason Why the input was rejected.
+ */
+data class FieldError(
+    val field: String,
+    val reason: String
+) {
+    companion object {
+        internal fun fromRustCore(rustCore: RustCoreFieldError): FieldError {
+            return FieldError(rustCore.field, rustCore.reason)
+        }
+    }
+}
+
+/**
+ * The form for a payment method's `requiredPaymentDetails`.
+ *
+ * Fields are ordered as the PFI intends them to be shown. Input is keyed by [FormField.name], and fields left
+ * blank are treated as not filled in.
+ *
+ * @property title Optional title of the form.
+ * @property description Optional description of the form.
+ * @property fields The inputs of the form.
+ */
+data class PaymentForm private constructor(
+    val title: String?,
+    val description: String?,
+    val fields: List<FormField>,
+    private val jsonSerializedSchema: String
+) {
+    companion object {
+        /**
+         * Compiles the form for a payment method's `requiredPaymentDetails` schema.
+         *
+         * @param requiredPaymentDetails The JSON schema of the payment method's required payment details.
+         * @return The compiled form.
+         * @throws TbdexException if the schema is not supported.
+         */
+        fun fromSchema(requiredPaymentDetails: Map<String, Any?>): PaymentForm {
+            try {
+                val jsonSerializedSchema = Json.stringify(requiredPaymentDetails)
+                val rustCoreForm = rustCorePaymentFormFromSchema(jsonSerializedSchema)
+                return PaymentForm(
+                    rustCoreForm.title,
+                    rustCoreForm.description,
+                    rustCoreForm.fields.map { FormField.fromRustCore(it) },
+                    jsonSerializedSchema
+                )
+            } catch (e: tbdex.sdk.rust.TbdexException.Exception) {
+                throw TbdexException.fromRustCore(e)
+            }
+        }
+    }
+
+    /**
+     * Checks form input against the schema.
+     *
+     * @param values The form input, keyed by field name.
+     * @return Every field which does not satisfy the schema; empty if the form may be submitted.
+     * @throws TbdexException if the input cannot be checked.
+     */
+    fun validate(values: Map<String, String>): List<FieldError> {
+        try {
+            return rustCoreValidatePaymentForm(jsonSerializedSchema, values).map { FieldError.fromRustCore(it) }
+        } catch (e: tbdex.sdk.rust.TbdexException.Exception) {
+            throw TbdexException.fromRustCore(e)
+        }
+    }
+
+    /**
+     * Turns form input into payment details conformed to the schema, for use in an Rfq.
+     *
+     * @param values The form input, keyed by field name.
+     * @return The payment details.
+     * @throws TbdexException if any field does not satisfy the schema.
+     */
+    fun toPaymentDetails(values: Map<String, String>): Map<String, Any?> {
+        try {
+            val jsonSerializedPaymentDetails = rustCorePaymentDetailsFromForm(jsonSerializedSchema, values)
+            @Suppress("UNCHECKED_CAST")
+            return Json.jsonMapper.readValue(jsonSerializedPaymentDetails, Map::class.java) as Map<String, Any?>
+        } catch (e: tbdex.sdk.rust.TbdexException.Exception) {
+            throw TbdexException.fromRustCore(e)
+        }
+    }
+}
@@ -1300,10 +1300,16 @@ internal interface UniffiLib : Library {
     ): RustBuffer.ByValue
     fun uniffi_tbdex_uniffi_fn_func_get_offerings(`pfiDidUri`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
+    fun uniffi_tbdex_uniffi_fn_func_payment_details_from_form(`jsonSerializedSchema`: RustBuffer.ByValue,`values`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
+    fun uniffi_tbdex_uniffi_fn_func_payment_form_from_schema(`jsonSerializedSchema`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
     fun uniffi_tbdex_uniffi_fn_func_submit_cancel(`cancel`: Pointer,uniffi_out_err: UniffiRustCallStatus, 
     ): Unit
     fun uniffi_tbdex_uniffi_fn_func_submit_order(`order`: Pointer,uniffi_out_err: UniffiRustCallStatus, 
     ): Unit
+    fun uniffi_tbdex_uniffi_fn_func_validate_payment_form(`jsonSerializedSchema`: RustBuffer.ByValue,`values`: RustBuffer.ByValue,uniffi_out_err: UniffiRustCallStatus, 
+    ): RustBuffer.ByValue
     fun ffi_tbdex_uniffi_rustbuffer_alloc(`size`: Long,uniffi_out_err: UniffiRustCallStatus, 
     ): RustBuffer.ByValue
     fun ffi_tbdex_uniffi_rustbuffer_from_bytes(`bytes`: ForeignBytes.ByValue,uniffi_out_err: UniffiRustCallStatus, 
@@ -1426,10 +1432,16 @@ internal interface UniffiLib : Library {
     ): Short
     fun uniffi_tbdex_uniffi_checksum_func_get_offerings(
     ): Short
+    fun uniffi_tbdex_uniffi_checksum_func_payment_details_from_form(
+    ): Short
+    fun uniffi_tbdex_uniffi_checksum_func_payment_form_from_schema(
+    ): Short
     fun uniffi_tbdex_uniffi_checksum_func_submit_cancel(
     ): Short
     fun uniffi_tbdex_uniffi_checksum_func_submit_order(
     ): Short
+    fun uniffi_tbdex_uniffi_checksum_func_validate_payment_form(
+    ): Short
     fun uniffi_tbdex_uniffi_checksum_method_balance_get_data(
     ): Short
     fun uniffi_tbdex_uniffi_checksum_method_balance_sign(
@@ -1656,12 +1668,21 @@ private fun uniffiCheckApiChecksums(lib: UniffiLib) {
     if (lib.uniffi_tbdex_uniffi_checksum_func_get_offerings() != 14537.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
+    if (lib.uniffi_tbdex_uniffi_checksum_func_payment_details_from_form() != 9343.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
+    if (lib.uniffi_tbdex_uniffi_checksum_func_payment_form_from_schema() != 31239.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
     if (lib.uniffi_tbdex_uniffi_checksum_func_submit_cancel() != 61136.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
     if (lib.uniffi_tbdex_uniffi_checksum_func_submit_order() != 52455.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
+    if (lib.uniffi_tbdex_uniffi_checksum_func_validate_payment_form() != 62895.toShort()) {
+        throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
+    }
     if (lib.uniffi_tbdex_uniffi_checksum_method_balance_get_data() != 2164.toShort()) {
         throw RuntimeException("UniFFI API checksum mismatch: try cleaning and rebuilding your project")
     }
@@ -1993,6 +2014,26 @@ inline fun <T : Disposable?, R> T.use(block: (T) -> R) =
 /** Used to instantiate an interface without an actual pointer, for fakes in tests, mostly. */
 object NoPointer
 
+public object FfiConverterULong: FfiConverter<ULong, Long> {
+    override fun lift(value: Long): ULong {
+        return value.toULong()
+    }
+
+    override fun read(buf: ByteBuffer): ULong {
+        return lift(buf.getLong())
+    }
+
+    override fun lower(value: ULong): Long {
+        return value.toLong()
+    }
+
+    override fun allocationSize(value: ULong) = 8UL
+
+    override fun write(value: ULong, buf: ByteBuffer) {
+        buf.putLong(value.toLong())
+    }
+}
+
 public object FfiConverterLong: FfiConverter<Long, Long> {
     override fun lift(value: Long): Long {
         return value
@@ -8588,6 +8629,96 @@ public object FfiConverterTypeExchangeData: FfiConverterRustBuffer<ExchangeData>
 
 
 
+data class FieldErrorData (
+    var `field`: kotlin.String, 
+    var `reason`: kotlin.String
+) {
+    
+    companion object
+}
+
+public object FfiConverterTypeFieldErrorData: FfiConverterRustBuffer<FieldErrorData> {
+    override fun read(buf: ByteBuffer): FieldErrorData {
+        return FieldErrorData(
+            FfiConverterString.read(buf),
+            FfiConverterString.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: FieldErrorData) = (
+            FfiConverterString.allocationSize(value.`field`) +
+            FfiConverterString.allocationSize(value.`reason`)
+    )
+
+    override fun write(value: FieldErrorData, buf: ByteBuffer) {
+            FfiConverterString.write(value.`field`, buf)
+            FfiConverterString.write(value.`reason`, buf)
+    }
+}
+
+
+
+data class FormFieldData (
+    var `name`: kotlin.String, 
+    var `title`: kotlin.String, 
+    var `description`: kotlin.String?, 
+    var `fieldType`: FormFieldType, 
+    var `required`: kotlin.Boolean, 
+    var `options`: List<kotlin.String>?, 
+    var `pattern`: kotlin.String?, 
+    var `minLength`: kotlin.ULong?, 
+    var `maxLength`: kotlin.ULong?, 
+    var `defaultValue`: kotlin.String?
+) {
+    
+    companion object
+}
+
+public object FfiConverterTypeFormFieldData: FfiConverterRustBuffer<FormFieldData> {
+    override fun read(buf: ByteBuffer): FormFieldData {
+        return FormFieldData(
+            FfiConverterString.read(buf),
+            FfiConverterString.read(buf),
+            FfiConverterOptionalString.read(buf),
+            FfiConverterTypeFormFieldType.read(buf),
+            FfiConverterBoolean.read(buf),
+            FfiConverterOptionalSequenceString.read(buf),
+            FfiConverterOptionalString.read(buf),
+            FfiConverterOptionalULong.read(buf),
+            FfiConverterOptionalULong.read(buf),
+            FfiConverterOptionalString.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: FormFieldData) = (
+            FfiConverterString.allocationSize(value.`name`) +
+            FfiConverterString.allocationSize(value.`title`) +
+            FfiConverterOptionalString.allocationSize(value.`description`) +
+            FfiConverterTypeFormFieldType.allocationSize(value.`fieldType`) +
+            FfiConverterBoolean.allocationSize(value.`required`) +
+            FfiConverterOptionalSequenceString.allocationSize(value.`options`) +
+            FfiConverterOptionalString.allocationSize(value.`pattern`) +
+            FfiConverterOptionalULong.allocationSize(value.`minLength`) +
+            FfiConverterOptionalULong.allocationSize(value.`maxLength`) +
+            FfiConverterOptionalString.allocationSize(value.`defaultValue`)
+    )
+
+    override fun write(value: FormFieldData, buf: ByteBuffer) {
+            FfiConverterString.write(value.`name`, buf)
+            FfiConverterString.write(value.`title`, buf)
+            FfiConverterOptionalString.write(value.`description`, buf)
+            FfiConverterTypeFormFieldType.write(value.`fieldType`, buf)
+            FfiConverterBoolean.write(value.`required`, buf)
+            FfiConverterOptionalSequenceString.write(value.`options`, buf)
+            FfiConverterOptionalString.write(value.`pattern`, buf)
+            FfiConverterOptionalULong.write(value.`minLength`, buf)
+            FfiConverterOptionalULong.write(value.`maxLength`, buf)
+            FfiConverterOptionalString.write(value.`defaultValue`, buf)
+    }
+}
+
+
+
 data class GetBalancesResponseBodyData (
     var `data`: List<Balance>
 ) : Disposable {
@@ -9073,6 +9204,39 @@ public object FfiConverterTypeOrderStatusDataData: FfiConverterRustBuffer<OrderS
 
 
 
+data class PaymentFormData (
+    var `title`: kotlin.String?, 
+    var `description`: kotlin.String?, 
+    var `fields`: List<FormFieldData>
+) {
+    
+    companion object
+}
+
+public object FfiConverterTypePaymentFormData: FfiConverterRustBuffer<PaymentFormData> {
+    override fun read(buf: ByteBuffer): PaymentFormData {
+        return PaymentFormData(
+            FfiConverterOptionalString.read(buf),
+            FfiConverterOptionalString.read(buf),
+            FfiConverterSequenceTypeFormFieldData.read(buf),
+        )
+    }
+
+    override fun allocationSize(value: PaymentFormData) = (
+            FfiConverterOptionalString.allocationSize(value.`title`) +
+            FfiConverterOptionalString.allocationSize(value.`description`) +
+            FfiConverterSequenceTypeFormFieldData.allocationSize(value.`fields`)
+    )
+
+    override fun write(value: PaymentFormData, buf: ByteBuffer) {
+            FfiConverterOptionalString.write(value.`title`, buf)
+            FfiConverterOptionalString.write(value.`description`, buf)
+            FfiConverterSequenceTypeFormFieldData.write(value.`fields`, buf)
+    }
+}
+
+
+
 data class PaymentInstructionData (
     var `link`: kotlin.String?, 
     var `instruction`: kotlin.String?
@@ -9420,6 +9584,35 @@ public object FfiConverterTypeVerificationMethodData: FfiConverterRustBuffer<Ver
 
 
 
+enum class FormFieldType {
+    
+    STRING,
+    NUMBER,
+    INTEGER,
+    BOOLEAN;
+    companion object
+}
+
+
+public object FfiConverterTypeFormFieldType: FfiConverterRustBuffer<FormFieldType> {
+    override fun read(buf: ByteBuffer) = try {
+        FormFieldType.values()[buf.getInt() - 1]
+    } catch (e: IndexOutOfBoundsException) {
+        throw RuntimeException("invalid enum value, something is very wrong!!", e)
+    }
+
+    override fun allocationSize(value: FormFieldType) = 4UL
+
+    override fun write(value: FormFieldType, buf: ByteBuffer) {
+        buf.putInt(value.ordinal + 1)
+    }
+}
+
+
+
+
+
+
 enum class MessageKind {
     
     RFQ,
@@ -9639,6 +9832,35 @@ public object FfiConverterTypeWeb5Error : FfiConverterRustBuffer<Web5Exception>
 
 
 
+public object FfiConverterOptionalULong: FfiConverterRustBuffer<kotlin.ULong?> {
+    override fun read(buf: ByteBuffer): kotlin.ULong? {
+        if (buf.get().toInt() == 0) {
+            return null
+        }
+        return FfiConverterULong.read(buf)
+    }
+
+    override fun allocationSize(value: kotlin.ULong?): ULong {
+        if (value == null) {
+            return 1UL
+        } else {
+            return 1UL + FfiConverterULong.allocationSize(value)
+        }
+    }
+
+    override fun write(value: kotlin.ULong?, buf: ByteBuffer) {
+        if (value == null) {
+            buf.put(0)
+        } else {
+            buf.put(1)
+            FfiConverterULong.write(value, buf)
+        }
+    }
+}
+
+
+
+
 public object FfiConverterOptionalLong: FfiConverterRustBuffer<kotlin.Long?> {
     override fun read(buf: ByteBuffer): kotlin.Long? {
         if (buf.get().toInt() == 0) {
@@ -10170,6 +10392,56 @@ public object FfiConverterSequenceTypeErrorDetailData: FfiConverterRustBuffer<Li
 
 
 
+public object FfiConverterSequenceTypeFieldErrorData: FfiConverterRustBuffer<List<FieldErrorData>> {
+    override fun read(buf: ByteBuffer): List<FieldErrorData> {
+        val len = buf.getInt()
+        return List<FieldErrorData>(len) {
+            FfiConverterTypeFieldErrorData.read(buf)
+        }
+    }
+
+    override fun allocationSize(value: List<FieldErrorData>): ULong {
+        val sizeForLength = 4UL
+        val sizeForItems = value.map { FfiConverterTypeFieldErrorData.allocationSize(it) }.sum()
+        return sizeForLength + sizeForItems
+    }
+
+    override fun write(value: List<FieldErrorData>, buf: ByteBuffer) {
+        buf.putInt(value.size)
+        value.iterator().forEach {
+            FfiConverterTypeFieldErrorData.write(it, buf)
+        }
+    }
+}
+
+
+
+
+public object FfiConverterSequenceTypeFormFieldData: FfiConverterRustBuffer<List<FormFieldData>> {
+    override fun read(buf: ByteBuffer): List<FormFieldData> {
+        val len = buf.getInt()
+        return List<FormFieldData>(len) {
+            FfiConverterTypeFormFieldData.read(buf)
+        }
+    }
+
+    override fun allocationSize(value: List<FormFieldData>): ULong {
+        val sizeForLength = 4UL
+        val sizeForItems = value.map { FfiConverterTypeFormFieldData.allocationSize(it) }.sum()
+        return sizeForLength + sizeForItems
+    }
+
+    override fun write(value: List<FormFieldData>, buf: ByteBuffer) {
+        buf.putInt(value.size)
+        value.iterator().forEach {
+            FfiConverterTypeFormFieldData.write(it, buf)
+        }
+    }
+}
+
+
+
+
 public object FfiConverterSequenceTypeGetExchangeResponseBodyDataSerializedMessage: FfiConverterRustBuffer<List<GetExchangeResponseBodyDataSerializedMessage>> {
     override fun read(buf: ByteBuffer): List<GetExchangeResponseBodyDataSerializedMessage> {
         val len = buf.getInt()
@@ -10325,6 +10597,26 @@ public object FfiConverterMapStringString: FfiConverterRustBuffer<Map<kotlin.Str
     }
     
 
+    @Throws(TbdexException::class) fun `paymentDetailsFromForm`(`jsonSerializedSchema`: kotlin.String, `values`: Map<kotlin.String, kotlin.String>): kotlin.String {
+            return FfiConverterString.lift(
+    uniffiRustCallWithError(TbdexException) { _status ->
+    UniffiLib.INSTANCE.uniffi_tbdex_uniffi_fn_func_payment_details_from_form(
+        FfiConverterString.lower(`jsonSerializedSchema`),FfiConverterMapStringString.lower(`values`),_status)
+}
+    )
+    }
+    
+
+    @Throws(TbdexException::class) fun `paymentFormFromSchema`(`jsonSerializedSchema`: kotlin.String): PaymentFormData {
+            return FfiConverterTypePaymentFormData.lift(
+    uniffiRustCallWithError(TbdexException) { _status ->
+    UniffiLib.INSTANCE.uniffi_tbdex_uniffi_fn_func_payment_form_from_schema(
+        FfiConverterString.lower(`jsonSerializedSchema`),_status)
+}
+    )
+    }
+    
+
     @Throws(TbdexException::class) fun `submitCancel`(`cancel`: Cancel)
         = 
     uniffiRustCallWithError(TbdexException) { _status ->
@@ -10343,4 +10635,14 @@ public object FfiConverterMapStringString: FfiConverterRustBuffer<Map<kotlin.Str
     
     
 
+    @Throws(TbdexException::class) fun `validatePaymentForm`(`jsonSerializedSchema`: kotlin.String, `values`: Map<kotlin.String, kotlin.String>): List<FieldErrorData> {
+            return FfiConverterSequenceTypeFieldErrorData.lift(
+    uniffiRustCallWithError(TbdexException) { _status ->
+    UniffiLib.INSTANCE.uniffi_tbdex_uniffi_fn_func_validate_payment_form(
+        FfiConverterString.lower(`jsonSerializedSchema`),FfiConverterMapStringString.lower(`values`),_status)
+}
+    )
+    }
+    
+
 
@@ -3,6 +3,7 @@ import { Offering } from "./offering";
 
 export * from "./balance"
 export * from "./offering"
+export * from "./payment-form"
 
 export type Resource = Balance | Offering;
 
@@ -0,0 +1,81 @@
+import { tbdexError } from "../errors";
+import wasm from "../wasm";
+
+export class PaymentForm {
+  readonly title?: string;
+  readonly description?: string;
+  readonly fields: FormField[];
+  private readonly schemaJSON: string;
+
+  private constructor(
+    title: string | undefined,
+    description: string | undefined,
+    fields: FormField[],
+    schemaJSON: string
+  ) {
+    this.title = title;
+    this.description = description;
+    this.fields = fields;
+    this.schemaJSON = schemaJSON;
+  }
+
+  static fromSchema = (requiredPaymentDetails: any): PaymentForm => {
+    try {
+      const schemaJSON = JSON.stringify(requiredPaymentDetails);
+      const json = wasm.payment_form_from_schema(schemaJSON);
+      const obj = JSON.parse(json);
+      return new PaymentForm(
+        obj.title,
+        obj.description,
+        obj.fields,
+        schemaJSON
+      );
+    } catch (error) {
+      throw tbdexError(error);
+    }
+  };
+
+  validate = (values: Record<string, string>): FieldError[] => {
+    try {
+      const json = wasm.validate_payment_form(
+        this.schemaJSON,
+        JSON.stringify(values)
+      );
+      return JSON.parse(json);
+    } catch (error) {
+      throw tbdexError(error);
+    }
+  };
+
+  toPaymentDetails = (values: Record<string, string>): any => {
+    try {
+      const json = wasm.payment_details_from_form(
+        this.schemaJSON,
+        JSON.stringify(values)
+      );
+      return JSON.parse(json);
+    } catch (error) {
+      throw tbdexError(error);
+    }
+  };
+}
+
+export type FormField = {
+  name: string;
+  title: string;
+  description?: string;
+  fieldType: FormFieldType;
+  required: boolean;
+  options?: string[];
+  pattern?: string;
+  minLength?: number;
+  maxLength?: number;
+  defaultValue?: string;
+};
+
+export type FormFieldType = "string" | "number" | "integer" | "boolean";
+
+export type FieldError = {
+  field: string;
+  reason: string;
+};
@@ -1,71 +1,153 @@
+use std::env;
 use std::fs;
 use std::io::{self, BufRead};
 use std::path::Path;
 
-fn write_json_schemas() -> Result<(), Box<dyn std::error::Error>> {
-    let commit_hash = "7d2fdd03c9405b920b056ab7c7c776a858dc3591";
-    let schemas = vec![
-        // Tbdex Schemas
-      ("DEFINITIONS_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/definitions.json", commit_hash)),
-      ("RESOURCE_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/resource.schema.json", commit_hash)),
-      ("BALANCE_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/balance.schema.json", commit_hash)),
-      ("OFFERING_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/offering.schema.json", commit_hash)),
-      ("MESSAGE_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/message.schema.json", commit_hash)),
-      ("RFQ_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/rfq.schema.json", commit_hash)),
-      ("RFQ_PRIVATE_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/rfq-private.schema.json", commit_hash)),
-      ("QUOTE_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/quote.schema.json", commit_hash)),
-      ("ORDER_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/order.schema.json", commit_hash)),
-      ("ORDER_INSTRUCTIONS_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/orderinstructions.schema.json", commit_hash)),
-      ("CANCEL_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/cancel.schema.json", commit_hash)),
-      ("ORDER_STATUS_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/orderstatus.schema.json", commit_hash)),
-      ("CLOSE_DATA_JSON_SCHEMA", format!("https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/close.schema.json", commit_hash)),
-
-        // Json schema
-      ("DRAFT_07_JSON_SCHEMA", "https://json-schema.org/draft-07/schema".to_string()),
-    ];
-
-    let dest_path = Path::new("src/json_schemas").join("generated.rs");
-
-    // Check if the existing file contains the same commit hash
-    if let Ok(file) = fs::File::open(&dest_path) {
-        let reader = io::BufReader::new(file);
-        let mut lines = reader.lines();
-        let third_line = lines.nth(3); // Get the third line (index 2)
-
-        if let Some(Ok(line)) = third_line {
-            if line == format!("pub const GIT_COMMIT_HASH: &str = \"{}\";", commit_hash) {
-                // The commit hash matches, no need to re-fetch the schemas
-                println!("Schemas are up-to-date, skipping download.");
-                return Ok(());
-            }
-        }
-    }
+/// The tbDEX repository revision each supported protocol version's schemas are taken from, and
+/// the module under `src/json_schemas/generated` they are written to.
+const PROTOCOL_SCHEMA_SETS: &[(&str, &str, &[(&str, &str)])] = &[
+    (
+        "v1_0",
+        // the release of the 1.0 specification
+        "v1.0.0",
+        &[
+            ("DEFINITIONS_JSON_SCHEMA", "definitions.json"),
+            ("RESOURCE_JSON_SCHEMA", "resource.schema.json"),
+            ("OFFERING_DATA_JSON_SCHEMA", "offering.schema.json"),
+            ("MESSAGE_JSON_SCHEMA", "message.schema.json"),
+            ("RFQ_DATA_JSON_SCHEMA", "rfq.schema.json"),
+            ("RFQ_PRIVATE_DATA_JSON_SCHEMA", "rfq-private.schema.json"),
+            ("QUOTE_DATA_JSON_SCHEMA", "quote.schema.json"),
+            ("ORDER_DATA_JSON_SCHEMA", "order.schema.json"),
+            ("ORDER_STATUS_DATA_JSON_SCHEMA", "orderstatus.schema.json"),
+            ("CLOSE_DATA_JSON_SCHEMA", "close.schema.json"),
+        ],
+    ),
+    (
+        "v2_0",
+        "7d2fdd03c9405b920b056ab7c7c776a858dc3591",
+        &[
+            ("DEFINITIONS_JSON_SCHEMA", "definitions.json"),
+            ("RESOURCE_JSON_SCHEMA", "resource.schema.json"),
+            ("BALANCE_DATA_JSON_SCHEMA", "balance.schema.json"),
+            ("OFFERING_DATA_JSON_SCHEMA", "offering.schema.json"),
+            ("MESSAGE_JSON_SCHEMA", "message.schema.json"),
+            ("RFQ_DATA_JSON_SCHEMA", "rfq.schema.json"),
+            ("RFQ_PRIVATE_DATA_JSON_SCHEMA", "rfq-private.schema.json"),
+            ("QUOTE_DATA_JSON_SCHEMA", "quote.schema.json"),
+            ("ORDER_DATA_JSON_SCHEMA", "order.schema.json"),
+            (
+                "ORDER_INSTRUCTIONS_DATA_JSON_SCHEMA",
+                "orderinstructions.schema.json",
+            ),
+            ("CANCEL_DATA_JSON_SCHEMA", "cancel.schema.json"),
+            ("ORDER_STATUS_DATA_JSON_SCHEMA", "orderstatus.schema.json"),
+            ("CLOSE_DATA_JSON_SCHEMA", "close.schema.json"),
+        ],
+    ),
+];
 
-    let mut file_content = String::new();
+const DRAFT_07_JSON_SCHEMA_URL: &str = "https://json-schema.org/draft-07/schema";
+
+/// Set to regenerate the schema modules from their pinned revisions, which needs network access.
+/// Otherwise the checked in modules are only checked to be generated from those revisions.
+const REGENERATE_ENV: &str = "TBDEX_REGENERATE_SCHEMAS";
+
+fn generated_dir() -> &'static Path {
+    Path::new("src/json_schemas/generated")
+}
+
+/// Whether the generated file was written from the given revision, per its `GIT_COMMIT_HASH`.
+fn is_up_to_date(dest_path: &Path, revision: &str) -> bool {
+    let file = match fs::File::open(dest_path) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
 
-    // Add the commit hash to the top of the file
+    // the fourth line, after the header
+    let line = io::BufReader::new(file).lines().nth(3);
+    matches!(line, Some(Ok(line)) if line == format!("pub const GIT_COMMIT_HASH: &str = \"{}\";", revision))
+}
+
+fn fetch(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let response = reqwest::blocking::get(url)?.error_for_status()?;
+    Ok(response.text()?.replace('#', "\\#"))
+}
+
+fn header() -> String {
+    let mut file_content = String::new();
     file_content.push_str("// THIS FILE IS AUTO-GENERATED BY build.rs\n");
     file_content.push_str("#[warn(unused_imports)]\n");
     file_content.push_str("#[allow(dead_code)]\n");
+    file_content
+}
+
+fn write_protocol_schemas(
+    module: &str,
+    revision: &str,
+    schemas: &[(&str, &str)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file_content = header();
     file_content.push_str(&format!(
         "pub const GIT_COMMIT_HASH: &str = \"{}\";\n",
-        commit_hash
+        revision
     ));
 
-    for (name, url) in schemas {
-        let response = reqwest::blocking::get(&url)?;
-        let mut schema = response.text()?;
-        schema = schema.replace('#', "\\#");
+    for (name, file) in schemas {
+        let url = format!(
+            "https://raw.githubusercontent.com/TBD54566975/tbdex/{}/hosted/json-schemas/{}",
+            revision, file
+        );
+        let schema = fetch(&url)?;
         file_content.push_str(&format!("pub const {}: &str = r#\"{}\"#;\n", name, schema));
     }
 
-    fs::write(dest_path, file_content)?;
+    fs::write(generated_dir().join(format!("{}.rs", module)), file_content)?;
+
+    Ok(())
+}
+
+fn write_draft_07_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file_content = header();
+    file_content.push_str(&format!(
+        "pub const DRAFT_07_JSON_SCHEMA: &str = r#\"{}\"#;\n",
+        fetch(DRAFT_07_JSON_SCHEMA_URL)?
+    ));
+
+    fs::write(generated_dir().join("draft_07.rs"), file_content)?;
 
     Ok(())
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    write_json_schemas()?;
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/json_schemas/generated");
+    println!("cargo:rerun-if-env-changed={}", REGENERATE_ENV);
+
+    if env::var_os(REGENERATE_ENV).is_some() {
+        for (module, revision, schemas) in PROTOCOL_SCHEMA_SETS {
+            write_protocol_schemas(module, revision, schemas)?;
+        }
+        write_draft_07_schema()?;
+        return Ok(());
+    }
+
+    for (module, revision, _) in PROTOCOL_SCHEMA_SETS {
+        if !is_up_to_date(&generated_dir().join(format!("{}.rs", module)), revision) {
+            return Err(format!(
+                "src/json_schemas/generated/{}.rs is not generated from {}, set {}=1 to regenerate it",
+                module, revision, REGENERATE_ENV
+            )
+            .into());
+        }
+    }
+    if !generated_dir().join("draft_07.rs").exists() {
+        return Err(format!(
+            "src/json_schemas/generated/draft_07.rs is missing, set {}=1 to regenerate it",
+            REGENERATE_ENV
+        )
+        .into());
+    }
 
     Ok(())
 }
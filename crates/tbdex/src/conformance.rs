@@ -0,0 +1,442 @@
+//! Runs every vector in a directory of tbDEX test vectors according to its declared type,
+//! checking that those marked `error: true` are rejected.
+
+use crate::{
+    errors::{Result, TbdexError},
+    json::FromJson,
+    messages::{
+        cancel::Cancel, close::Close, order::Order, order_instructions::OrderInstructions,
+        order_status::OrderStatus, quote::Quote, rfq::Rfq,
+    },
+    resources::{balance::Balance, offering::Offering},
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// The hosted protocol test vectors, from the `tbdex` submodule at the root of the workspace.
+const HOSTED_VECTORS_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../tbdex/hosted/test-vectors/protocol/vectors"
+);
+
+/// What a test vector exercises, declared by the prefix of its file name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum VectorType {
+    /// `parse-*`: the input parses, verifies and equals the output.
+    Parse,
+    /// `verify-*`: the input parses and verifies.
+    Verify,
+    /// `verify-offering-requirements-*`: the `rfq` of the input satisfies its `offering`.
+    OfferingRequirements,
+    /// `verify-private-data-*` or `verify-rfq-private-data-*`: the private data of the input RFQ
+    /// matches the hashes in its data.
+    PrivateData,
+    /// Any other file, which is reported as skipped.
+    Unknown,
+}
+
+impl VectorType {
+    fn from_file_name(name: &str) -> Self {
+        if name.starts_with("parse-") {
+            VectorType::Parse
+        } else if name.starts_with("verify-offering-requirements") {
+            VectorType::OfferingRequirements
+        } else if name.starts_with("verify-private-data")
+            || name.starts_with("verify-rfq-private-data")
+        {
+            VectorType::PrivateData
+        } else if name.starts_with("verify-") {
+            VectorType::Verify
+        } else {
+            VectorType::Unknown
+        }
+    }
+}
+
+impl fmt::Display for VectorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VectorType::Parse => "parse",
+            VectorType::Verify => "verify",
+            VectorType::OfferingRequirements => "offering requirements",
+            VectorType::PrivateData => "private data",
+            VectorType::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The result of running a single test vector.
+#[derive(Clone, Debug, PartialEq)]
+enum VectorOutcome {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+/// A single test vector and its result.
+#[derive(Clone, Debug, PartialEq)]
+struct VectorReport {
+    /// The path of the vector relative to the directory it was discovered in.
+    name: String,
+    vector_type: VectorType,
+    /// Whether the vector is marked `error: true`, and so passes only if it is rejected.
+    expects_error: bool,
+    outcome: VectorOutcome,
+}
+
+/// The results of running every test vector in a directory.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ConformanceReport {
+    vectors: Vec<VectorReport>,
+}
+
+impl ConformanceReport {
+    fn passed(&self) -> impl Iterator<Item = &VectorReport> {
+        self.with_outcome(|o| matches!(o, VectorOutcome::Passed))
+    }
+
+    fn failed(&self) -> impl Iterator<Item = &VectorReport> {
+        self.with_outcome(|o| matches!(o, VectorOutcome::Failed(_)))
+    }
+
+    fn skipped(&self) -> impl Iterator<Item = &VectorReport> {
+        self.with_outcome(|o| matches!(o, VectorOutcome::Skipped(_)))
+    }
+
+    /// Whether no vector failed. Skipped vectors do not count as failures.
+    fn is_success(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    fn with_outcome(
+        &self,
+        predicate: impl Fn(&VectorOutcome) -> bool,
+    ) -> impl Iterator<Item = &VectorReport> {
+        self.vectors.iter().filter(move |v| predicate(&v.outcome))
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for vector in &self.vectors {
+            let expectation = if vector.expects_error {
+                "rejects"
+            } else {
+                "accepts"
+            };
+            match &vector.outcome {
+                VectorOutcome::Passed => writeln!(
+                    f,
+                    "PASS {} ({}, {})",
+                    vector.name, vector.vector_type, expectation
+                )?,
+                VectorOutcome::Failed(reason) => writeln!(
+                    f,
+                    "FAIL {} ({}, {}): {}",
+                    vector.name, vector.vector_type, expectation, reason
+                )?,
+                VectorOutcome::Skipped(reason) => writeln!(f, "SKIP {}: {}", vector.name, reason)?,
+            }
+        }
+        write!(
+            f,
+            "{} passed, {} failed, {} skipped",
+            self.passed().count(),
+            self.failed().count(),
+            self.skipped().count()
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct TestVector {
+    input: Value,
+    output: Option<Value>,
+    #[serde(default)]
+    error: bool,
+}
+
+/// Discovers every `.json` test vector beneath `dir` and runs each according to its type.
+///
+/// # Arguments
+///
+/// * `dir` - The directory of test vectors, such as [`HOSTED_VECTORS_DIR`].
+///
+/// # Returns
+///
+/// A report with one entry per vector in path order, or an error if the directory cannot be read.
+async fn run_directory(dir: impl AsRef<Path>) -> Result<ConformanceReport> {
+    let dir = dir.as_ref();
+
+    let mut paths = vec![];
+    discover(dir, &mut paths)?;
+    paths.sort();
+
+    let mut report = ConformanceReport::default();
+    for path in paths {
+        let name = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        report.vectors.push(run_vector(&path, name).await);
+    }
+
+    Ok(report)
+}
+
+fn discover(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        TbdexError::Generic(format!(
+            "unable to read test vectors in {}: {}",
+            dir.display(),
+            e
+        ))
+    })?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|e| TbdexError::Generic(e.to_string()))?
+            .path();
+        if path.is_dir() {
+            discover(&path, paths)?;
+        } else if path.extension().is_some_and(|e| e == "json") {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_vector(path: &Path, name: String) -> VectorReport {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let vector_type = VectorType::from_file_name(&file_name);
+
+    let mut report = VectorReport {
+        name,
+        vector_type: vector_type.clone(),
+        expects_error: false,
+        outcome: VectorOutcome::Passed,
+    };
+
+    let vector = match fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str::<TestVector>(&json).map_err(|e| e.to_string()))
+    {
+        Ok(vector) => vector,
+        Err(e) => {
+            report.outcome = VectorOutcome::Failed(format!("malformed test vector: {}", e));
+            return report;
+        }
+    };
+    report.expects_error = vector.error;
+
+    let result = match vector_type {
+        VectorType::Parse => run_parse(&vector.input, vector.output.as_ref()).await,
+        VectorType::Verify => run_parse(&vector.input, None).await,
+        VectorType::OfferingRequirements => run_offering_requirements(&vector.input).await,
+        VectorType::PrivateData => run_private_data(&vector.input),
+        VectorType::Unknown => {
+            report.outcome = VectorOutcome::Skipped("unrecognised vector type".to_string());
+            return report;
+        }
+    };
+
+    report.outcome = match (result, vector.error) {
+        (Ok(()), false) | (Err(_), true) => VectorOutcome::Passed,
+        (Ok(()), true) => {
+            VectorOutcome::Failed("expected an error but the vector was accepted".to_string())
+        }
+        (Err(e), false) => VectorOutcome::Failed(e.to_string()),
+    };
+    report
+}
+
+/// The input as a JSON string; vectors carry the serialized form, though objects are accepted.
+fn input_json(input: &Value) -> Result<String> {
+    match input {
+        Value::String(json) => Ok(json.clone()),
+        value => Ok(serde_json::to_string(value)?),
+    }
+}
+
+async fn run_parse(input: &Value, output: Option<&Value>) -> Result<()> {
+    let json = input_json(input)?;
+    let value: Value = serde_json::from_str(&json)?;
+    let kind = value
+        .pointer("/metadata/kind")
+        .and_then(Value::as_str)
+        .ok_or_else(|| TbdexError::Generic("input has no metadata.kind".to_string()))?;
+
+    match kind {
+        "rfq" => check::<Rfq>(&json, output)?.verify().await,
+        "quote" => check::<Quote>(&json, output)?.verify().await,
+        "order" => check::<Order>(&json, output)?.verify().await,
+        "orderinstructions" => check::<OrderInstructions>(&json, output)?.verify().await,
+        "cancel" => check::<Cancel>(&json, output)?.verify().await,
+        "orderstatus" => check::<OrderStatus>(&json, output)?.verify().await,
+        "close" => check::<Close>(&json, output)?.verify().await,
+        "offering" => check::<Offering>(&json, output)?.verify().await,
+        "balance" => check::<Balance>(&json, output)?.verify().await,
+        kind => Err(TbdexError::Generic(format!("unsupported kind {}", kind))),
+    }
+}
+
+/// Parses the JSON, comparing it to the expected output if there is one.
+fn check<T>(json: &str, output: Option<&Value>) -> Result<T>
+where
+    T: FromJson + PartialEq + fmt::Debug,
+{
+    let parsed = T::from_json_string(json)?;
+
+    if let Some(output) = output {
+        let expected = T::deserialize(output)?;
+        if parsed != expected {
+            return Err(TbdexError::Generic(format!(
+                "parsed {:?} but expected {:?}",
+                parsed, expected
+            )));
+        }
+    }
+
+    Ok(parsed)
+}
+
+async fn run_offering_requirements(input: &Value) -> Result<()> {
+    let field = |name: &str| {
+        input
+            .get(name)
+            .ok_or_else(|| TbdexError::Generic(format!("input has no {}", name)))
+            .and_then(input_json)
+    };
+
+    let offering = Offering::from_json_string(&field("offering")?)?;
+    let rfq = Rfq::from_json_string(&field("rfq")?)?;
+
+    rfq.verify_offering_requirements(&offering).await
+}
+
+fn run_private_data(input: &Value) -> Result<()> {
+    let rfq = Rfq::from_json_string(&input_json(input)?)?;
+    rfq.verify_all_private_data()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        json::ToJson,
+        test_util::{exchange_id, signed_close},
+    };
+    use futures::executor::block_on;
+    use serde_json::json;
+    use web5::dids::methods::did_jwk::DidJwk;
+
+    fn write_vector(dir: &Path, name: &str, vector: Value) {
+        fs::write(dir.join(name), vector.to_string()).unwrap();
+    }
+
+    #[test]
+    fn runs_positive_and_negative_vectors() {
+        let dir =
+            std::env::temp_dir().join(format!("tbdex-conformance-{}", crate::ids::new_uuid_v7()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+
+        let close = signed_close(&DidJwk::create(None).unwrap(), &exchange_id(), "done");
+        let mut tampered = close.clone();
+        tampered.data.reason = Some("tampered".to_string());
+
+        write_vector(
+            &dir,
+            "parse-close.json",
+            json!({
+                "description": "valid close",
+                "input": close.to_json_string().unwrap(),
+                "output": close,
+                "error": false,
+            }),
+        );
+        write_vector(
+            &dir.join("nested"),
+            "parse-close-bad-signature.json",
+            json!({
+                "input": tampered.to_json_string().unwrap(),
+                "error": true,
+            }),
+        );
+        write_vector(
+            &dir,
+            "verify-close-wrongly-rejected.json",
+            json!({
+                "input": close.to_json_string().unwrap(),
+                "error": true,
+            }),
+        );
+        write_vector(&dir, "readme.json", json!({"input": {}}));
+
+        let report = block_on(run_directory(&dir)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let outcomes: Vec<(&str, &VectorOutcome)> = report
+            .vectors
+            .iter()
+            .map(|v| (v.name.as_str(), &v.outcome))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    "nested/parse-close-bad-signature.json",
+                    &VectorOutcome::Passed
+                ),
+                ("parse-close.json", &VectorOutcome::Passed),
+                (
+                    "readme.json",
+                    &VectorOutcome::Skipped("unrecognised vector type".to_string())
+                ),
+                (
+                    "verify-close-wrongly-rejected.json",
+                    &VectorOutcome::Failed(
+                        "expected an error but the vector was accepted".to_string()
+                    )
+                ),
+            ],
+            outcomes
+        );
+        assert!(!report.is_success());
+        assert!(report
+            .to_string()
+            .ends_with("2 passed, 1 failed, 1 skipped"));
+    }
+
+    #[test]
+    fn fails_when_the_directory_is_missing() {
+        assert!(block_on(run_directory("does/not/exist")).is_err());
+    }
+}
+
+#[cfg(test)]
+mod tbdex_test_vectors_protocol {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    #[ignore = "verifies signatures, resolving the signers' DIDs over the network"]
+    fn hosted_vectors() {
+        let report = block_on(run_directory(HOSTED_VECTORS_DIR)).unwrap();
+
+        assert!(
+            report.passed().next().is_some(),
+            "no vectors in {}",
+            HOSTED_VECTORS_DIR
+        );
+        assert!(report.is_success(), "{}", report);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{errors::Result, ids};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use rand::{rngs::OsRng, rngs::StdRng, RngCore, SeedableRng};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+/// The number of random bytes in a private data salt.
+const SALT_LENGTH: usize = 16;
+
+/// The source of the current time, used for `createdAt`, `updatedAt` and the timestamp of ids.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The source of the UUIDv7s from which message and resource ids are formed.
+pub trait IdSource: Send + Sync {
+    /// Generates a UUIDv7 for an id created at `now`.
+    fn next_uuid(&self, now: DateTime<Utc>) -> Uuid;
+}
+
+/// The source of the random bytes which salt hashed RFQ private data.
+pub trait SaltSource: Send + Sync {
+    fn fill_salt(&self, salt: &mut [u8]);
+}
+
+/// Reads the system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Always reports the same time.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Generates UUIDv7s which sort after every other UUIDv7 generated by this process.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MonotonicIdSource;
+
+impl IdSource for MonotonicIdSource {
+    fn next_uuid(&self, now: DateTime<Utc>) -> Uuid {
+        ids::new_uuid_v7_at(now)
+    }
+}
+
+/// Generates a reproducible sequence of monotonic UUIDv7s from a seed.
+pub struct SeededIdSource {
+    state: Mutex<((u64, u16), StdRng)>,
+}
+
+impl SeededIdSource {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Mutex::new(((0, 0), StdRng::seed_from_u64(seed))),
+        }
+    }
+}
+
+impl IdSource for SeededIdSource {
+    fn next_uuid(&self, now: DateTime<Utc>) -> Uuid {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (last, rng) = &mut *state;
+        ids::next_uuid_v7(last, now, rng)
+    }
+}
+
+/// Draws salts from the operating system's random number generator.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsSaltSource;
+
+impl SaltSource for OsSaltSource {
+    fn fill_salt(&self, salt: &mut [u8]) {
+        OsRng.fill_bytes(salt);
+    }
+}
+
+/// Draws a reproducible sequence of salts from a seed.
+///
+/// Salts keep hashed private data from being guessed, so this is only suitable for tests and
+/// generating known-answer vectors.
+pub struct SeededSaltSource {
+    rng: Mutex<StdRng>,
+}
+
+impl SeededSaltSource {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl SaltSource for SeededSaltSource {
+    fn fill_salt(&self, salt: &mut [u8]) {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        rng.fill_bytes(salt);
+    }
+}
+
+/// The clock, id source and salt source used when creating messages and resources.
+///
+/// The default reads the system clock, generates process-wide monotonic ids and draws salts from
+/// the operating system. Clones share the same sources.
+#[derive(Clone)]
+pub struct CreationContext {
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdSource>,
+    salts: Arc<dyn SaltSource>,
+}
+
+impl Default for CreationContext {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            ids: Arc::new(MonotonicIdSource),
+            salts: Arc::new(OsSaltSource),
+        }
+    }
+}
+
+impl fmt::Debug for CreationContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreationContext").finish_non_exhaustive()
+    }
+}
+
+impl CreationContext {
+    /// Creates a context from the given sources.
+    pub fn new(
+        clock: impl Clock + 'static,
+        ids: impl IdSource + 'static,
+        salts: impl SaltSource + 'static,
+    ) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ids: Arc::new(ids),
+            salts: Arc::new(salts),
+        }
+    }
+
+    /// Creates a context whose clock is fixed at `now` and whose ids and salts are derived from
+    /// `seed`.
+    ///
+    /// The same sequence of creations from two such contexts with the same arguments yields
+    /// identical messages, and so byte-identical signed messages when signed with the same
+    /// deterministic key (such as Ed25519).
+    pub fn deterministic(seed: u64, now: DateTime<Utc>) -> Self {
+        Self::new(
+            FixedClock(now),
+            SeededIdSource::new(seed),
+            // distinct from the id stream so that salts do not repeat bytes of the ids
+            SeededSaltSource::new(seed.wrapping_add(1)),
+        )
+    }
+
+    /// Replaces the clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Replaces the id source.
+    pub fn with_id_source(mut self, ids: impl IdSource + 'static) -> Self {
+        self.ids = Arc::new(ids);
+        self
+    }
+
+    /// Replaces the salt source.
+    pub fn with_salt_source(mut self, salts: impl SaltSource + 'static) -> Self {
+        self.salts = Arc::new(salts);
+        self
+    }
+
+    /// The current time according to the clock.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Generates a typesafe id, such as `rfq_01hw...`, for something created at `now`.
+    pub(crate) fn typesafe_id(&self, prefix: &str, now: DateTime<Utc>) -> Result<String> {
+        ids::typesafe_id_from_uuid(prefix, self.ids.next_uuid(now))
+    }
+
+    /// Generates a base64url encoded salt for hashing private data.
+    pub(crate) fn salt(&self) -> String {
+        let mut salt = [0u8; SALT_LENGTH];
+        self.salts.fill_salt(&mut salt);
+        general_purpose::URL_SAFE_NO_PAD.encode(salt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 8, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn deterministic_contexts_repeat() {
+        let a = CreationContext::deterministic(7, start());
+        let b = CreationContext::deterministic(7, start());
+
+        for _ in 0..10 {
+            assert_eq!(
+                a.typesafe_id("rfq", a.now()).unwrap(),
+                b.typesafe_id("rfq", b.now()).unwrap()
+            );
+            assert_eq!(a.salt(), b.salt());
+        }
+
+        let c = CreationContext::deterministic(8, start());
+        assert_ne!(a.salt(), c.salt());
+    }
+
+    #[test]
+    fn seeded_ids_are_monotonic_and_carry_the_clock_time() {
+        let context = CreationContext::deterministic(1, start());
+
+        let uuids: Vec<Uuid> = (0..100)
+            .map(|_| context.ids.next_uuid(context.now()))
+            .collect();
+
+        let mut sorted = uuids.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(uuids, sorted);
+
+        assert_eq!(start(), ids::uuid_v7_timestamp(&uuids[0]).unwrap());
+    }
+
+    #[test]
+    fn default_salts_differ() {
+        let context = CreationContext::default();
+        assert_ne!(context.salt(), context.salt());
+    }
+}
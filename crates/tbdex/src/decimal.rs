@@ -0,0 +1,90 @@
+//! Decimal strings as the tbDEX definitions specify them: digits, optionally followed by a dot
+//! and more digits, with no sign or exponent.
+
+use crate::errors::{ParseError, Result, TbdexError};
+
+/// Splits a decimal string into its whole and fractional digits, or `None` if it is not one.
+fn split(value: &str) -> Option<(&str, &str)> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if !is_digits(whole) || (value.contains('.') && !is_digits(fraction)) {
+        return None;
+    }
+    Some((whole, fraction))
+}
+
+/// Parses a decimal string into the nearest `f64`, for comparisons which need not be exact.
+pub(crate) fn to_f64(value: &str) -> Option<f64> {
+    split(value)?;
+    value.parse().ok()
+}
+
+/// Adds two decimal strings exactly.
+pub(crate) fn add(a: &str, b: &str) -> Result<String> {
+    let invalid =
+        |s: &str| TbdexError::Parse(ParseError::new(format!("invalid decimal string {}", s)));
+    let (a_whole, a_fraction) = split(a).ok_or_else(|| invalid(a))?;
+    let (b_whole, b_fraction) = split(b).ok_or_else(|| invalid(b))?;
+
+    let scale = a_fraction.len().max(b_fraction.len());
+    let digits = |whole: &str, fraction: &str| -> Vec<u8> {
+        format!("{}{:0<scale$}", whole, fraction, scale = scale)
+            .bytes()
+            .rev()
+            .map(|b| b - b'0')
+            .collect()
+    };
+    let a_digits = digits(a_whole, a_fraction);
+    let b_digits = digits(b_whole, b_fraction);
+
+    let mut sum = Vec::with_capacity(a_digits.len().max(b_digits.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a_digits.len().max(b_digits.len()) {
+        let d = a_digits.get(i).unwrap_or(&0) + b_digits.get(i).unwrap_or(&0) + carry;
+        sum.push(d % 10);
+        carry = d / 10;
+    }
+    if carry > 0 {
+        sum.push(carry);
+    }
+
+    let sum = sum
+        .iter()
+        .rev()
+        .map(|d| (d + b'0') as char)
+        .collect::<String>();
+    let (whole, fraction) = sum.split_at(sum.len() - scale);
+    let whole = match whole.trim_start_matches('0') {
+        "" => "0",
+        w => w,
+    };
+
+    Ok(match fraction.is_empty() {
+        true => whole.to_string(),
+        false => format!("{}.{}", whole, fraction),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_decimal_strings() {
+        assert_eq!("100.25", add("99.5", "0.75").unwrap());
+        assert_eq!("10", add("9", "1").unwrap());
+        assert_eq!("0.30", add("0.1", "0.20").unwrap());
+        assert!(add("1e3", "1").is_err());
+        assert!(add("-1", "1").is_err());
+        assert!(add(".5", "1").is_err());
+        assert!(add("1.", "1").is_err());
+    }
+
+    #[test]
+    fn parses_decimal_strings() {
+        assert_eq!(Some(1.5), to_f64("1.5"));
+        assert_eq!(None, to_f64("-1"));
+        assert_eq!(None, to_f64("1e3"));
+        assert_eq!(None, to_f64("1."));
+    }
+}
@@ -0,0 +1,134 @@
+//! Content addressing for messages and resources.
+//!
+//! A [`ContentHash`] is the SHA-256 digest of the JCS canonicalized `metadata` and `data` of a
+//! message or resource, the same digest its signature covers. Any change to either changes the
+//! hash, while the signature and private data do not take part, so it identifies the content
+//! itself: for deduplicating, referencing from a ledger, or detecting tampering in storage.
+
+use crate::errors::{Result, TbdexError};
+use base64::{engine::general_purpose, Engine};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::{fmt, str::FromStr};
+
+/// The SHA-256 digest of a message or resource, as returned by their `digest()`.
+///
+/// Displays, parses and serializes as 64 lowercase hex digits.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContentHash([u8; 32]);
+
+impl ContentHash {
+    /// Hashes the canonical JSON returned by [`canonical_json`].
+    pub(crate) fn of(canonical_json: &str) -> Self {
+        Self(Sha256::digest(canonical_json.as_bytes()).into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// The hash as unpadded base64url, the form in which it is the payload of the signature.
+    pub fn to_base64url(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(self.0)
+    }
+}
+
+impl From<[u8; 32]> for ContentHash {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl fmt::Debug for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ContentHash({})", self)
+    }
+}
+
+impl FromStr for ContentHash {
+    type Err = TbdexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || TbdexError::Generic(format!("invalid content hash {}", s));
+        if s.len() != 64 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for ContentHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContentHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// The JCS canonicalization of `{"metadata": ..., "data": ...}`, whose UTF-8 bytes are what is
+/// hashed and signed.
+pub(crate) fn canonical_json(metadata: &Value, data: &Value) -> Result<String> {
+    let mut combined = Map::new();
+    combined.insert("metadata".to_string(), metadata.clone());
+    combined.insert("data".to_string(), data.clone());
+
+    Ok(serde_jcs::to_string(&Value::Object(combined))?)
+}
+
+/// Hashes the JCS canonicalization of any value.
+pub(crate) fn hash_canonical<T: Serialize>(value: &T) -> Result<ContentHash> {
+    Ok(ContentHash::of(&serde_jcs::to_string(value)?))
+}
+
+pub(crate) fn content_hash(metadata: &Value, data: &Value) -> Result<ContentHash> {
+    Ok(ContentHash::of(&canonical_json(metadata, data)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn canonicalizes_before_hashing() {
+        let canonical = canonical_json(&json!({"b": 1, "a": 2}), &json!({"x": "y"})).unwrap();
+        assert_eq!(r#"{"data":{"x":"y"},"metadata":{"a":2,"b":1}}"#, canonical);
+
+        let reordered = content_hash(&json!({"a": 2, "b": 1}), &json!({"x": "y"})).unwrap();
+        assert_eq!(ContentHash::of(&canonical), reordered);
+    }
+
+    #[test]
+    fn round_trips_as_hex() {
+        let hash = ContentHash::of("{}");
+        assert_eq!(
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a",
+            hash.to_string()
+        );
+        assert_eq!(hash, hash.to_string().parse().unwrap());
+
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(hash, serde_json::from_str::<ContentHash>(&json).unwrap());
+
+        assert!("44136fa3".parse::<ContentHash>().is_err());
+        assert!("zz".repeat(32).parse::<ContentHash>().is_err());
+    }
+}
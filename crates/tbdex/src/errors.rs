@@ -1,5 +1,8 @@
+#[cfg(feature = "client")]
 use crate::http::ErrorResponseBody;
+#[cfg(feature = "client")]
 use http_std::Error as HttpStdError;
+use serde::{Deserialize, Serialize};
 use serde_json::Error as SerdeJsonError;
 use type_safe_id::Error as TypeIdError;
 use web5::errors::Web5Error;
@@ -17,20 +20,36 @@ pub enum TbdexError {
     #[error("typeid error {0}")]
     TypeId(String),
     #[error("parse error {0}")]
-    Parse(String),
+    Parse(ParseError),
+    #[error("unknown fields {0}")]
+    UnknownFields(String),
+    #[error("unsupported protocol version {0}")]
+    UnsupportedProtocolVersion(String),
     #[error("offering verification error {0}")]
     OfferingVerification(String),
+    #[error("offering lint error {0}")]
+    OfferingLint(String),
     #[error("private data verification error {0}")]
     PrivateDataVerification(String),
+    #[error("selective disclosure error {0}")]
+    SelectiveDisclosure(String),
+    #[error("order status transition error {0}")]
+    OrderStatusTransition(String),
+    #[error("cancel not permitted {0}")]
+    CancelNotPermitted(String),
+    #[error("invalid payment details {}", describe_fields(.0))]
+    InvalidPaymentDetails(Vec<FieldError>),
     #[error("http error {0}")]
     Http(String),
     #[error("http client error {0}")]
     HttpClient(String),
 
+    #[cfg(feature = "client")]
     #[error(transparent)]
     HttpStdError(#[from] HttpStdError),
     #[error(transparent)]
     Web5Error(#[from] Web5Error),
+    #[cfg(feature = "client")]
     #[error(transparent)]
     ErrorResponseBody(#[from] ErrorResponseBody),
 
@@ -50,4 +69,87 @@ impl From<TypeIdError> for TbdexError {
     }
 }
 
+impl From<ParseError> for TbdexError {
+    fn from(err: ParseError) -> Self {
+        TbdexError::Parse(err)
+    }
+}
+
+/// Why and where a value failed to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// The path to the offending field, e.g. `data.payin.amount` or `data[0].metadata.kind`,
+    /// or `None` if the problem is with the value as a whole.
+    pub path: Option<String>,
+
+    /// What was wrong, e.g. ``missing field `amount` ``.
+    pub reason: String,
+}
+
+impl ParseError {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            path: None,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn at(path: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            path: Some(path.into()),
+            reason: reason.into(),
+        }
+    }
+
+    /// Prefixes the path with that of the enclosing value.
+    pub(crate) fn within(mut self, parent: &str) -> Self {
+        self.path = Some(match self.path {
+            Some(path) if path.starts_with('[') => format!("{}{}", parent, path),
+            Some(path) => format!("{}.{}", parent, path),
+            None => parent.to_string(),
+        });
+        self
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "at {}: {}", path, self.reason),
+            None => write!(f, "{}", self.reason),
+        }
+    }
+}
+
+/// A payment details field which does not satisfy the payment method's `requiredPaymentDetails`,
+/// or which could not be read as the requested type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// The path to the field, e.g. `accountNumber` or `address.postalCode`, or empty if the
+    /// problem is with the payment details as a whole.
+    pub field: String,
+
+    /// What was wrong, e.g. `"12" is not of type "integer"`.
+    pub reason: String,
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.field.as_str() {
+            "" => write!(f, "{}", self.reason),
+            field => write!(f, "{}: {}", field, self.reason),
+        }
+    }
+}
+
+fn describe_fields(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(FieldError::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl std::error::Error for ParseError {}
+
 pub type Result<T> = std::result::Result<T, TbdexError>;
@@ -1,12 +1,20 @@
 use crate::{
-    json::{FromJson, ToJson},
+    errors::ParseError,
+    json::{FromJson, JsonObject, ToJson},
     resources::balance::Balance,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize, Deserialize)]
 pub struct GetBalancesResponseBody {
     pub data: Vec<Balance>,
 }
-impl FromJson for GetBalancesResponseBody {}
+impl FromJson for GetBalancesResponseBody {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        Ok(Self {
+            data: JsonObject::new(value)?.field("data")?,
+        })
+    }
+}
 impl ToJson for GetBalancesResponseBody {}
@@ -1,18 +1,28 @@
 use crate::{
-    json::{FromJson, ToJson},
+    errors::{ParseError, Result},
+    json::{FromJson, JsonObject, ToJson, UnknownFields},
     messages::{
-        cancel::Cancel, close::Close, order::Order, order_instructions::OrderInstructions,
-        order_status::OrderStatus, quote::Quote, rfq::Rfq, Message, MessageKind,
+        cancel::Cancel, close::Close, message_kind, order::Order,
+        order_instructions::OrderInstructions, order_status::OrderStatus, quote::Quote, rfq::Rfq,
+        Message, MessageKind, MessageMetadata, TbdexMessage,
     },
 };
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
-use std::{str::FromStr, sync::Arc};
+use serde_json::Value;
+use std::sync::Arc;
+use web5::dids::bearer_did::BearerDid;
 
 #[derive(Serialize, Deserialize)]
 pub struct GetExchangeResponseBody {
     pub data: Vec<Message>,
 }
-impl FromJson for GetExchangeResponseBody {}
+impl FromJson for GetExchangeResponseBody {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        Ok(Self {
+            data: JsonObject::new(value)?.field("data")?,
+        })
+    }
+}
 impl ToJson for GetExchangeResponseBody {}
 
 #[derive(Serialize, Deserialize)]
@@ -29,7 +39,15 @@ pub struct CreateExchangeRequestBody {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_to: Option<String>,
 }
-impl FromJson for CreateExchangeRequestBody {}
+impl FromJson for CreateExchangeRequestBody {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            message: object.field("message")?,
+            reply_to: object.deserialize("replyTo")?,
+        })
+    }
+}
 impl ToJson for CreateExchangeRequestBody {}
 
 #[derive(Serialize, Debug, PartialEq)]
@@ -38,9 +56,70 @@ pub enum WalletUpdateMessage {
     Order(Arc<Order>),
     Cancel(Arc<Cancel>),
 }
-impl FromJson for WalletUpdateMessage {}
+impl FromJson for WalletUpdateMessage {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        match message_kind(value)? {
+            MessageKind::Order => Ok(WalletUpdateMessage::Order(Arc::new(
+                Order::from_json_value(value)?,
+            ))),
+            MessageKind::Cancel => Ok(WalletUpdateMessage::Cancel(Arc::new(
+                Cancel::from_json_value(value)?,
+            ))),
+            kind => Err(ParseError::at(
+                "metadata.kind",
+                format!("expected order or cancel, found {}", kind),
+            )),
+        }
+    }
+}
 impl ToJson for WalletUpdateMessage {}
 
+impl UnknownFields for WalletUpdateMessage {
+    fn unknown_fields(&self) -> &[String] {
+        match self {
+            WalletUpdateMessage::Order(order) => order.unknown_fields(),
+            WalletUpdateMessage::Cancel(cancel) => cancel.unknown_fields(),
+        }
+    }
+}
+
+impl TbdexMessage for WalletUpdateMessage {
+    fn metadata(&self) -> &MessageMetadata {
+        match self {
+            WalletUpdateMessage::Order(order) => &order.metadata,
+            WalletUpdateMessage::Cancel(cancel) => &cancel.metadata,
+        }
+    }
+
+    fn metadata_json(&self) -> Result<serde_json::Value> {
+        match self {
+            WalletUpdateMessage::Order(order) => order.metadata_json(),
+            WalletUpdateMessage::Cancel(cancel) => cancel.metadata_json(),
+        }
+    }
+
+    fn data_json(&self) -> Result<serde_json::Value> {
+        match self {
+            WalletUpdateMessage::Order(order) => order.data_json(),
+            WalletUpdateMessage::Cancel(cancel) => cancel.data_json(),
+        }
+    }
+
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        match self {
+            WalletUpdateMessage::Order(order) => Arc::make_mut(order).sign(bearer_did),
+            WalletUpdateMessage::Cancel(cancel) => Arc::make_mut(cancel).sign(bearer_did),
+        }
+    }
+
+    async fn verify(&self) -> Result<()> {
+        match self {
+            WalletUpdateMessage::Order(order) => order.verify().await,
+            WalletUpdateMessage::Cancel(cancel) => cancel.verify().await,
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for WalletUpdateMessage {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
@@ -61,44 +140,7 @@ impl<'de> Deserialize<'de> for WalletUpdateMessage {
             {
                 let value: serde_json::Value = Deserialize::deserialize(deserializer)?;
 
-                let kind_str = value
-                    .get("metadata")
-                    .and_then(|m| m.get("kind"))
-                    .and_then(|k| k.as_str());
-
-                match kind_str {
-                    Some(k) => match MessageKind::from_str(k) {
-                        Ok(kind) => match kind {
-                            MessageKind::Order => {
-                                if let Ok(order) = serde_json::from_value::<Order>(value.clone()) {
-                                    Ok(WalletUpdateMessage::Order(Arc::new(order)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize order"))
-                                }
-                            }
-                            MessageKind::Cancel => {
-                                if let Ok(cancel) = serde_json::from_value::<Cancel>(value.clone())
-                                {
-                                    Ok(WalletUpdateMessage::Cancel(Arc::new(cancel)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize cancel"))
-                                }
-                            }
-                            _ => Err(serde::de::Error::custom(format!(
-                                "unexpected message kind {:?}",
-                                kind
-                            ))),
-                        },
-                        Err(_) => Err(serde::de::Error::custom(format!(
-                            "unexpected message kind {}",
-                            k
-                        ))),
-                    },
-                    None => Err(serde::de::Error::custom(format!(
-                        "unexpected message kind {:?}",
-                        kind_str
-                    ))),
-                }
+                WalletUpdateMessage::from_json_value(&value).map_err(serde::de::Error::custom)
             }
 
             fn visit_none<E>(self) -> std::result::Result<Self::Value, E>
@@ -117,7 +159,13 @@ impl<'de> Deserialize<'de> for WalletUpdateMessage {
 pub struct UpdateExchangeRequestBody {
     pub message: WalletUpdateMessage,
 }
-impl FromJson for UpdateExchangeRequestBody {}
+impl FromJson for UpdateExchangeRequestBody {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        Ok(Self {
+            message: JsonObject::new(value)?.field("message")?,
+        })
+    }
+}
 impl ToJson for UpdateExchangeRequestBody {}
 
 #[derive(Serialize, Debug, PartialEq)]
@@ -128,9 +176,101 @@ pub enum ReplyToMessage {
     OrderInstructions(Arc<OrderInstructions>),
     Close(Arc<Close>),
 }
-impl FromJson for ReplyToMessage {}
+impl FromJson for ReplyToMessage {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        match message_kind(value)? {
+            MessageKind::Quote => Ok(ReplyToMessage::Quote(Arc::new(Quote::from_json_value(
+                value,
+            )?))),
+            MessageKind::OrderStatus => Ok(ReplyToMessage::OrderStatus(Arc::new(
+                OrderStatus::from_json_value(value)?,
+            ))),
+            MessageKind::OrderInstructions => Ok(ReplyToMessage::OrderInstructions(Arc::new(
+                OrderInstructions::from_json_value(value)?,
+            ))),
+            MessageKind::Close => Ok(ReplyToMessage::Close(Arc::new(Close::from_json_value(
+                value,
+            )?))),
+            kind => Err(ParseError::at(
+                "metadata.kind",
+                format!(
+                    "expected quote, orderstatus, orderinstructions or close, found {}",
+                    kind
+                ),
+            )),
+        }
+    }
+}
 impl ToJson for ReplyToMessage {}
 
+impl UnknownFields for ReplyToMessage {
+    fn unknown_fields(&self) -> &[String] {
+        match self {
+            ReplyToMessage::Quote(quote) => quote.unknown_fields(),
+            ReplyToMessage::OrderStatus(order_status) => order_status.unknown_fields(),
+            ReplyToMessage::OrderInstructions(order_instructions) => {
+                order_instructions.unknown_fields()
+            }
+            ReplyToMessage::Close(close) => close.unknown_fields(),
+        }
+    }
+}
+
+impl TbdexMessage for ReplyToMessage {
+    fn metadata(&self) -> &MessageMetadata {
+        match self {
+            ReplyToMessage::Quote(quote) => &quote.metadata,
+            ReplyToMessage::OrderStatus(order_status) => &order_status.metadata,
+            ReplyToMessage::OrderInstructions(order_instructions) => &order_instructions.metadata,
+            ReplyToMessage::Close(close) => &close.metadata,
+        }
+    }
+
+    fn metadata_json(&self) -> Result<serde_json::Value> {
+        match self {
+            ReplyToMessage::Quote(quote) => quote.metadata_json(),
+            ReplyToMessage::OrderStatus(order_status) => order_status.metadata_json(),
+            ReplyToMessage::OrderInstructions(order_instructions) => {
+                order_instructions.metadata_json()
+            }
+            ReplyToMessage::Close(close) => close.metadata_json(),
+        }
+    }
+
+    fn data_json(&self) -> Result<serde_json::Value> {
+        match self {
+            ReplyToMessage::Quote(quote) => quote.data_json(),
+            ReplyToMessage::OrderStatus(order_status) => order_status.data_json(),
+            ReplyToMessage::OrderInstructions(order_instructions) => order_instructions.data_json(),
+            ReplyToMessage::Close(close) => close.data_json(),
+        }
+    }
+
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        match self {
+            ReplyToMessage::Quote(quote) => Arc::make_mut(quote).sign(bearer_did),
+            ReplyToMessage::OrderStatus(order_status) => {
+                Arc::make_mut(order_status).sign(bearer_did)
+            }
+            ReplyToMessage::OrderInstructions(order_instructions) => {
+                Arc::make_mut(order_instructions).sign(bearer_did)
+            }
+            ReplyToMessage::Close(close) => Arc::make_mut(close).sign(bearer_did),
+        }
+    }
+
+    async fn verify(&self) -> Result<()> {
+        match self {
+            ReplyToMessage::Quote(quote) => quote.verify().await,
+            ReplyToMessage::OrderStatus(order_status) => order_status.verify().await,
+            ReplyToMessage::OrderInstructions(order_instructions) => {
+                order_instructions.verify().await
+            }
+            ReplyToMessage::Close(close) => close.verify().await,
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for ReplyToMessage {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
@@ -151,67 +291,7 @@ impl<'de> Deserialize<'de> for ReplyToMessage {
             {
                 let value: serde_json::Value = Deserialize::deserialize(deserializer)?;
 
-                let kind_str = value
-                    .get("metadata")
-                    .and_then(|m| m.get("kind"))
-                    .and_then(|k| k.as_str());
-
-                match kind_str {
-                    Some(k) => match MessageKind::from_str(k) {
-                        Ok(kind) => match kind {
-                            MessageKind::Quote => {
-                                if let Ok(quote) = serde_json::from_value::<Quote>(value.clone()) {
-                                    Ok(ReplyToMessage::Quote(Arc::new(quote)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize quote"))
-                                }
-                            }
-                            MessageKind::OrderStatus => {
-                                if let Ok(order_status) =
-                                    serde_json::from_value::<OrderStatus>(value.clone())
-                                {
-                                    Ok(ReplyToMessage::OrderStatus(Arc::new(order_status)))
-                                } else {
-                                    Err(serde::de::Error::custom(
-                                        "failed to deserialize order_status",
-                                    ))
-                                }
-                            }
-                            MessageKind::OrderInstructions => {
-                                if let Ok(order_instructions) =
-                                    serde_json::from_value::<OrderInstructions>(value.clone())
-                                {
-                                    Ok(ReplyToMessage::OrderInstructions(Arc::new(
-                                        order_instructions,
-                                    )))
-                                } else {
-                                    Err(serde::de::Error::custom(
-                                        "failed to deserialize order_instructions",
-                                    ))
-                                }
-                            }
-                            MessageKind::Close => {
-                                if let Ok(close) = serde_json::from_value::<Close>(value.clone()) {
-                                    Ok(ReplyToMessage::Close(Arc::new(close)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize close"))
-                                }
-                            }
-                            _ => Err(serde::de::Error::custom(format!(
-                                "unexpected message kind {:?}",
-                                kind
-                            ))),
-                        },
-                        Err(_) => Err(serde::de::Error::custom(format!(
-                            "unexpected message kind {}",
-                            k
-                        ))),
-                    },
-                    None => Err(serde::de::Error::custom(format!(
-                        "unexpected message kind {:?}",
-                        kind_str
-                    ))),
-                }
+                ReplyToMessage::from_json_value(&value).map_err(serde::de::Error::custom)
             }
 
             fn visit_none<E>(self) -> std::result::Result<Self::Value, E>
@@ -230,7 +310,13 @@ impl<'de> Deserialize<'de> for ReplyToMessage {
 pub struct ReplyToRequestBody {
     pub message: ReplyToMessage,
 }
-impl FromJson for ReplyToRequestBody {}
+impl FromJson for ReplyToRequestBody {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        Ok(Self {
+            message: JsonObject::new(value)?.field("message")?,
+        })
+    }
+}
 impl ToJson for ReplyToRequestBody {}
 
 #[cfg(test)]
@@ -245,6 +331,31 @@ mod tests {
         pub output: T,
     }
 
+    #[test]
+    fn rejects_wrong_kind_of_wallet_update() {
+        let close = Close::create(
+            "did:test:pfi",
+            "did:test:alice",
+            &MessageKind::Rfq.typesafe_id().unwrap(),
+            &crate::messages::close::CloseData {
+                reason: None,
+                success: None,
+            },
+            None,
+            None,
+        )
+        .unwrap();
+        let json = format!(r#"{{"message":{}}}"#, close.to_json_string().unwrap());
+
+        match UpdateExchangeRequestBody::from_json_string(&json) {
+            Err(crate::errors::TbdexError::Parse(e)) => {
+                assert_eq!(Some("message.metadata.kind".to_string()), e.path);
+                assert_eq!("expected order or cancel, found close", e.reason);
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
     #[test]
     fn order_update_exchange_request_body() {
         let path = "../../tbdex/hosted/test-vectors/protocol/vectors/parse-order.json";
@@ -1,12 +1,20 @@
 use crate::{
-    json::{FromJson, ToJson},
+    errors::ParseError,
+    json::{FromJson, JsonObject, ToJson},
     resources::offering::Offering,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize, Deserialize)]
 pub struct GetOfferingsResponseBody {
     pub data: Vec<Offering>,
 }
-impl FromJson for GetOfferingsResponseBody {}
+impl FromJson for GetOfferingsResponseBody {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        Ok(Self {
+            data: JsonObject::new(value)?.field("data")?,
+        })
+    }
+}
 impl ToJson for GetOfferingsResponseBody {}
@@ -1,18 +1,22 @@
 use super::{generate_access_token, get_json, get_service_endpoint, Result};
-use crate::{http::balances::GetBalancesResponseBody, resources::balance::Balance};
+use crate::{http::balances::GetBalancesResponseBody, metrics, resources::balance::Balance};
 use web5::dids::bearer_did::BearerDid;
 
+#[tracing::instrument(level = "debug", skip_all, fields(pfi_did = %pfi_did_uri))]
 pub async fn get_balances(pfi_did_uri: &str, bearer_did: &BearerDid) -> Result<Vec<Balance>> {
-    let service_endpoint = get_service_endpoint(pfi_did_uri).await?;
-    let balances_endpoint = format!("{}/balances", service_endpoint);
+    metrics::observe("get_balances", pfi_did_uri, async {
+        let service_endpoint = get_service_endpoint(pfi_did_uri).await?;
+        let balances_endpoint = format!("{}/balances", service_endpoint);
 
-    let access_token = generate_access_token(pfi_did_uri, bearer_did)?;
-    let get_balances_response_body =
-        get_json::<GetBalancesResponseBody>(&balances_endpoint, Some(access_token)).await?;
+        let access_token = generate_access_token(pfi_did_uri, bearer_did)?;
+        let get_balances_response_body =
+            get_json::<GetBalancesResponseBody>(&balances_endpoint, Some(access_token)).await?;
 
-    for balance in &get_balances_response_body.data {
-        balance.verify().await?;
-    }
+        for balance in &get_balances_response_body.data {
+            balance.verify().await?;
+        }
 
-    Ok(get_balances_response_body.data)
+        Ok(get_balances_response_body.data)
+    })
+    .await
 }
@@ -0,0 +1,477 @@
+//! A local copy of exchanges, kept up to date incrementally.
+//!
+//! An [`ExchangeCache`] keeps the verified messages of each exchange in an [`ExchangeStore`], so
+//! that their history can be read offline. [`ExchangeCache::sync`] fetches an exchange from its
+//! PFI and [`ExchangeCache::merge`]s the response: only messages not seen before are verified and
+//! stored, and the cache's [`ExchangeListener`]s are told about them.
+
+use super::{exchanges::get_exchange_messages, exchanges::Exchange, Result};
+use crate::{
+    errors::TbdexError,
+    json::{FromJson, ToJson},
+    messages::{Message, TbdexMessage},
+    metrics,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+};
+use web5::dids::bearer_did::BearerDid;
+
+/// Where an [`ExchangeCache`] keeps the messages of each exchange, in the order they were added.
+pub trait ExchangeStore: Send + Sync {
+    /// The messages of the exchange, or none if it is unknown.
+    fn load(&self, exchange_id: &str) -> Result<Vec<Message>>;
+
+    /// Adds the messages to the end of the exchange.
+    fn append(&self, exchange_id: &str, messages: &[Message]) -> Result<()>;
+
+    /// The ids of the exchanges with messages.
+    fn exchange_ids(&self) -> Result<Vec<String>>;
+}
+
+/// Keeps exchanges in memory, for as long as the store lives.
+#[derive(Default)]
+pub struct MemoryStore {
+    exchanges: RwLock<HashMap<String, Vec<Message>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ExchangeStore for MemoryStore {
+    fn load(&self, exchange_id: &str) -> Result<Vec<Message>> {
+        Ok(self
+            .exchanges
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(exchange_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn append(&self, exchange_id: &str, messages: &[Message]) -> Result<()> {
+        self.exchanges
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(exchange_id.to_string())
+            .or_default()
+            .extend_from_slice(messages);
+        Ok(())
+    }
+
+    fn exchange_ids(&self) -> Result<Vec<String>> {
+        Ok(self
+            .exchanges
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect())
+    }
+}
+
+/// Keeps each exchange in a file of the directory, `<exchange_id>.jsonl`, one message per line.
+///
+/// RFQs are stored with their private data, so the directory should be as protected as any other
+/// store of the user's payment details.
+pub struct DirectoryStore {
+    directory: PathBuf,
+}
+
+impl DirectoryStore {
+    /// Stores exchanges in the directory, creating it if need be.
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory).map_err(|e| {
+            TbdexError::Generic(format!("failed to create {}: {}", directory.display(), e))
+        })?;
+        Ok(Self { directory })
+    }
+
+    fn path(&self, exchange_id: &str) -> Result<PathBuf> {
+        // exchange ids are typesafe ids, which never need escaping
+        if exchange_id.is_empty()
+            || !exchange_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(TbdexError::Generic(format!(
+                "invalid exchange id {}",
+                exchange_id
+            )));
+        }
+        Ok(self.directory.join(format!("{}.jsonl", exchange_id)))
+    }
+}
+
+impl ExchangeStore for DirectoryStore {
+    fn load(&self, exchange_id: &str) -> Result<Vec<Message>> {
+        let path = self.path(exchange_id)?;
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => {
+                return Err(TbdexError::Generic(format!(
+                    "failed to read {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(Message::from_json_string)
+            .collect()
+    }
+
+    fn append(&self, exchange_id: &str, messages: &[Message]) -> Result<()> {
+        let path = self.path(exchange_id)?;
+
+        let mut lines = String::new();
+        for message in messages {
+            lines.push_str(&message.to_json_string()?);
+            lines.push('\n');
+        }
+
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .map_err(|e| TbdexError::Generic(format!("failed to write {}: {}", path.display(), e)))
+    }
+
+    fn exchange_ids(&self) -> Result<Vec<String>> {
+        let entries = fs::read_dir(&self.directory).map_err(|e| {
+            TbdexError::Generic(format!(
+                "failed to read {}: {}",
+                self.directory.display(),
+                e
+            ))
+        })?;
+
+        Ok(entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(".jsonl"))
+                    .map(str::to_string)
+            })
+            .collect())
+    }
+}
+
+/// Messages newly added to an exchange in the cache.
+#[derive(Debug)]
+pub struct ExchangeChange<'a> {
+    pub exchange_id: &'a str,
+    /// The added messages, in order. The last is now the exchange's latest message.
+    pub added: &'a [Message],
+}
+
+/// Told about every change to the exchanges in an [`ExchangeCache`].
+pub trait ExchangeListener: Send + Sync {
+    fn exchange_changed(&self, change: &ExchangeChange);
+}
+
+/// A local, verified copy of exchanges, backed by an [`ExchangeStore`].
+pub struct ExchangeCache {
+    store: Box<dyn ExchangeStore>,
+    listeners: RwLock<Vec<Arc<dyn ExchangeListener>>>,
+    // serializes appends, so that concurrent merges do not store a message twice
+    append_lock: Mutex<()>,
+}
+
+impl ExchangeCache {
+    pub fn new(store: impl ExchangeStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+            listeners: RwLock::new(vec![]),
+            append_lock: Mutex::new(()),
+        }
+    }
+
+    /// Tells the listener about every change from now on.
+    pub fn add_listener(&self, listener: Arc<dyn ExchangeListener>) {
+        self.listeners
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(listener);
+    }
+
+    /// The cached messages of the exchange, in the order they were added.
+    pub fn messages(&self, exchange_id: &str) -> Result<Vec<Message>> {
+        self.store.load(exchange_id)
+    }
+
+    /// The cached exchange, or `None` if none of its messages are cached.
+    pub fn exchange(&self, exchange_id: &str) -> Result<Option<Exchange>> {
+        let messages = self.store.load(exchange_id)?;
+        if messages.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Exchange::from_messages(messages)))
+    }
+
+    /// The last message added to the exchange.
+    pub fn latest(&self, exchange_id: &str) -> Result<Option<Message>> {
+        Ok(self.store.load(exchange_id)?.pop())
+    }
+
+    pub fn exchange_ids(&self) -> Result<Vec<String>> {
+        self.store.exchange_ids()
+    }
+
+    /// Adds the messages of the exchange which are not cached yet, after verifying each of them,
+    /// and returns them.
+    ///
+    /// The messages may repeat the cached ones, as a PFI's response does. Nothing is added if
+    /// any new message fails to verify, belongs to another exchange, or if a message differs
+    /// from the cached message with the same id.
+    pub async fn merge(&self, exchange_id: &str, messages: Vec<Message>) -> Result<Vec<Message>> {
+        let added = self.additions(exchange_id, messages)?;
+        for message in &added {
+            message.verify().await?;
+        }
+
+        let added = {
+            let _guard = self.append_lock.lock().unwrap_or_else(|e| e.into_inner());
+            // another merge may have added some of them while verifying
+            let added = self.additions(exchange_id, added)?;
+            if !added.is_empty() {
+                self.store.append(exchange_id, &added)?;
+            }
+            added
+        };
+
+        if !added.is_empty() {
+            tracing::debug!(
+                %exchange_id,
+                added = added.len(),
+                "merged new messages into exchange cache"
+            );
+            let change = ExchangeChange {
+                exchange_id,
+                added: &added,
+            };
+            let listeners = self
+                .listeners
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone();
+            for listener in listeners {
+                listener.exchange_changed(&change);
+            }
+        }
+
+        Ok(added)
+    }
+
+    /// Fetches the exchange from the PFI and merges its new messages. See
+    /// [`ExchangeCache::merge`].
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(pfi_did = %pfi_did_uri, exchange_id = %exchange_id)
+    )]
+    pub async fn sync(
+        &self,
+        pfi_did_uri: &str,
+        bearer_did: &BearerDid,
+        exchange_id: &str,
+    ) -> Result<Vec<Message>> {
+        metrics::observe("sync_exchange", pfi_did_uri, async {
+            let messages = get_exchange_messages(pfi_did_uri, bearer_did, exchange_id).await?;
+            self.merge(exchange_id, messages).await
+        })
+        .await
+    }
+
+    /// The messages not cached yet, checking that the rest match the cached ones.
+    fn additions(&self, exchange_id: &str, messages: Vec<Message>) -> Result<Vec<Message>> {
+        let mut cached = HashMap::new();
+        for message in self.store.load(exchange_id)? {
+            cached.insert(message.metadata().id.clone(), message.digest()?);
+        }
+
+        let mut added = vec![];
+        let mut seen = HashSet::new();
+        for message in messages {
+            let metadata = message.metadata();
+            if metadata.exchange_id != exchange_id {
+                return Err(TbdexError::Generic(format!(
+                    "message {} belongs to exchange {}, not {}",
+                    metadata.id, metadata.exchange_id, exchange_id
+                )));
+            }
+
+            match cached.get(&metadata.id) {
+                Some(digest) if *digest != message.digest()? => {
+                    return Err(TbdexError::Generic(format!(
+                        "message {} differs from the cached message with the same id",
+                        metadata.id
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    if seen.insert(metadata.id.clone()) {
+                        added.push(message);
+                    }
+                }
+            }
+        }
+
+        Ok(added)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        messages::{
+            order_status::{OrderStatus, OrderStatusData, Status},
+            MessageKind,
+        },
+        test_util::signed_close,
+    };
+    use futures::executor::block_on;
+    use web5::dids::methods::did_jwk::DidJwk;
+
+    fn order_status(pfi: &BearerDid, exchange_id: &str, status: Status) -> Message {
+        let mut order_status = OrderStatus::create(
+            "did:test:wallet",
+            &pfi.did.uri,
+            exchange_id,
+            &OrderStatusData {
+                status,
+                details: None,
+            },
+            None,
+            None,
+        )
+        .unwrap();
+        order_status.sign(pfi).unwrap();
+        Message::OrderStatus(Arc::new(order_status))
+    }
+
+    #[derive(Default)]
+    struct RecordingListener {
+        changes: Mutex<Vec<(String, usize)>>,
+    }
+
+    impl ExchangeListener for RecordingListener {
+        fn exchange_changed(&self, change: &ExchangeChange) {
+            self.changes
+                .lock()
+                .unwrap()
+                .push((change.exchange_id.to_string(), change.added.len()));
+        }
+    }
+
+    #[test]
+    fn merges_only_new_messages() {
+        let pfi = DidJwk::create(None).unwrap();
+        let exchange_id = MessageKind::Rfq.typesafe_id().unwrap();
+        let cache = ExchangeCache::new(MemoryStore::new());
+        let listener = Arc::new(RecordingListener::default());
+        cache.add_listener(listener.clone());
+
+        let first = order_status(&pfi, &exchange_id, Status::PayinPending);
+        let second = order_status(&pfi, &exchange_id, Status::PayinSettled);
+
+        let added = block_on(cache.merge(&exchange_id, vec![first.clone()])).unwrap();
+        assert_eq!(1, added.len());
+
+        let added =
+            block_on(cache.merge(&exchange_id, vec![first.clone(), second.clone()])).unwrap();
+        assert_eq!(1, added.len());
+        assert_eq!(second.metadata().id, added[0].metadata().id);
+
+        let added = block_on(cache.merge(&exchange_id, vec![first, second.clone()])).unwrap();
+        assert!(added.is_empty());
+
+        assert_eq!(2, cache.messages(&exchange_id).unwrap().len());
+        assert_eq!(
+            second.metadata().id,
+            cache.latest(&exchange_id).unwrap().unwrap().metadata().id
+        );
+        let exchange = cache.exchange(&exchange_id).unwrap().unwrap();
+        assert_eq!(2, exchange.order_statuses.unwrap().len());
+        assert_eq!(vec![exchange_id.clone()], cache.exchange_ids().unwrap());
+        assert_eq!(
+            vec![(exchange_id.clone(), 1), (exchange_id, 1)],
+            *listener.changes.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_unverifiable_and_changed_messages() {
+        let pfi = DidJwk::create(None).unwrap();
+        let exchange_id = MessageKind::Rfq.typesafe_id().unwrap();
+        let cache = ExchangeCache::new(MemoryStore::new());
+
+        let mut tampered = signed_close(&pfi, &exchange_id, "done");
+        tampered.data.reason = Some("tampered".to_string());
+        let pending = order_status(&pfi, &exchange_id, Status::PayinPending);
+        assert!(block_on(cache.merge(
+            &exchange_id,
+            vec![pending, Message::Close(Arc::new(tampered))]
+        ))
+        .is_err());
+        assert!(cache.exchange(&exchange_id).unwrap().is_none());
+
+        let original = signed_close(&pfi, &exchange_id, "done");
+        let mut resigned = original.clone();
+        resigned.data.reason = Some("changed".to_string());
+        resigned.sign(&pfi).unwrap();
+        block_on(cache.merge(&exchange_id, vec![Message::Close(Arc::new(original))])).unwrap();
+        assert!(
+            block_on(cache.merge(&exchange_id, vec![Message::Close(Arc::new(resigned))])).is_err()
+        );
+
+        let other_exchange_id = MessageKind::Rfq.typesafe_id().unwrap();
+        let other = order_status(&pfi, &other_exchange_id, Status::PayinPending);
+        assert!(block_on(cache.merge(&exchange_id, vec![other])).is_err());
+        assert_eq!(1, cache.messages(&exchange_id).unwrap().len());
+    }
+
+    #[test]
+    fn reads_back_from_a_directory() {
+        let pfi = DidJwk::create(None).unwrap();
+        let exchange_id = MessageKind::Rfq.typesafe_id().unwrap();
+        let directory = std::env::temp_dir().join(format!("tbdex-exchange-cache-{}", exchange_id));
+
+        {
+            let cache = ExchangeCache::new(DirectoryStore::new(&directory).unwrap());
+            let messages = vec![
+                order_status(&pfi, &exchange_id, Status::PayinPending),
+                Message::Close(Arc::new(signed_close(&pfi, &exchange_id, "done"))),
+            ];
+            block_on(cache.merge(&exchange_id, messages)).unwrap();
+        }
+
+        let cache = ExchangeCache::new(DirectoryStore::new(&directory).unwrap());
+        let exchange = cache.exchange(&exchange_id).unwrap().unwrap();
+        assert_eq!(1, exchange.order_statuses.unwrap().len());
+        assert_eq!(
+            Some("done".to_string()),
+            exchange.close.unwrap().data.reason
+        );
+        assert_eq!(vec![exchange_id.clone()], cache.exchange_ids().unwrap());
+        assert!(cache.messages("../escape").is_err());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
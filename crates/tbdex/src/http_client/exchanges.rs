@@ -2,19 +2,29 @@ use std::sync::Arc;
 
 use super::{add_pagination, get_json, get_service_endpoint, post_json, put_json, Result};
 use crate::http::exchanges::GetExchangesResponseBody;
-use crate::json::{FromJson, ToJson};
+use crate::json::{FromJson, JsonObject, ToJson};
 use crate::{
+    errors::ParseError,
     http::exchanges::{
         CreateExchangeRequestBody, GetExchangeResponseBody, UpdateExchangeRequestBody,
         WalletUpdateMessage,
     },
     http_client::generate_access_token,
     messages::{
-        cancel::Cancel, close::Close, order::Order, order_instructions::OrderInstructions,
-        order_status::OrderStatus, quote::Quote, rfq::Rfq, Message,
+        cancel::{cancel_eligibility, Cancel, CancelEligibility},
+        close::Close,
+        order::Order,
+        order_instructions::OrderInstructions,
+        order_status::{validate_transitions, OrderStatus, Status},
+        quote::Quote,
+        rfq::Rfq,
+        Message,
     },
+    metrics,
+    resources::offering::Offering,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use web5::dids::bearer_did::BearerDid;
 
 #[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq)]
@@ -35,72 +45,280 @@ pub struct Exchange {
     pub close: Option<Arc<Close>>,
 }
 
-impl FromJson for Exchange {}
+impl FromJson for Exchange {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            rfq: object.field("rfq")?,
+            quote: object.field("quote")?,
+            order: object.field("order")?,
+            order_instructions: object.field("orderInstructions")?,
+            cancel: object.field("cancel")?,
+            order_statuses: object.field("orderStatuses")?,
+            close: object.field("close")?,
+        })
+    }
+}
 impl ToJson for Exchange {}
 
-pub async fn create_exchange(rfq: &Rfq, reply_to: Option<String>) -> Result<()> {
-    let service_endpoint = get_service_endpoint(&rfq.metadata.to).await?;
-    let create_exchange_endpoint = format!("{}/exchanges", service_endpoint);
+impl Exchange {
+    /// Assembles the exchange from its messages, a later message of a kind replacing an earlier
+    /// one, except that OrderStatuses accumulate in order.
+    pub fn from_messages(messages: impl IntoIterator<Item = Message>) -> Self {
+        let mut exchange = Exchange::default();
+
+        for message in messages {
+            match message {
+                Message::Rfq(rfq) => {
+                    exchange.rfq = rfq;
+                }
+                Message::Quote(quote) => {
+                    exchange.quote = Some(quote);
+                }
+                Message::Order(order) => {
+                    exchange.order = Some(order);
+                }
+                Message::OrderInstructions(order_instructions) => {
+                    exchange.order_instructions = Some(order_instructions);
+                }
+                Message::Cancel(cancel) => {
+                    exchange.cancel = Some(cancel);
+                }
+                Message::OrderStatus(order_status) => {
+                    if let Some(order_statuses) = &mut exchange.order_statuses {
+                        order_statuses.push(order_status);
+                    } else {
+                        exchange.order_statuses = Some(vec![order_status]);
+                    }
+                }
+                Message::Close(close) => {
+                    exchange.close = Some(close);
+                }
+            }
+        }
+
+        exchange
+    }
+
+    /// The messages of the exchange, in the order of the protocol. A Cancel is placed after the
+    /// OrderStatuses, since when it arrived among them is not kept.
+    pub fn messages(&self) -> Vec<Message> {
+        let mut messages = vec![Message::Rfq(self.rfq.clone())];
+        messages.extend(self.quote.clone().map(Message::Quote));
+        messages.extend(self.order.clone().map(Message::Order));
+        messages.extend(
+            self.order_instructions
+                .clone()
+                .map(Message::OrderInstructions),
+        );
+        messages.extend(
+            self.order_statuses
+                .iter()
+                .flatten()
+                .cloned()
+                .map(Message::OrderStatus),
+        );
+        messages.extend(self.cancel.clone().map(Message::Cancel));
+        messages.extend(self.close.clone().map(Message::Close));
+        messages
+    }
+
+    /// Whether Alice may cancel the exchange under the offering's cancellation policy. See
+    /// [`cancel_eligibility`].
+    pub fn cancel_eligibility(&self, offering: &Offering) -> CancelEligibility {
+        cancel_eligibility(offering, &self.messages())
+    }
+
+    /// The status of the latest OrderStatus, if any.
+    pub fn latest_order_status(&self) -> Option<&Status> {
+        self.order_statuses
+            .as_ref()
+            .and_then(|order_statuses| order_statuses.last())
+            .map(|order_status| &order_status.data.status)
+    }
+
+    /// Checks that the OrderStatuses follow the order lifecycle. See
+    /// [`Status::can_transition_to`].
+    pub fn validate_order_statuses(&self) -> crate::errors::Result<()> {
+        validate_transitions(
+            self.order_statuses
+                .iter()
+                .flatten()
+                .map(|order_status| &order_status.data.status),
+        )
+    }
+
+    /// The state of the exchange as a whole, from its latest OrderStatus, Cancel and Close.
+    pub fn current_status(&self) -> ExchangeStatus {
+        let latest = self.latest_order_status();
 
-    rfq.verify().await?;
+        if let Some(close) = &self.close {
+            return match latest {
+                Some(Status::PayoutSettled) => ExchangeStatus::Completed,
+                Some(Status::RefundSettled) => ExchangeStatus::Refunded,
+                _ if self.cancel.is_some() => ExchangeStatus::Cancelled,
+                _ if close.data.success == Some(true) => ExchangeStatus::Completed,
+                _ => ExchangeStatus::Failed {
+                    reason: close.data.reason.clone(),
+                },
+            };
+        }
 
-    post_json(
-        &create_exchange_endpoint,
-        &CreateExchangeRequestBody {
-            message: rfq.clone(),
-            reply_to,
-        },
+        match latest {
+            Some(Status::PayoutSettled) => ExchangeStatus::Completed,
+            Some(Status::RefundSettled) => ExchangeStatus::Refunded,
+            Some(status) if status.is_terminal() => ExchangeStatus::Failed { reason: None },
+            _ if self.cancel.is_some() => ExchangeStatus::Cancelling,
+            Some(status) => ExchangeStatus::InProgress(status.clone()),
+            None if self.order.is_some() => ExchangeStatus::Ordered,
+            None if self.quote.is_some() => ExchangeStatus::Quoted,
+            None => ExchangeStatus::AwaitingQuote,
+        }
+    }
+}
+
+/// The state of an exchange as a whole, for showing to a user. See [`Exchange::current_status`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExchangeStatus {
+    /// The RFQ awaits a Quote.
+    AwaitingQuote,
+    /// The Quote awaits an Order.
+    Quoted,
+    /// The Order awaits an OrderStatus.
+    Ordered,
+    /// The order is underway, as of its latest OrderStatus.
+    InProgress(Status),
+    /// A Cancel awaits a Close.
+    Cancelling,
+    /// The payout settled, or the PFI closed the exchange successfully.
+    Completed,
+    /// The payin was refunded.
+    Refunded,
+    /// The exchange was closed after a Cancel.
+    Cancelled,
+    /// The exchange was closed unsuccessfully, or its order ended without settling.
+    Failed { reason: Option<String> },
+}
+
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(
+        pfi_did = %rfq.metadata.to,
+        exchange_id = %rfq.metadata.exchange_id,
+        kind = "rfq"
     )
-    .await?;
+)]
+pub async fn create_exchange(rfq: &Rfq, reply_to: Option<String>) -> Result<()> {
+    metrics::observe("create_exchange", &rfq.metadata.to, async {
+        let service_endpoint = get_service_endpoint(&rfq.metadata.to).await?;
+        let create_exchange_endpoint = format!("{}/exchanges", service_endpoint);
 
-    Ok(())
+        rfq.verify().await?;
+
+        post_json(
+            &create_exchange_endpoint,
+            &CreateExchangeRequestBody {
+                message: rfq.clone(),
+                reply_to,
+            },
+        )
+        .await?;
+
+        Ok(())
+    })
+    .await
 }
 
-pub async fn submit_order(order: &Order) -> Result<()> {
-    let service_endpoint = get_service_endpoint(&order.metadata.to).await?;
-    let submit_order_endpoint = format!(
-        "{}/exchanges/{}",
-        service_endpoint, order.metadata.exchange_id
-    );
-
-    order.verify().await?;
-
-    put_json(
-        &submit_order_endpoint,
-        &UpdateExchangeRequestBody {
-            message: WalletUpdateMessage::Order(Arc::new(order.clone())),
-        },
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(
+        pfi_did = %order.metadata.to,
+        exchange_id = %order.metadata.exchange_id,
+        kind = "order"
     )
-    .await?;
+)]
+pub async fn submit_order(order: &Order) -> Result<()> {
+    metrics::observe("submit_order", &order.metadata.to, async {
+        let service_endpoint = get_service_endpoint(&order.metadata.to).await?;
+        let submit_order_endpoint = format!(
+            "{}/exchanges/{}",
+            service_endpoint, order.metadata.exchange_id
+        );
+
+        order.verify().await?;
 
-    Ok(())
+        put_json(
+            &submit_order_endpoint,
+            &UpdateExchangeRequestBody {
+                message: WalletUpdateMessage::Order(Arc::new(order.clone())),
+            },
+        )
+        .await?;
+
+        Ok(())
+    })
+    .await
 }
 
-pub async fn submit_cancel(cancel: &Cancel) -> Result<()> {
-    let service_endpoint = get_service_endpoint(&cancel.metadata.to).await?;
-    let submit_cancel_endpoint = format!(
-        "{}/exchanges/{}",
-        service_endpoint, cancel.metadata.exchange_id
-    );
-
-    cancel.verify().await?;
-
-    put_json(
-        &submit_cancel_endpoint,
-        &UpdateExchangeRequestBody {
-            message: WalletUpdateMessage::Cancel(Arc::new(cancel.clone())),
-        },
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(
+        pfi_did = %cancel.metadata.to,
+        exchange_id = %cancel.metadata.exchange_id,
+        kind = "cancel"
     )
-    .await?;
+)]
+pub async fn submit_cancel(cancel: &Cancel) -> Result<()> {
+    metrics::observe("submit_cancel", &cancel.metadata.to, async {
+        let service_endpoint = get_service_endpoint(&cancel.metadata.to).await?;
+        let submit_cancel_endpoint = format!(
+            "{}/exchanges/{}",
+            service_endpoint, cancel.metadata.exchange_id
+        );
+
+        cancel.verify().await?;
 
-    Ok(())
+        put_json(
+            &submit_cancel_endpoint,
+            &UpdateExchangeRequestBody {
+                message: WalletUpdateMessage::Cancel(Arc::new(cancel.clone())),
+            },
+        )
+        .await?;
+
+        Ok(())
+    })
+    .await
 }
 
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(pfi_did = %pfi_did_uri, exchange_id = %exchange_id)
+)]
 pub async fn get_exchange(
     pfi_did_uri: &str,
     bearer_did: &BearerDid,
     exchange_id: &str,
 ) -> Result<Exchange> {
+    metrics::observe("get_exchange", pfi_did_uri, async {
+        let messages = get_exchange_messages(pfi_did_uri, bearer_did, exchange_id).await?;
+
+        Ok(Exchange::from_messages(messages))
+    })
+    .await
+}
+
+/// Fetches the messages of the exchange in the order the PFI returns them, without verifying
+/// them.
+pub(crate) async fn get_exchange_messages(
+    pfi_did_uri: &str,
+    bearer_did: &BearerDid,
+    exchange_id: &str,
+) -> Result<Vec<Message>> {
     let service_endpoint = get_service_endpoint(pfi_did_uri).await?;
     let get_exchange_endpoint = format!("{}/exchanges/{}", service_endpoint, exchange_id);
 
@@ -108,39 +326,7 @@ pub async fn get_exchange(
     let get_exchange_response_body =
         get_json::<GetExchangeResponseBody>(&get_exchange_endpoint, Some(access_token)).await?;
 
-    let mut exchange = Exchange::default();
-
-    for message in get_exchange_response_body.data {
-        match message {
-            Message::Rfq(rfq) => {
-                exchange.rfq = rfq;
-            }
-            Message::Quote(quote) => {
-                exchange.quote = Some(quote);
-            }
-            Message::Order(order) => {
-                exchange.order = Some(order);
-            }
-            Message::OrderInstructions(order_instructions) => {
-                exchange.order_instructions = Some(order_instructions);
-            }
-            Message::Cancel(cancel) => {
-                exchange.cancel = Some(cancel);
-            }
-            Message::OrderStatus(order_status) => {
-                if let Some(order_statuses) = &mut exchange.order_statuses {
-                    order_statuses.push(order_status);
-                } else {
-                    exchange.order_statuses = Some(vec![order_status]);
-                }
-            }
-            Message::Close(close) => {
-                exchange.close = Some(close);
-            }
-        }
-    }
-
-    Ok(exchange)
+    Ok(get_exchange_response_body.data)
 }
 
 #[derive(Clone, Default, Debug, PartialEq)]
@@ -149,27 +335,150 @@ pub struct GetExchangeIdsQueryParams {
     pub pagination_limit: Option<i64>,
 }
 
+#[tracing::instrument(level = "debug", skip_all, fields(pfi_did = %pfi_did))]
 pub async fn get_exchange_ids(
     pfi_did: &str,
     requestor_did: &BearerDid,
     query_params: Option<GetExchangeIdsQueryParams>,
 ) -> Result<Vec<String>> {
-    let service_endpoint = get_service_endpoint(pfi_did).await?;
-    let get_exchanges_endpoint = format!("{}/exchanges", service_endpoint);
-
-    let get_exchanges_endpoint = if let Some(params) = query_params {
-        add_pagination(
-            &get_exchanges_endpoint,
-            params.pagination_offset,
-            params.pagination_limit,
-        )
-    } else {
-        get_exchanges_endpoint
+    metrics::observe("get_exchange_ids", pfi_did, async {
+        let service_endpoint = get_service_endpoint(pfi_did).await?;
+        let get_exchanges_endpoint = format!("{}/exchanges", service_endpoint);
+
+        let get_exchanges_endpoint = if let Some(params) = query_params {
+            add_pagination(
+                &get_exchanges_endpoint,
+                params.pagination_offset,
+                params.pagination_limit,
+            )
+        } else {
+            get_exchanges_endpoint
+        };
+
+        let access_token = generate_access_token(pfi_did, requestor_did)?;
+        let get_exchanges_response_body =
+            get_json::<GetExchangesResponseBody>(&get_exchanges_endpoint, Some(access_token))
+                .await?;
+
+        Ok(get_exchanges_response_body.data)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{
+        cancel::CancelData, close::CloseData, order_status::OrderStatusData, MessageKind,
     };
 
-    let access_token = generate_access_token(pfi_did, requestor_did)?;
-    let get_exchanges_response_body =
-        get_json::<GetExchangesResponseBody>(&get_exchanges_endpoint, Some(access_token)).await?;
+    fn order_status(status: Status) -> Arc<OrderStatus> {
+        Arc::new(
+            OrderStatus::create(
+                "did:test:wallet",
+                "did:test:pfi",
+                &MessageKind::Rfq.typesafe_id().unwrap(),
+                &OrderStatusData {
+                    status,
+                    details: None,
+                },
+                None,
+                None,
+            )
+            .unwrap(),
+        )
+    }
+
+    fn close(success: Option<bool>, reason: &str) -> Arc<Close> {
+        Arc::new(
+            Close::create(
+                "did:test:wallet",
+                "did:test:pfi",
+                &MessageKind::Rfq.typesafe_id().unwrap(),
+                &CloseData {
+                    reason: Some(reason.to_string()),
+                    success,
+                },
+                None,
+                None,
+            )
+            .unwrap(),
+        )
+    }
+
+    fn cancel() -> Arc<Cancel> {
+        Arc::new(
+            Cancel::create(
+                "did:test:pfi",
+                "did:test:wallet",
+                &MessageKind::Rfq.typesafe_id().unwrap(),
+                &CancelData { reason: None },
+                None,
+                None,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn derives_current_status() {
+        let mut exchange = Exchange::default();
+        assert_eq!(ExchangeStatus::AwaitingQuote, exchange.current_status());
+
+        exchange.order_statuses = Some(vec![
+            order_status(Status::PayinPending),
+            order_status(Status::PayinSettled),
+        ]);
+        assert_eq!(
+            ExchangeStatus::InProgress(Status::PayinSettled),
+            exchange.current_status()
+        );
 
-    Ok(get_exchanges_response_body.data)
+        exchange.cancel = Some(cancel());
+        assert_eq!(ExchangeStatus::Cancelling, exchange.current_status());
+
+        exchange.close = Some(close(None, "cancelled"));
+        assert_eq!(ExchangeStatus::Cancelled, exchange.current_status());
+
+        exchange
+            .order_statuses
+            .as_mut()
+            .unwrap()
+            .push(order_status(Status::RefundSettled));
+        assert_eq!(ExchangeStatus::Refunded, exchange.current_status());
+        exchange.validate_order_statuses().unwrap();
+    }
+
+    #[test]
+    fn derives_outcome_from_close() {
+        let mut exchange = Exchange {
+            close: Some(close(Some(true), "done")),
+            ..Default::default()
+        };
+        assert_eq!(ExchangeStatus::Completed, exchange.current_status());
+
+        exchange.close = Some(close(Some(false), "declined"));
+        assert_eq!(
+            ExchangeStatus::Failed {
+                reason: Some("declined".to_string())
+            },
+            exchange.current_status()
+        );
+
+        exchange.close = None;
+        exchange.order_statuses = Some(vec![
+            order_status(Status::PayinPending),
+            order_status(Status::PayinExpired),
+        ]);
+        assert_eq!(
+            ExchangeStatus::Failed { reason: None },
+            exchange.current_status()
+        );
+
+        exchange.order_statuses = Some(vec![
+            order_status(Status::PayoutSettled),
+            order_status(Status::PayinPending),
+        ]);
+        assert!(exchange.validate_order_statuses().is_err());
+    }
 }
@@ -1,12 +1,16 @@
 pub mod balances;
+pub mod exchange_cache;
 pub mod exchanges;
 pub mod offerings;
 
-use crate::errors::{Result, TbdexError};
+use crate::{
+    errors::{Result, TbdexError},
+    redact::{Redacted, REDACTED},
+};
+use chrono::{Duration, Utc};
 use http_std::FetchOptions;
 use serde::{de::DeserializeOwned, Serialize};
-use chrono::{Duration, Utc};
-use std::time::SystemTime;
+use std::{fmt, time::SystemTime};
 use uuid::Uuid;
 use web5::{
     dids::{
@@ -18,8 +22,38 @@ use web5::{
     errors::Web5Error,
     jose::{Jwt, JwtClaims},
 };
+use zeroize::Zeroize;
+
+/// A bearer token authenticating the requester to a PFI.
+///
+/// Its `Debug` and `Display` output is redacted, and it is zeroized on drop.
+pub(crate) struct AccessToken(String);
+
+impl AccessToken {
+    fn authorization_header(&self) -> String {
+        format!("Bearer {}", self.0)
+    }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AccessToken").field(&Redacted).finish()
+    }
+}
+
+impl fmt::Display for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
 
-fn generate_access_token(pfi_did_uri: &str, bearer_did: &BearerDid) -> Result<String> {
+impl Drop for AccessToken {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+fn generate_access_token(pfi_did_uri: &str, bearer_did: &BearerDid) -> Result<AccessToken> {
     let now = Utc::now();
     let exp = now + Duration::seconds(60);
 
@@ -37,20 +71,67 @@ fn generate_access_token(pfi_did_uri: &str, bearer_did: &BearerDid) -> Result<St
     // TODO default to first vm
     let jwt = Jwt::from_claims(claims, bearer_did, None)?;
 
-    Ok(jwt.compact_jws)
+    Ok(AccessToken(jwt.compact_jws))
+}
+
+#[cfg(feature = "test-util")]
+lazy_static::lazy_static! {
+    static ref SERVICE_ENDPOINTS: std::sync::RwLock<std::collections::HashMap<String, String>> =
+        Default::default();
+}
+
+/// Uses `endpoint` as the PFI's service endpoint instead of resolving its DID.
+///
+/// For PFIs whose DIDs cannot carry services, such as a local mock PFI identified by a `did:jwk`.
+/// Only available with the `test-util` feature, as it affects every client in the process.
+#[cfg(feature = "test-util")]
+pub fn set_service_endpoint(pfi_did_uri: &str, endpoint: &str) {
+    SERVICE_ENDPOINTS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(pfi_did_uri.to_string(), endpoint.to_string());
+}
+
+/// Removes an endpoint set with [`set_service_endpoint`], so that the PFI's DID is resolved again.
+#[cfg(feature = "test-util")]
+pub fn remove_service_endpoint(pfi_did_uri: &str) {
+    SERVICE_ENDPOINTS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(pfi_did_uri);
 }
 
-async fn get_service_endpoint(pfi_did_uri: &str) -> Result<String> {
+/// Resolves the PFI's DID and returns the endpoint of its `PFI` service, unless one has been set
+/// with `set_service_endpoint` under the `test-util` feature.
+#[tracing::instrument(level = "debug", skip_all, fields(pfi_did = %pfi_did_uri))]
+pub async fn get_service_endpoint(pfi_did_uri: &str) -> Result<String> {
+    #[cfg(feature = "test-util")]
+    {
+        let overridden = SERVICE_ENDPOINTS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(pfi_did_uri)
+            .cloned();
+        if let Some(endpoint) = overridden {
+            tracing::debug!(%endpoint, "using overridden service endpoint");
+            return Ok(endpoint);
+        }
+    }
+
     let resolution_result = ResolutionResult::resolve(pfi_did_uri).await;
 
     let endpoint = match &resolution_result.document {
         None => {
+            tracing::warn!(
+                error = ?resolution_result.resolution_metadata.error,
+                "pfi did resolution failed"
+            );
             return Err(match resolution_result.resolution_metadata.error {
                 Some(e) => TbdexError::Web5Error(Web5Error::Resolution(e)),
                 None => TbdexError::Web5Error(Web5Error::Resolution(
                     ResolutionMetadataError::InternalError,
                 )),
-            })
+            });
         }
         Some(d) => match &d.service {
             None => {
@@ -76,6 +157,7 @@ async fn get_service_endpoint(pfi_did_uri: &str) -> Result<String> {
         },
     };
 
+    tracing::debug!(%endpoint, "resolved service endpoint");
     Ok(endpoint)
 }
 
@@ -101,15 +183,21 @@ fn add_pagination(
     format!("{}{}", endpoint, query_string)
 }
 
+// request bodies and the access token are never recorded, as they carry private data
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(method = "GET", url = %url, status_code = tracing::field::Empty)
+)]
 pub(crate) async fn get_json<T: DeserializeOwned>(
     url: &str,
-    access_token: Option<String>,
+    access_token: Option<AccessToken>,
 ) -> Result<T> {
     let options = access_token.map(|access_token| FetchOptions {
         headers: Some(
             [(
                 "Authorization".to_string(),
-                format!("Bearer {}", access_token),
+                access_token.authorization_header(),
             )]
             .into_iter()
             .collect(),
@@ -117,8 +205,10 @@ pub(crate) async fn get_json<T: DeserializeOwned>(
         ..Default::default()
     });
     let response = http_std::fetch(url, options).await?;
+    tracing::Span::current().record("status_code", response.status_code);
 
     if !(200..300).contains(&response.status_code) {
+        tracing::warn!("pfi responded with an error status");
         return Err(TbdexError::Http(format!(
             "http error status code {} for url {}",
             response.status_code, url
@@ -130,6 +220,11 @@ pub(crate) async fn get_json<T: DeserializeOwned>(
     Ok(json)
 }
 
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(method = "POST", url = %url, status_code = tracing::field::Empty)
+)]
 pub(crate) async fn post_json<T: Serialize>(url: &str, body: &T) -> Result<()> {
     let body = serde_json::to_vec(body)?;
 
@@ -146,8 +241,10 @@ pub(crate) async fn post_json<T: Serialize>(url: &str, body: &T) -> Result<()> {
         }),
     )
     .await?;
+    tracing::Span::current().record("status_code", response.status_code);
 
     if !(200..300).contains(&response.status_code) {
+        tracing::warn!("pfi responded with an error status");
         return Err(TbdexError::Http(format!(
             "http error status code {} for url {}",
             response.status_code, url
@@ -157,6 +254,11 @@ pub(crate) async fn post_json<T: Serialize>(url: &str, body: &T) -> Result<()> {
     Ok(())
 }
 
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(method = "PUT", url = %url, status_code = tracing::field::Empty)
+)]
 pub(crate) async fn put_json<T: Serialize>(url: &str, body: &T) -> Result<()> {
     let body = serde_json::to_vec(body)?;
 
@@ -173,8 +275,10 @@ pub(crate) async fn put_json<T: Serialize>(url: &str, body: &T) -> Result<()> {
         }),
     )
     .await?;
+    tracing::Span::current().record("status_code", response.status_code);
 
     if !(200..300).contains(&response.status_code) {
+        tracing::warn!("pfi responded with an error status");
         return Err(TbdexError::Http(format!(
             "http error status code {} for url {}",
             response.status_code, url
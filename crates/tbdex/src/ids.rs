@@ -0,0 +1,137 @@
+use crate::errors::{Result, TbdexError};
+use chrono::{DateTime, Utc};
+use rand::{thread_rng, Rng};
+use std::{str::FromStr, sync::Mutex};
+use type_safe_id::{DynamicType, TypeSafeId};
+use uuid::{Builder, Uuid, Variant, Version};
+
+/// The largest value of the 12 bit counter held in `rand_a`.
+const MAX_COUNTER: u16 = 0x0fff;
+
+/// The counter is seeded randomly below this value each millisecond, leaving room to increment.
+const COUNTER_SEED_LIMIT: u16 = 0x0200;
+
+/// The millisecond and counter of the last generated UUID.
+static LAST: Mutex<(u64, u16)> = Mutex::new((0, 0));
+
+/// Generates a UUIDv7 which sorts after every UUIDv7 previously generated by this process.
+///
+/// Follows method 1 of RFC 9562 section 6.2: the 12 bit `rand_a` field holds a counter which
+/// is seeded randomly each millisecond and incremented for UUIDs within the same millisecond.
+/// If the counter is exhausted, or the clock moves backwards, the timestamp of the last UUID is
+/// carried forward instead so ordering still holds.
+pub(crate) fn new_uuid_v7() -> Uuid {
+    new_uuid_v7_at(Utc::now())
+}
+
+pub(crate) fn new_uuid_v7_at(now: DateTime<Utc>) -> Uuid {
+    let mut last = LAST.lock().unwrap_or_else(|e| e.into_inner());
+    next_uuid_v7(&mut last, now, &mut thread_rng())
+}
+
+/// Generates the UUIDv7 following `last`, the millisecond and counter of the previous UUID
+/// generated from the same state, which is advanced in place.
+pub(crate) fn next_uuid_v7<R: Rng>(last: &mut (u64, u16), now: DateTime<Utc>, rng: &mut R) -> Uuid {
+    let now = now.timestamp_millis().max(0) as u64;
+    let (last_millis, last_counter) = *last;
+
+    *last = if now > last_millis {
+        (now, rng.gen_range(0..COUNTER_SEED_LIMIT))
+    } else if last_counter < MAX_COUNTER {
+        (last_millis, last_counter + 1)
+    } else {
+        (last_millis + 1, rng.gen_range(0..COUNTER_SEED_LIMIT))
+    };
+    let (millis, counter) = *last;
+
+    let mut bytes: [u8; 16] = rng.gen();
+    bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+    bytes[6..8].copy_from_slice(&counter.to_be_bytes());
+
+    Builder::from_bytes(bytes)
+        .with_version(Version::SortRand)
+        .with_variant(Variant::RFC4122)
+        .into_uuid()
+}
+
+/// Generates a typesafe id, such as `rfq_01hw...`, with the given prefix.
+pub(crate) fn new_typesafe_id(prefix: &str) -> Result<String> {
+    typesafe_id_from_uuid(prefix, new_uuid_v7())
+}
+
+/// Formats a typesafe id from the given prefix and UUID.
+pub(crate) fn typesafe_id_from_uuid(prefix: &str, uuid: Uuid) -> Result<String> {
+    let dynamic_type = DynamicType::new(prefix)?;
+    Ok(TypeSafeId::from_type_and_uuid(dynamic_type, uuid).to_string())
+}
+
+/// Splits a typesafe id into its prefix and UUID.
+pub(crate) fn parse_typesafe_id(id: &str) -> Result<(String, Uuid)> {
+    let parsed = TypeSafeId::<DynamicType>::from_str(id)?;
+    Ok((parsed.type_prefix().to_string(), parsed.uuid()))
+}
+
+/// The creation time embedded in a UUIDv7, to millisecond precision.
+pub(crate) fn uuid_v7_timestamp(uuid: &Uuid) -> Result<DateTime<Utc>> {
+    if uuid.get_version_num() != 7 {
+        return Err(TbdexError::TypeId(format!(
+            "{} is a version {} uuid, expected version 7",
+            uuid,
+            uuid.get_version_num()
+        )));
+    }
+
+    let bytes = uuid.as_bytes();
+    let mut millis = [0u8; 8];
+    millis[2..].copy_from_slice(&bytes[..6]);
+
+    DateTime::from_timestamp_millis(u64::from_be_bytes(millis) as i64)
+        .ok_or_else(|| TbdexError::TypeId(format!("{} has an invalid timestamp", uuid)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn generates_monotonic_uuids() {
+        let uuids: Vec<Uuid> = (0..10_000).map(|_| new_uuid_v7()).collect();
+
+        let mut sorted = uuids.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        assert_eq!(uuids, sorted);
+        assert!(uuids.iter().all(|u| u.get_version_num() == 7));
+    }
+
+    #[test]
+    fn embeds_the_current_time() {
+        let before = Utc::now() - Duration::milliseconds(1);
+        let timestamp = uuid_v7_timestamp(&new_uuid_v7()).unwrap();
+
+        // the carried forward timestamp may run slightly ahead when ids are generated quickly
+        assert!(timestamp >= before);
+        assert!(timestamp <= Utc::now() + Duration::seconds(1));
+    }
+
+    #[test]
+    fn stays_monotonic_when_the_clock_moves_backwards() {
+        let first = new_uuid_v7();
+        let second = new_uuid_v7_at(Utc::now() - Duration::hours(1));
+
+        assert!(second > first);
+    }
+
+    #[test]
+    fn parses_typesafe_ids() {
+        let id = new_typesafe_id("rfq").unwrap();
+        let (prefix, uuid) = parse_typesafe_id(&id).unwrap();
+
+        assert_eq!("rfq", prefix);
+        assert!(uuid_v7_timestamp(&uuid).is_ok());
+        assert!(parse_typesafe_id("not an id").is_err());
+        assert!(uuid_v7_timestamp(&Uuid::nil()).is_err());
+    }
+}
@@ -0,0 +1,458 @@
+//! A tamper-evident record of the messages sent and received.
+//!
+//! Each [`JournalEntry`] records a [`Message`] with its [`digest`](TbdexMessage::digest), the
+//! time and direction, and the hash of the entry before it, so that altering, removing or
+//! reordering any entry breaks the chain from there on. A [`Journal`] with an operator DID also
+//! signs the hash of every entry. [`verify_chain`] walks the entries and reports every break.
+//!
+//! Only the entries after the first are protected by the chain: anchor the [`Journal::head`]
+//! elsewhere, such as by publishing it periodically, to also detect entries dropped from the end.
+
+use crate::{
+    context::CreationContext,
+    digest::{hash_canonical, ContentHash},
+    errors::{ParseError, Result},
+    json::{FromJson, JsonObject, ToJson},
+    messages::{Message, TbdexMessage},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use web5::dids::bearer_did::BearerDid;
+
+/// Whether a message was sent or received by the journal's owner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// A message recorded in a [`Journal`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    /// The position of the entry in the journal, from 0.
+    pub sequence: u64,
+
+    /// When the entry was recorded, in RFC 3339 format.
+    pub timestamp: String,
+
+    pub direction: Direction,
+
+    /// The digest of the message when it was recorded.
+    pub message_digest: ContentHash,
+
+    /// The hash of the entry before this one, or `None` for the first entry.
+    pub previous_hash: Option<ContentHash>,
+
+    pub message: Message,
+
+    /// The operator's detached compact JWS over the [`hash`](Self::hash) of the entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl FromJson for JournalEntry {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        let object = JsonObject::new(value)?;
+        Ok(Self {
+            sequence: object.deserialize("sequence")?,
+            timestamp: object.deserialize("timestamp")?,
+            direction: object.deserialize("direction")?,
+            message_digest: object.deserialize("messageDigest")?,
+            previous_hash: object.deserialize("previousHash")?,
+            message: object.field("message")?,
+            signature: object.deserialize("signature")?,
+        })
+    }
+}
+impl ToJson for JournalEntry {}
+
+/// The fields of an entry covered by its hash, with the message standing in by its digest.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChainedFields<'a> {
+    sequence: u64,
+    timestamp: &'a str,
+    direction: Direction,
+    message_digest: &'a ContentHash,
+    previous_hash: &'a Option<ContentHash>,
+}
+
+impl JournalEntry {
+    /// The SHA-256 digest of the JCS canonicalized entry, without its message or signature. The
+    /// next entry's `previous_hash` is this.
+    pub fn hash(&self) -> Result<ContentHash> {
+        hash_canonical(&ChainedFields {
+            sequence: self.sequence,
+            timestamp: &self.timestamp,
+            direction: self.direction,
+            message_digest: &self.message_digest,
+            previous_hash: &self.previous_hash,
+        })
+    }
+}
+
+/// An append-only, hash-chained journal of messages.
+#[derive(Default)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+    operator: Option<BearerDid>,
+    context: CreationContext,
+}
+
+impl Journal {
+    /// An empty journal whose entries are not signed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continues a journal from its entries, as previously returned by [`Journal::entries`].
+    ///
+    /// The entries are not checked; see [`verify_chain`].
+    pub fn resume(entries: Vec<JournalEntry>) -> Self {
+        Self {
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// Signs every entry recorded from now on with the operator's DID.
+    pub fn with_operator(mut self, bearer_did: BearerDid) -> Self {
+        self.operator = Some(bearer_did);
+        self
+    }
+
+    /// Replaces the context whose clock timestamps the entries.
+    pub fn with_context(mut self, context: CreationContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Records a message sent to a counterparty.
+    pub fn record_sent(&mut self, message: &Message) -> Result<&JournalEntry> {
+        self.record(Direction::Sent, message)
+    }
+
+    /// Records a message received from a counterparty.
+    pub fn record_received(&mut self, message: &Message) -> Result<&JournalEntry> {
+        self.record(Direction::Received, message)
+    }
+
+    /// Appends an entry for the message, chained to the last entry.
+    pub fn record(&mut self, direction: Direction, message: &Message) -> Result<&JournalEntry> {
+        let mut entry = JournalEntry {
+            sequence: self.entries.len() as u64,
+            timestamp: self.context.now().to_rfc3339(),
+            direction,
+            message_digest: message.digest()?,
+            previous_hash: self.head()?,
+            message: message.clone(),
+            signature: None,
+        };
+
+        if let Some(operator) = &self.operator {
+            entry.signature = Some(crate::signature::sign_digest(operator, &entry.hash()?)?);
+        }
+
+        self.entries.push(entry);
+        Ok(&self.entries[self.entries.len() - 1])
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// The hash of the last entry, which commits to the whole journal.
+    pub fn head(&self) -> Result<Option<ContentHash>> {
+        self.entries.last().map(JournalEntry::hash).transpose()
+    }
+
+    /// Verifies the journal's chain, requiring entries to be signed if it has an operator. See
+    /// [`verify_chain`].
+    pub async fn verify(&self) -> Vec<ChainBreak> {
+        let operator_did = self.operator.as_ref().map(|o| o.did.uri.as_str());
+        verify_chain(&self.entries, operator_did).await
+    }
+}
+
+/// Where and how a journal's chain is broken.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChainBreak {
+    /// The entry at `index` claims to be at a different position.
+    Sequence { index: usize, sequence: u64 },
+    /// The entry's previous hash is not the hash of the entry before it, so that entry was
+    /// altered, or entries were inserted, removed or reordered between them.
+    PreviousHash {
+        sequence: u64,
+        expected: Option<ContentHash>,
+        found: Option<ContentHash>,
+    },
+    /// The message no longer has the digest recorded with it.
+    MessageDigest {
+        sequence: u64,
+        expected: ContentHash,
+        found: ContentHash,
+    },
+    /// The entry's signature is missing, not the operator's, or does not verify.
+    Signature { sequence: u64, reason: String },
+    /// The entry could not be hashed.
+    Unhashable { sequence: u64, reason: String },
+}
+
+impl fmt::Display for ChainBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hash = |hash: &Option<ContentHash>| {
+            hash.as_ref()
+                .map_or_else(|| "none".to_string(), ContentHash::to_string)
+        };
+
+        match self {
+            ChainBreak::Sequence { index, sequence } => {
+                write!(f, "entry {} claims sequence {}", index, sequence)
+            }
+            ChainBreak::PreviousHash {
+                sequence,
+                expected,
+                found,
+            } => write!(
+                f,
+                "entry {} previous hash {} does not match {}",
+                sequence,
+                hash(found),
+                hash(expected)
+            ),
+            ChainBreak::MessageDigest {
+                sequence,
+                expected,
+                found,
+            } => write!(
+                f,
+                "entry {} message digest {} does not match recorded {}",
+                sequence, found, expected
+            ),
+            ChainBreak::Signature { sequence, reason } => {
+                write!(f, "entry {} signature invalid: {}", sequence, reason)
+            }
+            ChainBreak::Unhashable { sequence, reason } => {
+                write!(f, "entry {} could not be hashed: {}", sequence, reason)
+            }
+        }
+    }
+}
+
+/// Walks the entries from the first, checking each one's sequence, previous hash, message digest
+/// and signature, and returns every break found. An intact chain returns none.
+///
+/// With an `operator_did`, every entry must be signed by that DID. Without one, only the
+/// signatures present are verified.
+pub async fn verify_chain(entries: &[JournalEntry], operator_did: Option<&str>) -> Vec<ChainBreak> {
+    let mut breaks = vec![];
+    let mut previous_hash = None;
+
+    for (index, entry) in entries.iter().enumerate() {
+        let sequence = entry.sequence;
+        if sequence != index as u64 {
+            breaks.push(ChainBreak::Sequence { index, sequence });
+        }
+
+        if entry.previous_hash != previous_hash {
+            breaks.push(ChainBreak::PreviousHash {
+                sequence,
+                expected: previous_hash,
+                found: entry.previous_hash,
+            });
+        }
+
+        match entry.message.digest() {
+            Ok(found) if found != entry.message_digest => breaks.push(ChainBreak::MessageDigest {
+                sequence,
+                expected: entry.message_digest,
+                found,
+            }),
+            Ok(_) => {}
+            Err(e) => breaks.push(ChainBreak::Unhashable {
+                sequence,
+                reason: e.to_string(),
+            }),
+        }
+
+        let hash = match entry.hash() {
+            Ok(hash) => hash,
+            Err(e) => {
+                breaks.push(ChainBreak::Unhashable {
+                    sequence,
+                    reason: e.to_string(),
+                });
+                previous_hash = None;
+                continue;
+            }
+        };
+
+        if let Err(reason) = verify_entry_signature(entry, &hash, operator_did).await {
+            breaks.push(ChainBreak::Signature { sequence, reason });
+        }
+
+        previous_hash = Some(hash);
+    }
+
+    breaks
+}
+
+async fn verify_entry_signature(
+    entry: &JournalEntry,
+    hash: &ContentHash,
+    operator_did: Option<&str>,
+) -> std::result::Result<(), String> {
+    let signature = match (&entry.signature, operator_did) {
+        (Some(signature), _) => signature,
+        (None, Some(_)) => return Err("missing".to_string()),
+        (None, None) => return Ok(()),
+    };
+
+    if let Some(operator_did) = operator_did {
+        let signer = crate::signature::signer_did(signature).map_err(|e| e.to_string())?;
+        if signer != operator_did {
+            return Err(format!("signed by {} rather than the operator", signer));
+        }
+    }
+
+    crate::signature::verify_digest(hash, signature)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{exchange_id, signed_close};
+    use futures::executor::block_on;
+    use std::sync::Arc;
+    use web5::dids::methods::did_jwk::DidJwk;
+
+    fn close(from: &BearerDid, reason: &str) -> Message {
+        Message::Close(Arc::new(signed_close(from, &exchange_id(), reason)))
+    }
+
+    fn signed_journal(operator: BearerDid) -> Journal {
+        let counterparty = DidJwk::create(None).unwrap();
+        let mut journal = Journal::new().with_operator(operator);
+        journal.record_sent(&close(&counterparty, "first")).unwrap();
+        journal
+            .record_received(&close(&counterparty, "second"))
+            .unwrap();
+        journal.record_sent(&close(&counterparty, "third")).unwrap();
+        journal
+    }
+
+    #[test]
+    fn records_an_intact_chain() {
+        let journal = signed_journal(DidJwk::create(None).unwrap());
+
+        let entries = journal.entries();
+        assert_eq!(3, entries.len());
+        assert_eq!(None, entries[0].previous_hash);
+        assert_eq!(Some(entries[0].hash().unwrap()), entries[1].previous_hash);
+        assert_eq!(Direction::Received, entries[1].direction);
+        assert_eq!(
+            entries[2].message.digest().unwrap(),
+            entries[2].message_digest
+        );
+        assert_eq!(Some(entries[2].hash().unwrap()), journal.head().unwrap());
+
+        assert_eq!(Vec::<ChainBreak>::new(), block_on(journal.verify()));
+    }
+
+    #[test]
+    fn resumes_from_serialized_entries() {
+        let journal = signed_journal(DidJwk::create(None).unwrap());
+
+        let stored = journal
+            .entries()
+            .iter()
+            .map(|entry| entry.to_json_string().unwrap())
+            .collect::<Vec<_>>();
+        let entries = stored
+            .iter()
+            .map(|json| JournalEntry::from_json_string(json).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut resumed = Journal::resume(entries);
+        let entry = resumed
+            .record_received(&close(&DidJwk::create(None).unwrap(), "fourth"))
+            .unwrap();
+        assert_eq!(3, entry.sequence);
+        assert_eq!(journal.head().unwrap(), entry.previous_hash);
+
+        assert_eq!(
+            Vec::<ChainBreak>::new(),
+            block_on(verify_chain(resumed.entries(), None))
+        );
+    }
+
+    #[test]
+    fn reports_altered_entries() {
+        let journal = signed_journal(DidJwk::create(None).unwrap());
+
+        let mut altered = journal.entries().to_vec();
+        altered[1].direction = Direction::Sent;
+        let breaks = block_on(verify_chain(&altered, None));
+        assert!(matches!(
+            breaks.as_slice(),
+            [
+                ChainBreak::Signature { sequence: 1, .. },
+                ChainBreak::PreviousHash { sequence: 2, .. }
+            ]
+        ));
+
+        let mut replaced = journal.entries().to_vec();
+        replaced[0].message = close(&DidJwk::create(None).unwrap(), "forged");
+        let breaks = block_on(verify_chain(&replaced, None));
+        assert!(matches!(
+            breaks.as_slice(),
+            [ChainBreak::MessageDigest { sequence: 0, .. }]
+        ));
+    }
+
+    #[test]
+    fn reports_removed_entries() {
+        let journal = signed_journal(DidJwk::create(None).unwrap());
+
+        let mut removed = journal.entries().to_vec();
+        removed.remove(1);
+        let breaks = block_on(verify_chain(&removed, None));
+        assert!(matches!(
+            breaks.as_slice(),
+            [
+                ChainBreak::Sequence {
+                    index: 1,
+                    sequence: 2
+                },
+                ChainBreak::PreviousHash { sequence: 2, .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn requires_the_operators_signature() {
+        let journal = signed_journal(DidJwk::create(None).unwrap());
+        let other_did = DidJwk::create(None).unwrap().did.uri;
+
+        let breaks = block_on(verify_chain(journal.entries(), Some(&other_did)));
+        assert_eq!(3, breaks.len());
+        assert!(breaks
+            .iter()
+            .all(|b| matches!(b, ChainBreak::Signature { .. })));
+
+        let mut unsigned = Journal::new();
+        unsigned
+            .record_sent(&close(&DidJwk::create(None).unwrap(), "first"))
+            .unwrap();
+        assert!(block_on(unsigned.verify()).is_empty());
+        assert!(matches!(
+            block_on(verify_chain(unsigned.entries(), Some(&other_did))).as_slice(),
+            [ChainBreak::Signature { sequence: 0, .. }]
+        ));
+    }
+}
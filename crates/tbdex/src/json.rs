@@ -1,10 +1,41 @@
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
 
-use crate::errors::{Result, TbdexError};
+use crate::{
+    errors::{ParseError, Result, TbdexError},
+    redact::{zeroize_value, Secret},
+};
 
 pub trait FromJson: Sized + DeserializeOwned {
+    /// Parses the JSON, reporting the path to and reason for any failure as a
+    /// [`TbdexError::Parse`].
     fn from_json_string(json: &str) -> Result<Self> {
-        serde_json::from_str(json).map_err(TbdexError::from)
+        let value = serde_json::from_str(json).map_err(|e| ParseError::new(e.to_string()))?;
+        Ok(Self::from_json_value(&value)?)
+    }
+
+    /// Parses the JSON value, reporting the path to and reason for any failure.
+    ///
+    /// Messages and resources, and the values containing them, parse themselves here rather
+    /// than through `Deserialize`, whose errors carry only a message.
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        parse_value(value)
+    }
+
+    /// Parses the JSON, rejecting any field this crate does not model instead of preserving it.
+    fn from_json_string_strict(json: &str) -> Result<Self>
+    where
+        Self: UnknownFields,
+    {
+        let parsed = Self::from_json_string(json)?;
+
+        let unknown_fields = parsed.unknown_fields();
+        if !unknown_fields.is_empty() {
+            return Err(TbdexError::UnknownFields(unknown_fields.join(", ")));
+        }
+
+        Ok(parsed)
     }
 }
 
@@ -13,3 +44,472 @@ pub trait ToJson: Serialize {
         serde_json::to_string(self).map_err(TbdexError::from)
     }
 }
+
+/// Implemented by messages and resources, which keep fields they do not model when parsed so
+/// that signatures verify and the fields are forwarded intact when re-serialized.
+///
+/// The kept fields are not compared by `PartialEq`, which compares only the modelled fields: a
+/// message equals the one it was parsed from, whether or not that carried unknown fields.
+pub trait UnknownFields {
+    /// The paths (e.g. `data.payin.foo`) of fields present in the parsed JSON which are not
+    /// modelled by this crate.
+    fn unknown_fields(&self) -> &[String];
+}
+
+/// Deserializes a JSON value, reporting the path to and reason for any failure.
+pub(crate) fn parse_value<T: DeserializeOwned>(
+    value: &Value,
+) -> std::result::Result<T, ParseError> {
+    serde_path_to_error::deserialize(value).map_err(|error| {
+        let path = error.path().to_string();
+        let reason = error.into_inner().to_string();
+
+        // point at the missing field itself rather than the object missing it
+        let error = match reason
+            .strip_prefix("missing field `")
+            .and_then(|r| r.strip_suffix('`'))
+        {
+            Some(field) => ParseError::at(field, reason.clone()),
+            None => ParseError::new(reason),
+        };
+
+        match path.as_str() {
+            "." => error,
+            path => error.within(path),
+        }
+    })
+}
+
+/// Parses a message or resource through its `TryFrom<RawEnvelope>` impl.
+pub(crate) fn parse_envelope<T>(value: &Value) -> std::result::Result<T, ParseError>
+where
+    T: TryFrom<RawEnvelope, Error = ParseError>,
+{
+    T::try_from(parse_value(value)?)
+}
+
+/// Deserializes a message or resource through its `TryFrom<RawEnvelope>` impl.
+///
+/// A failure within the message is reduced to its message; [`FromJson::from_json_value`] keeps
+/// its path.
+pub(crate) fn deserialize_envelope<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<RawEnvelope, Error = ParseError>,
+{
+    T::try_from(RawEnvelope::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        let Value::Array(elements) = value else {
+            return Err(invalid_type(value, "a sequence"));
+        };
+
+        elements
+            .iter()
+            .enumerate()
+            .map(|(i, element)| {
+                T::from_json_value(element).map_err(|e| e.within(&format!("[{}]", i)))
+            })
+            .collect()
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_json_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Arc<T> {
+    fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+        T::from_json_value(value).map(Arc::new)
+    }
+}
+
+/// A JSON object parsed field by field, for the values containing messages or resources.
+pub(crate) struct JsonObject<'a>(&'a Map<String, Value>);
+
+impl<'a> JsonObject<'a> {
+    pub(crate) fn new(value: &'a Value) -> std::result::Result<Self, ParseError> {
+        match value {
+            Value::Object(object) => Ok(Self(object)),
+            value => Err(invalid_type(value, "a map")),
+        }
+    }
+
+    /// Parses the field with [`FromJson::from_json_value`].
+    pub(crate) fn field<T: FromJson>(&self, key: &str) -> std::result::Result<T, ParseError> {
+        self.parse_field(key, T::from_json_value)
+    }
+
+    /// Parses a field which holds no message or resource with serde.
+    pub(crate) fn deserialize<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> std::result::Result<T, ParseError> {
+        self.parse_field(key, parse_value)
+    }
+
+    fn parse_field<T>(
+        &self,
+        key: &str,
+        parse: impl Fn(&Value) -> std::result::Result<T, ParseError>,
+    ) -> std::result::Result<T, ParseError> {
+        match self.0.get(key) {
+            Some(value) => parse(value).map_err(|e| e.within(key)),
+            // as with serde, an absent field is only allowed if it is optional
+            None => parse(&Value::Null)
+                .map_err(|_| ParseError::at(key, format!("missing field `{}`", key))),
+        }
+    }
+}
+
+fn invalid_type(value: &Value, expected: &str) -> ParseError {
+    let found = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "sequence",
+        Value::Object(_) => "map",
+    };
+    ParseError::new(format!("invalid type: {}, expected {}", found, expected))
+}
+
+/// The signed parts of a message or resource as plain JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawEnvelope {
+    pub metadata: Value,
+    pub data: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_data: Option<Value>,
+    pub signature: String,
+}
+
+/// The fields of a parsed message or resource which its typed view does not carry, so that they
+/// can be restored when signing, verifying or re-serializing.
+///
+/// Equality always holds, so that messages and resources compare by their typed view alone;
+/// see [`UnknownFields`].
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OriginalJson {
+    preserved: Vec<PreservedField>,
+    unknown_fields: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+struct PreservedField {
+    path: Vec<PathSegment>,
+    // fields under `privateData` may be private
+    value: Secret<Value>,
+}
+
+#[derive(Clone, Debug)]
+enum PathSegment {
+    Key(String),
+    /// An array element, found again by its `kind` if that is unique within the array (as for
+    /// payment methods, which may be reordered), or else by its index.
+    Element {
+        index: usize,
+        kind: Option<String>,
+    },
+}
+
+impl PartialEq for OriginalJson {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl OriginalJson {
+    /// Captures the fields of the parsed JSON missing from the typed view re-serialized from it.
+    ///
+    /// Explicit `null`s of unset optional fields are preserved but not reported as unknown.
+    pub(crate) fn capture(raw: RawEnvelope, typed: &RawEnvelope) -> Self {
+        let mut original = Self::default();
+        let mut path = vec![];
+
+        for (key, raw, typed) in [
+            ("metadata", Some(raw.metadata), Some(&typed.metadata)),
+            ("data", Some(raw.data), Some(&typed.data)),
+            ("privateData", raw.private_data, typed.private_data.as_ref()),
+        ] {
+            if let (Some(raw), Some(typed)) = (raw, typed) {
+                path.push(PathSegment::Key(key.to_string()));
+                original.collect(&mut path, raw, typed);
+                path.pop();
+            }
+        }
+
+        original
+    }
+
+    fn collect(&mut self, path: &mut Vec<PathSegment>, raw: Value, typed: &Value) {
+        match (raw, typed) {
+            (Value::Object(raw), Value::Object(typed)) => {
+                for (key, raw_value) in raw {
+                    path.push(PathSegment::Key(key.clone()));
+                    match typed.get(&key) {
+                        Some(typed_value) => self.collect(path, raw_value, typed_value),
+                        None => {
+                            if !raw_value.is_null() {
+                                self.unknown_fields.push(display_path(path));
+                            }
+                            self.preserved.push(PreservedField {
+                                path: path.clone(),
+                                value: raw_value.into(),
+                            });
+                        }
+                    }
+                    path.pop();
+                }
+            }
+            (Value::Array(raw), Value::Array(typed)) => {
+                let kinds: Vec<_> = (0..raw.len())
+                    .map(|i| unique_kind(&raw, i).map(str::to_string))
+                    .collect();
+                for (index, ((raw_value, typed_value), kind)) in
+                    raw.into_iter().zip(typed).zip(kinds).enumerate()
+                {
+                    path.push(PathSegment::Element { index, kind });
+                    self.collect(path, raw_value, typed_value);
+                    path.pop();
+                }
+            }
+            // the raw copy of a modelled value may be private data
+            (mut raw, _) => zeroize_value(&mut raw),
+        }
+    }
+
+    pub(crate) fn unknown_fields(original: Option<&OriginalJson>) -> &[String] {
+        original.map(|o| o.unknown_fields.as_slice()).unwrap_or(&[])
+    }
+
+    /// Restores the preserved fields onto the typed view, wherever their parent object is still
+    /// present and the typed view has not since set the field itself.
+    pub(crate) fn restore(original: Option<&OriginalJson>, typed: RawEnvelope) -> RawEnvelope {
+        let Some(original) = original else {
+            return typed;
+        };
+
+        let mut envelope = typed;
+
+        for field in &original.preserved {
+            let Some((PathSegment::Key(root), rest)) = field.path.split_first() else {
+                continue;
+            };
+            let target = match root.as_str() {
+                "metadata" => Some(&mut envelope.metadata),
+                "data" => Some(&mut envelope.data),
+                "privateData" => envelope.private_data.as_mut(),
+                _ => None,
+            };
+            if let Some(target) = target {
+                insert(target, rest, &field.value);
+            }
+        }
+
+        envelope
+    }
+}
+
+fn insert(target: &mut Value, path: &[PathSegment], value: &Value) {
+    match path {
+        [PathSegment::Key(key)] => {
+            if let Value::Object(object) = target {
+                object.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+        [PathSegment::Key(key), rest @ ..] => {
+            if let Some(child) = target.get_mut(key) {
+                insert(child, rest, value);
+            }
+        }
+        [PathSegment::Element { index, kind }, rest @ ..] => {
+            let child = match (target, kind) {
+                (Value::Array(elements), Some(kind)) => {
+                    let index = (0..elements.len())
+                        .find(|&i| unique_kind(elements, i) == Some(kind.as_str()));
+                    index.map(|i| &mut elements[i])
+                }
+                (Value::Array(elements), None) => elements.get_mut(*index),
+                _ => None,
+            };
+            if let Some(child) = child {
+                insert(child, rest, value);
+            }
+        }
+        [] => {}
+    }
+}
+
+/// The `kind` of the element at `index`, if no other element of the array has the same kind.
+fn unique_kind(elements: &[Value], index: usize) -> Option<&str> {
+    fn kind_of(element: &Value) -> Option<&str> {
+        element.get("kind").and_then(Value::as_str)
+    }
+
+    let kind = kind_of(&elements[index])?;
+    (elements.iter().filter(|e| kind_of(e) == Some(kind)).count() == 1).then_some(kind)
+}
+
+fn display_path(path: &[PathSegment]) -> String {
+    let mut display = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) if display.is_empty() => display.push_str(key),
+            PathSegment::Key(key) => {
+                display.push('.');
+                display.push_str(key);
+            }
+            PathSegment::Element { index, .. } => display.push_str(&format!("[{}]", index)),
+        }
+    }
+    display
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn envelope(metadata: Value, data: Value) -> RawEnvelope {
+        RawEnvelope {
+            metadata,
+            data,
+            private_data: None,
+            signature: String::default(),
+        }
+    }
+
+    #[test]
+    fn joins_paths_of_nested_errors() {
+        #[derive(Debug, Deserialize)]
+        struct Nested;
+
+        impl FromJson for Nested {
+            fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+                Err(match value {
+                    Value::Null => ParseError::new("at least one field is required: none given"),
+                    _ => ParseError::at("inner.field", "invalid value: 1"),
+                })
+            }
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Outer {
+            #[allow(dead_code)]
+            items: Vec<Option<Nested>>,
+        }
+
+        impl FromJson for Outer {
+            fn from_json_value(value: &Value) -> std::result::Result<Self, ParseError> {
+                Ok(Self {
+                    items: JsonObject::new(value)?.field("items")?,
+                })
+            }
+        }
+
+        let error = |json: &str| match Outer::from_json_string(json) {
+            Err(TbdexError::Parse(e)) => e,
+            result => panic!("expected parse error, got {:?}", result),
+        };
+
+        assert_eq!(
+            ParseError::at("items[1].inner.field", "invalid value: 1"),
+            error(r#"{"items": [null, {}]}"#)
+        );
+        assert_eq!(
+            ParseError::at("items", "missing field `items`"),
+            error(r#"{}"#)
+        );
+        assert_eq!(
+            ParseError::at("items", "invalid type: map, expected a sequence"),
+            error(r#"{"items": {}}"#)
+        );
+    }
+
+    #[test]
+    fn captures_unknown_fields() {
+        let raw = envelope(
+            json!({"id": "1", "extra": true, "externalId": null}),
+            json!({"methods": [{"kind": "A", "new": 1}]}),
+        );
+        let typed = envelope(json!({"id": "1"}), json!({"methods": [{"kind": "A"}]}));
+
+        let original = OriginalJson::capture(raw, &typed);
+
+        assert_eq!(
+            vec!["metadata.extra", "data.methods[0].new"],
+            OriginalJson::unknown_fields(Some(&original))
+        );
+    }
+
+    #[test]
+    fn restores_preserved_fields() {
+        let raw = envelope(
+            json!({"id": "1", "extra": true, "externalId": null}),
+            json!({"methods": [{"kind": "A", "new": 1}]}),
+        );
+        let typed = envelope(json!({"id": "1"}), json!({"methods": [{"kind": "A"}]}));
+        let original = OriginalJson::capture(raw.clone(), &typed);
+
+        let restored = OriginalJson::restore(Some(&original), typed);
+        assert_eq!(raw.metadata, restored.metadata);
+        assert_eq!(raw.data, restored.data);
+
+        let changed = envelope(json!({"id": "2"}), json!({"methods": []}));
+        let restored = OriginalJson::restore(Some(&original), changed);
+        assert_eq!(
+            json!({"id": "2", "extra": true, "externalId": null}),
+            restored.metadata
+        );
+        assert_eq!(json!({"methods": []}), restored.data);
+    }
+
+    #[test]
+    fn restores_fields_of_reordered_elements_by_kind() {
+        let raw = envelope(
+            json!({}),
+            json!({"methods": [{"kind": "A", "new": 1}, {"kind": "B", "new": 2}]}),
+        );
+        let typed = envelope(
+            json!({}),
+            json!({"methods": [{"kind": "A"}, {"kind": "B"}]}),
+        );
+        let original = OriginalJson::capture(raw, &typed);
+
+        let reordered = envelope(
+            json!({}),
+            json!({"methods": [{"kind": "C"}, {"kind": "B"}, {"kind": "A"}]}),
+        );
+        let restored = OriginalJson::restore(Some(&original), reordered);
+        assert_eq!(
+            json!({"methods": [{"kind": "C"}, {"kind": "B", "new": 2}, {"kind": "A", "new": 1}]}),
+            restored.data
+        );
+    }
+
+    #[test]
+    fn does_not_restore_fields_removed_from_typed_view() {
+        let raw = RawEnvelope {
+            private_data: Some(json!({"salt": "s", "claims": ["vc"]})),
+            ..envelope(json!({}), json!({}))
+        };
+        let original = OriginalJson::capture(raw.clone(), &raw);
+
+        let redacted = RawEnvelope {
+            private_data: Some(json!({"salt": "s"})),
+            ..envelope(json!({}), json!({}))
+        };
+        let restored = OriginalJson::restore(Some(&original), redacted);
+
+        assert_eq!(Some(json!({"salt": "s"})), restored.private_data);
+    }
+}
@@ -0,0 +1,10 @@
+//! The JSON schemas generated by `build.rs`: one module per supported protocol version, each
+//! holding the schemas of a pinned revision of the tbDEX repository, and the draft 07
+//! meta-schema they are written against.
+//!
+//! The modules are checked in. Builds only check that they match the pinned revisions; set
+//! `TBDEX_REGENERATE_SCHEMAS=1` to download them again.
+
+pub mod draft_07;
+pub mod v1_0;
+pub mod v2_0;
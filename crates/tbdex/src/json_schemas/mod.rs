@@ -1,15 +1,20 @@
 pub mod generated;
 
+#[cfg(feature = "remote-schemas")]
+use crate::http_client::get_json;
 use crate::{
-    errors::{Result, TbdexError},
-    http_client::get_json,
-    json_schemas::generated::DRAFT_07_JSON_SCHEMA,
+    errors::{FieldError, Result, TbdexError},
+    json_schemas::generated::draft_07::DRAFT_07_JSON_SCHEMA,
+    protocol::ProtocolVersion,
+};
+use jsonschema::{
+    error::{TypeKind, ValidationErrorKind},
+    JSONSchema, SchemaResolver, SchemaResolverError, ValidationError,
 };
-use futures::executor::block_on;
-use generated::DEFINITIONS_JSON_SCHEMA;
-use jsonschema::{JSONSchema, SchemaResolver, SchemaResolverError};
 use serde::Serialize;
+use serde_json::Value;
 use std::{collections::HashMap, sync::Arc};
+use url::Url;
 
 struct LocalSchemaResolver {
     schemas: HashMap<String, serde_json::Value>,
@@ -17,10 +22,15 @@ struct LocalSchemaResolver {
 
 impl LocalSchemaResolver {
     fn new() -> Self {
+        Self::with_definitions(ProtocolVersion::CURRENT.message_schema().definitions)
+    }
+
+    /// A resolver serving the given `definitions.json`, which each protocol version has its own of.
+    fn with_definitions(definitions: &str) -> Self {
         let mut schemas = HashMap::new();
         schemas.insert(
             "https://tbdex.dev/definitions.json".to_string(),
-            serde_json::from_str(&DEFINITIONS_JSON_SCHEMA.replace("\\#", "#")).unwrap(),
+            serde_json::from_str(&definitions.replace("\\#", "#")).unwrap(),
         );
         schemas.insert(
             "https://json-schema.org/draft-07/schema".to_string(),
@@ -29,6 +39,14 @@ impl LocalSchemaResolver {
         LocalSchemaResolver { schemas }
     }
 
+    /// A resolver which also serves the given schemas, keyed by their normalized URLs.
+    #[cfg(feature = "remote-schemas")]
+    fn with_schemas(fetched: HashMap<String, serde_json::Value>) -> Self {
+        let mut resolver = Self::new();
+        resolver.schemas.extend(fetched);
+        resolver
+    }
+
     fn normalize_url(url: &url::Url) -> String {
         let mut normalized_url = url.clone();
         normalized_url.set_fragment(None);
@@ -38,6 +56,11 @@ impl LocalSchemaResolver {
         }
         url_str
     }
+
+    fn contains(&self, url: &url::Url) -> bool {
+        self.schemas
+            .contains_key(&LocalSchemaResolver::normalize_url(url))
+    }
 }
 
 impl SchemaResolver for LocalSchemaResolver {
@@ -47,29 +70,175 @@ impl SchemaResolver for LocalSchemaResolver {
         url: &url::Url,
         _original_reference: &str,
     ) -> std::result::Result<std::sync::Arc<serde_json::Value>, SchemaResolverError> {
-        if let Some(schema) = self.schemas.get(&LocalSchemaResolver::normalize_url(url)) {
-            Ok(std::sync::Arc::new(schema.clone()))
-        } else {
-            match block_on(get_json::<serde_json::Value>(url.as_str(), None)) {
-                Ok(schema) => Ok(Arc::new(schema)),
-                Err(err) => Err(SchemaResolverError::new(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    format!("schema not found {}: {}", url, err),
-                ))),
+        match self.schemas.get(&LocalSchemaResolver::normalize_url(url)) {
+            Some(schema) => Ok(Arc::new(schema.clone())),
+            // remote schemas are fetched up front by validate_async, never from here
+            None => Err(SchemaResolverError::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "schema not found {}: remote schemas need validate_async",
+                    url
+                ),
+            ))),
+        }
+    }
+}
+
+/// The URL relative references in the schema resolve against: its `$id`, or else where it was
+/// fetched from.
+fn base_url(schema: &Value, fetched_from: Option<&Url>) -> Option<Url> {
+    schema
+        .get("$id")
+        .and_then(Value::as_str)
+        .and_then(|id| Url::parse(id).ok())
+        .or_else(|| fetched_from.cloned())
+}
+
+/// Collects the http(s) URLs of the schemas referred to by `$ref`s in the value.
+///
+/// `$id`s of subschemas are not tracked, so their relative references resolve against `base`.
+fn collect_remote_refs(value: &Value, base: Option<&Url>, urls: &mut Vec<Url>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => {
+                        if reference.starts_with('#') {
+                            continue;
+                        }
+                        let url = match base {
+                            Some(base) => base.join(reference),
+                            None => Url::parse(reference),
+                        };
+                        if let Ok(url) = url {
+                            if matches!(url.scheme(), "http" | "https") {
+                                urls.push(url);
+                            }
+                        }
+                    }
+                    _ => collect_remote_refs(value, base, urls),
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_remote_refs(value, base, urls);
             }
         }
+        _ => {}
     }
 }
 
-pub fn validate_from_str<T: Serialize>(schema_str: &str, value: &T) -> Result<()> {
-    let schema = &serde_json::from_str::<serde_json::Value>(&schema_str.replace("\\#", "#"))?;
+fn remote_refs(schema: &Value, fetched_from: Option<&Url>) -> Vec<Url> {
+    let mut urls = vec![];
+    collect_remote_refs(schema, base_url(schema, fetched_from).as_ref(), &mut urls);
+    urls
+}
+
+/// Whether everything the schema refers to is available without fetching, so that it can be
+/// validated against without the `remote-schemas` feature.
+pub fn is_local(schema: &Value) -> bool {
+    let resolver = LocalSchemaResolver::new();
+    remote_refs(schema, None)
+        .iter()
+        .all(|url| resolver.contains(url))
+}
+
+/// The most remote schemas fetched to validate against a single schema.
+#[cfg(feature = "remote-schemas")]
+const MAX_REMOTE_SCHEMAS: usize = 16;
 
-    validate(schema, value)?;
+/// The longest chain of remote schemas referring to one another which is followed.
+#[cfg(feature = "remote-schemas")]
+const MAX_REMOTE_SCHEMA_DEPTH: usize = 4;
 
+/// Fetches the remote schemas the schema refers to, directly or through other remote schemas,
+/// keyed by their normalized URLs.
+///
+/// Schemas come from offerings, so only `https` URLs are fetched, and no more than
+/// [`MAX_REMOTE_SCHEMAS`] schemas through chains of at most [`MAX_REMOTE_SCHEMA_DEPTH`].
+#[cfg(feature = "remote-schemas")]
+async fn fetch_remote_refs(schema: &Value) -> Result<HashMap<String, Value>> {
+    let local = LocalSchemaResolver::new();
+    let mut fetched = HashMap::new();
+
+    let mut pending: Vec<(Url, usize)> = remote_refs(schema, None)
+        .into_iter()
+        .map(|url| (url, 1))
+        .collect();
+    while let Some((url, depth)) = pending.pop() {
+        let key = LocalSchemaResolver::normalize_url(&url);
+        if local.contains(&url) || fetched.contains_key(&key) {
+            continue;
+        }
+        check_remote_ref(&url, depth, fetched.len())?;
+
+        tracing::debug!(%url, "fetching remote json schema");
+        let remote = get_json::<Value>(url.as_str(), None)
+            .await
+            .map_err(|e| TbdexError::JsonSchema(format!("schema not found {}: {}", url, e)))?;
+        pending.extend(
+            remote_refs(&remote, Some(&url))
+                .into_iter()
+                .map(|url| (url, depth + 1)),
+        );
+        fetched.insert(key, remote);
+    }
+
+    Ok(fetched)
+}
+
+/// Checks that the remote schema may be fetched, `depth` references away from the schema being
+/// validated against and with `fetched` schemas fetched so far.
+#[cfg(feature = "remote-schemas")]
+fn check_remote_ref(url: &Url, depth: usize, fetched: usize) -> Result<()> {
+    if url.scheme() != "https" {
+        return Err(TbdexError::JsonSchema(format!(
+            "remote schema {} is not served over https",
+            url
+        )));
+    }
+    if depth > MAX_REMOTE_SCHEMA_DEPTH {
+        return Err(TbdexError::JsonSchema(format!(
+            "remote schema {} is nested more than {} references deep",
+            url, MAX_REMOTE_SCHEMA_DEPTH
+        )));
+    }
+    if fetched >= MAX_REMOTE_SCHEMAS {
+        return Err(TbdexError::JsonSchema(format!(
+            "more than {} remote schemas are referred to",
+            MAX_REMOTE_SCHEMAS
+        )));
+    }
     Ok(())
 }
 
-pub fn validate<T: Serialize>(schema: &serde_json::Value, value: &T) -> Result<()> {
+/// A generated schema of a protocol version, along with that version's `definitions.json`
+/// which it refers to.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ProtocolSchema {
+    pub(crate) schema: &'static str,
+    pub(crate) definitions: &'static str,
+}
+
+/// Validates the value against the protocol schema. `metadata` is that of the message or resource
+/// the value belongs to, and is only recorded on the validation span.
+pub(crate) fn validate_from_str<T: Serialize>(
+    schema: ProtocolSchema,
+    value: &T,
+    metadata: &Value,
+) -> Result<()> {
+    let json_schema =
+        &serde_json::from_str::<serde_json::Value>(&schema.schema.replace("\\#", "#"))?;
+
+    let compiled = compile(
+        json_schema,
+        LocalSchemaResolver::with_definitions(schema.definitions),
+    )?;
+    validate_compiled(json_schema, &compiled, value, metadata)
+}
+
+fn compile(schema: &serde_json::Value, resolver: LocalSchemaResolver) -> Result<JSONSchema> {
     if let Some(serde_json::Value::String(url)) = schema.get("$schema") {
         if url.contains("draft-04") || url.contains("draft-06") {
             return Err(TbdexError::JsonSchema(format!(
@@ -79,31 +248,215 @@ pub fn validate<T: Serialize>(schema: &serde_json::Value, value: &T) -> Result<(
         }
     }
 
-    let compiled = JSONSchema::options()
-        .with_resolver(LocalSchemaResolver::new())
+    JSONSchema::options()
+        .with_resolver(resolver)
         .compile(schema)
-        .map_err(|e| TbdexError::JsonSchema(e.to_string()))?;
+        .map_err(|e| TbdexError::JsonSchema(e.to_string()))
+}
 
+/// Checks that the schema compiles, for linting offerings.
+#[cfg(feature = "server")]
+pub fn check_schema(schema: &serde_json::Value) -> Result<()> {
+    compile(schema, LocalSchemaResolver::new())?;
+    Ok(())
+}
+
+/// Validates the value against the schema, first fetching any remote schemas it refers to.
+///
+/// Without the `remote-schemas` feature nothing is fetched, and references to remote schemas fail
+/// to resolve; see [`is_local`].
+pub async fn validate_async<T: Serialize>(
+    schema: &serde_json::Value,
+    value: &T,
+    metadata: &Value,
+) -> Result<()> {
+    #[cfg(feature = "remote-schemas")]
+    let resolver = LocalSchemaResolver::with_schemas(fetch_remote_refs(schema).await?);
+    #[cfg(not(feature = "remote-schemas"))]
+    let resolver = LocalSchemaResolver::new();
+
+    let compiled = compile(schema, resolver)?;
+    validate_compiled(schema, &compiled, value, metadata)
+}
+
+#[tracing::instrument(
+    level = "trace",
+    skip_all,
+    fields(
+        schema = schema.get("$id").and_then(serde_json::Value::as_str),
+        kind = metadata["kind"].as_str(),
+        exchange_id = metadata["exchangeId"].as_str(),
+        pfi_did = crate::messages::pfi_did_of(metadata)
+    )
+)]
+fn validate_compiled<T: Serialize>(
+    schema: &serde_json::Value,
+    compiled: &JSONSchema,
+    value: &T,
+    metadata: &Value,
+) -> Result<()> {
     let instance = serde_json::to_value(value)?;
     let result = compiled.validate(&instance);
 
     if let Err(errors) = result {
         let error_messages = errors
             .map(|e| format!("{} at {}", e, e.instance_path))
-            .collect::<Vec<String>>()
-            .join(", ");
-        return Err(TbdexError::JsonSchema(error_messages));
+            .collect::<Vec<String>>();
+        // the messages quote the invalid values, which may be private data
+        tracing::debug!(
+            error_count = error_messages.len(),
+            "json schema validation failed"
+        );
+        return Err(TbdexError::JsonSchema(error_messages.join(", ")));
     }
 
     Ok(())
 }
 
+/// Validates the value against a fully local schema, returning each violation along with the
+/// field it concerns. An empty result means the value is valid.
+pub fn field_errors<T: Serialize>(schema: &Value, value: &T) -> Result<Vec<FieldError>> {
+    let compiled = compile(schema, LocalSchemaResolver::new())?;
+    let instance = serde_json::to_value(value)?;
+
+    let field_errors = match compiled.validate(&instance) {
+        Ok(()) => vec![],
+        Err(errors) => errors.flat_map(|e| field_errors_of(&e)).collect(),
+    };
+    Ok(field_errors)
+}
+
+fn field_errors_of(error: &ValidationError) -> Vec<FieldError> {
+    let parent = field_path(&error.instance_path.to_string());
+    let field_error = |field: &str, reason: String| FieldError {
+        field: match parent.as_str() {
+            "" => field.to_string(),
+            parent => format!("{}.{}", parent, field),
+        },
+        reason,
+    };
+
+    // point at the missing or unexpected field rather than the object containing it
+    match &error.kind {
+        ValidationErrorKind::Required {
+            property: Value::String(property),
+        } => vec![field_error(property, "is required".to_string())],
+        ValidationErrorKind::AdditionalProperties { unexpected } => unexpected
+            .iter()
+            .map(|property| field_error(property, "is not allowed".to_string()))
+            .collect(),
+        ValidationErrorKind::UnevaluatedProperties { unexpected } => unexpected
+            .iter()
+            .map(|property| field_error(property, "is not allowed".to_string()))
+            .collect(),
+        kind => vec![FieldError {
+            field: parent,
+            reason: reason_of(kind),
+        }],
+    }
+}
+
+/// Describes a validation error by the schema keyword that failed. Unlike the error's `Display`,
+/// which quotes the instance, this never includes the value, which may be private data.
+fn reason_of(kind: &ValidationErrorKind) -> String {
+    match kind {
+        ValidationErrorKind::AdditionalItems { limit } => {
+            format!("must have at most {} items", limit)
+        }
+        ValidationErrorKind::AnyOf | ValidationErrorKind::OneOfNotValid => {
+            "does not match any of the allowed schemas".to_string()
+        }
+        ValidationErrorKind::Constant { expected_value } => format!("must be {}", expected_value),
+        ValidationErrorKind::Contains => "does not contain a matching item".to_string(),
+        ValidationErrorKind::ContentEncoding { content_encoding } => {
+            format!("must be {} encoded", content_encoding)
+        }
+        ValidationErrorKind::ContentMediaType { content_media_type } => {
+            format!("must be of media type {}", content_media_type)
+        }
+        ValidationErrorKind::Enum { options } => format!("must be one of {}", options),
+        ValidationErrorKind::ExclusiveMaximum { limit } => format!("must be less than {}", limit),
+        ValidationErrorKind::ExclusiveMinimum { limit } => {
+            format!("must be greater than {}", limit)
+        }
+        ValidationErrorKind::FalseSchema => "is not allowed".to_string(),
+        ValidationErrorKind::Format { format } => format!("must be a valid {}", format),
+        ValidationErrorKind::MaxItems { limit } => format!("must have at most {} items", limit),
+        ValidationErrorKind::Maximum { limit } => format!("must be at most {}", limit),
+        ValidationErrorKind::MaxLength { limit } => {
+            format!("must be at most {} characters long", limit)
+        }
+        ValidationErrorKind::MaxProperties { limit } => {
+            format!("must have at most {} properties", limit)
+        }
+        ValidationErrorKind::MinItems { limit } => format!("must have at least {} items", limit),
+        ValidationErrorKind::Minimum { limit } => format!("must be at least {}", limit),
+        ValidationErrorKind::MinLength { limit } => {
+            format!("must be at least {} characters long", limit)
+        }
+        ValidationErrorKind::MinProperties { limit } => {
+            format!("must have at least {} properties", limit)
+        }
+        ValidationErrorKind::MultipleOf { multiple_of } => {
+            format!("must be a multiple of {}", multiple_of)
+        }
+        ValidationErrorKind::Not { .. } => "matches a schema it must not".to_string(),
+        ValidationErrorKind::OneOfMultipleValid => {
+            "matches more than one of the allowed schemas".to_string()
+        }
+        ValidationErrorKind::Pattern { pattern } => format!("must match the pattern {}", pattern),
+        ValidationErrorKind::PropertyNames { .. } => "has an invalid property name".to_string(),
+        ValidationErrorKind::Required { property } => format!("{} is required", property),
+        ValidationErrorKind::Type { kind } => match kind {
+            TypeKind::Single(json_type) => format!("must be of type {}", json_type),
+            TypeKind::Multiple(json_types) => format!(
+                "must be of type {}",
+                json_types
+                    .into_iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+        },
+        ValidationErrorKind::UniqueItems => "must not contain duplicate items".to_string(),
+        _ => "does not satisfy the schema".to_string(),
+    }
+}
+
+/// Follows a `$ref` to a definition within the root schema, returning the schema itself if it
+/// has none, or `None` if the reference is to another schema or does not resolve.
+pub(crate) fn resolve_local_ref<'a>(root: &'a Value, schema: &'a Value) -> Option<&'a Value> {
+    match schema.get("$ref").and_then(Value::as_str) {
+        None => Some(schema),
+        Some(reference) => reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer)),
+    }
+}
+
+/// Converts a JSON pointer such as `/address/postalCode` to `address.postalCode`.
+fn field_path(pointer: &str) -> String {
+    pointer
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
 #[cfg(test)]
 mod json_schemas_test {
     use super::*;
+    use crate::json_schemas::generated::v2_0::DEFINITIONS_JSON_SCHEMA;
     use serde_json::json;
     use url::Url;
 
+    /// Validates against a schema without fetching the remote schemas it refers to.
+    fn validate(schema: &Value, value: &Value) -> Result<()> {
+        let compiled = compile(schema, LocalSchemaResolver::new())?;
+        validate_compiled(schema, &compiled, value, &Value::Null)
+    }
+
     #[test]
     fn test_validate_json_schema() {
         let data = json!({
@@ -123,7 +476,7 @@ mod json_schemas_test {
             "required": ["name", "age", "email"]
         });
 
-        assert!(crate::json_schemas::validate(&schema, &data).is_ok());
+        assert!(validate(&schema, &data).is_ok());
     }
 
     #[test]
@@ -176,4 +529,128 @@ mod json_schemas_test {
             serde_json::from_str(&DRAFT_07_JSON_SCHEMA.replace("\\#", "#")).unwrap();
         assert_eq!(resolved_schema.as_ref(), &expected_schema);
     }
+
+    #[test]
+    fn test_remote_refs() {
+        let schema = json!({
+            "$id": "https://pfi.example/schemas/card.json",
+            "type": "object",
+            "properties": {
+                "card": { "$ref": "card-number.json" },
+                "address": { "$ref": "https://schemas.example/address.json#/definitions/us" },
+                "expiry": { "$ref": "#/definitions/expiry" },
+                "items": [{ "$ref": "http://json-schema.org/draft-07/schema#" }]
+            }
+        });
+
+        let mut urls = remote_refs(&schema, None)
+            .iter()
+            .map(|url| url.to_string())
+            .collect::<Vec<_>>();
+        urls.sort();
+
+        assert_eq!(
+            vec![
+                "http://json-schema.org/draft-07/schema#",
+                "https://pfi.example/schemas/card-number.json",
+                "https://schemas.example/address.json#/definitions/us",
+            ],
+            urls
+        );
+    }
+
+    #[test]
+    fn test_is_local() {
+        let local = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "properties": { "amount": { "$ref": "https://tbdex.dev/definitions.json#/definitions/decimalString" } }
+        });
+        assert!(is_local(&local));
+
+        let remote = json!({
+            "properties": { "address": { "$ref": "https://schemas.example/address.json" } }
+        });
+        assert!(!is_local(&remote));
+    }
+
+    #[test]
+    fn test_field_errors() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "accountNumber": { "type": "string" },
+                "address": {
+                    "type": "object",
+                    "properties": { "postalCode": { "type": "string" } },
+                    "required": ["postalCode"]
+                }
+            },
+            "required": ["accountNumber"],
+            "additionalProperties": false
+        });
+
+        let valid = json!({"accountNumber": "1234", "address": {"postalCode": "94107"}});
+        assert!(field_errors(&schema, &valid).unwrap().is_empty());
+
+        let invalid = json!({"address": {"postalCode": 94107}, "iban": "DE89"});
+        let mut fields = field_errors(&schema, &invalid)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.field)
+            .collect::<Vec<_>>();
+        fields.sort();
+        assert_eq!(vec!["accountNumber", "address.postalCode", "iban"], fields);
+    }
+
+    #[test]
+    fn test_field_errors_omit_values() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "accountNumber": { "type": "string", "maxLength": 4 } }
+        });
+
+        let field_errors = field_errors(&schema, &json!({"accountNumber": "0123456789"})).unwrap();
+        assert_eq!(1, field_errors.len());
+        assert_eq!("must be at most 4 characters long", field_errors[0].reason);
+    }
+
+    #[test]
+    fn test_validate_rejects_remote_refs() {
+        let schema = json!({
+            "properties": { "address": { "$ref": "https://schemas.example/address.json" } }
+        });
+
+        let result = validate(&schema, &json!({ "address": "1 Main St" }));
+        assert!(matches!(result, Err(TbdexError::JsonSchema(_))));
+    }
+
+    #[cfg(feature = "remote-schemas")]
+    #[test]
+    fn test_limits_remote_refs() {
+        let url = |url: &str| Url::parse(url).unwrap();
+        let https = url("https://schemas.example/a.json");
+
+        assert!(check_remote_ref(&https, 1, 0).is_ok());
+        for (url, depth, fetched) in [
+            (url("http://schemas.example/a.json"), 1, 0),
+            (url("file:///etc/passwd"), 1, 0),
+            (https.clone(), MAX_REMOTE_SCHEMA_DEPTH + 1, 0),
+            (https, 1, MAX_REMOTE_SCHEMAS),
+        ] {
+            assert!(matches!(
+                check_remote_ref(&url, depth, fetched),
+                Err(TbdexError::JsonSchema(_))
+            ));
+        }
+
+        let schema = json!({
+            "properties": { "address": { "$ref": "http://schemas.example/address.json" } }
+        });
+        let result = futures::executor::block_on(validate_async(
+            &schema,
+            &json!({ "address": "1 Main St" }),
+            &Value::Null,
+        ));
+        assert!(matches!(result, Err(TbdexError::JsonSchema(e)) if e.contains("https")));
+    }
 }
@@ -1,38 +1,25 @@
+pub mod context;
+pub mod digest;
+#[cfg(any(feature = "client", feature = "server"))]
 pub mod http;
+#[cfg(feature = "client")]
 pub mod http_client;
+pub mod journal;
 pub mod messages;
+pub mod metrics;
+pub mod protocol;
+pub mod redact;
 pub mod resources;
 
+#[cfg(test)]
+mod conformance;
+mod decimal;
 pub mod errors;
+mod ids;
 pub mod json;
 mod json_schemas;
 mod signature;
+#[cfg(test)]
+mod test_util;
 
-const DEFAULT_PROTOCOL_VERSION: &str = "2.0";
-
-lazy_static::lazy_static! {
-  pub(crate) static ref LOG_LEVEL: Option<String> = {
-      std::env::var("TBDEX_SDK_LOG_LEVEL").ok()
-  };
-}
-
-pub(crate) mod logging {
-    #[macro_export]
-    macro_rules! log_dbg {
-        ($msg:expr, $($arg:tt)*) => {
-            if let Some(ref level) = *$crate::LOG_LEVEL {
-                if level == "DEBUG" {
-                    println!("[DEBUG] {}", format!($msg, $($arg)*));
-                }
-            }
-        };
-        ($closure:expr) => {
-            if let Some(ref level) = *$crate::LOG_LEVEL {
-                if level == "DEBUG" {
-                    let msg = $closure();
-                    println!("[DEBUG] {}", msg);
-                }
-            }
-        };
-    }
-}
+const DEFAULT_PROTOCOL_VERSION: &str = protocol::ProtocolVersion::CURRENT.as_str();
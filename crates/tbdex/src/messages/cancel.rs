@@ -1,18 +1,25 @@
-use super::{MessageKind, MessageMetadata, Result};
+use super::{order_status::Status, Message, MessageKind, MessageMetadata, Result, TbdexMessage};
 use crate::{
-    json::{FromJson, ToJson},
-    json_schemas::generated::{CANCEL_DATA_JSON_SCHEMA, MESSAGE_JSON_SCHEMA},
+    context::CreationContext,
+    errors::{ParseError, TbdexError},
+    json::{
+        deserialize_envelope, parse_envelope, parse_value, FromJson, OriginalJson, RawEnvelope,
+        ToJson, UnknownFields,
+    },
+    metrics,
+    protocol::ProtocolVersion,
+    resources::offering::Offering,
     DEFAULT_PROTOCOL_VERSION,
 };
-use chrono::Utc;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
 use web5::dids::bearer_did::BearerDid;
 
 /// Represents a Cancel message in the tbDEX protocol.
 ///
 /// A Cancel message is sent by Alice to a PFI to terminate an exchange that has not been completed,
 /// typically when Alice decides to back out of the transaction or request a refund.
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Cancel {
     /// Metadata about the message, including sender, recipient, and protocol information.
     pub metadata: MessageMetadata,
@@ -22,10 +29,77 @@ pub struct Cancel {
 
     /// The signature verifying the authenticity and integrity of the Cancel message.
     pub signature: String,
+
+    /// The JSON as parsed, which preserves fields not modelled above.
+    pub(crate) original: Option<OriginalJson>,
 }
 
 impl ToJson for Cancel {}
-impl FromJson for Cancel {}
+impl FromJson for Cancel {
+    fn from_json_value(value: &serde_json::Value) -> std::result::Result<Self, ParseError> {
+        parse_envelope(value)
+    }
+}
+
+impl UnknownFields for Cancel {
+    fn unknown_fields(&self) -> &[String] {
+        OriginalJson::unknown_fields(self.original.as_ref())
+    }
+}
+
+impl Serialize for Cancel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.preserved_envelope()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cancel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_envelope(deserializer)
+    }
+}
+
+impl TryFrom<RawEnvelope> for Cancel {
+    type Error = ParseError;
+
+    fn try_from(raw: RawEnvelope) -> std::result::Result<Self, ParseError> {
+        let mut cancel = Self {
+            metadata: parse_value(&raw.metadata).map_err(|e| e.within("metadata"))?,
+            data: parse_value(&raw.data).map_err(|e| e.within("data"))?,
+            signature: raw.signature.clone(),
+            original: None,
+        };
+        let typed = cancel
+            .typed_envelope()
+            .map_err(|e| ParseError::new(e.to_string()))?;
+        cancel.original = Some(OriginalJson::capture(raw, &typed));
+        Ok(cancel)
+    }
+}
+
+impl TbdexMessage for Cancel {
+    fn metadata(&self) -> &MessageMetadata {
+        &self.metadata
+    }
+
+    fn metadata_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.metadata)
+    }
+
+    fn data_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.data)
+    }
+
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        Cancel::sign(self, bearer_did)
+    }
+
+    async fn verify(&self) -> Result<()> {
+        Cancel::verify(self).await
+    }
+}
 
 impl Cancel {
     /// Creates a new Cancel message.
@@ -50,26 +124,82 @@ impl Cancel {
         protocol: Option<String>,
         external_id: Option<String>,
     ) -> Result<Self> {
+        Self::create_with_context(
+            to,
+            from,
+            exchange_id,
+            data,
+            protocol,
+            external_id,
+            &CreationContext::default(),
+        )
+    }
+
+    /// Creates a new Cancel message, taking its id and timestamp from the given context.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The DID of the recipient (the PFI).
+    /// * `from` - The DID of the sender (Alice).
+    /// * `exchange_id` - The exchange ID shared between Alice and the PFI.
+    /// * `data` - The data containing the reason for canceling the exchange.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `external_id` - Optional external ID for additional identification.
+    /// * `context` - The clock and id source to create the message with.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Cancel`, as from [`Cancel::create`] with the default context.
+    pub fn create_with_context(
+        to: &str,
+        from: &str,
+        exchange_id: &str,
+        data: &CancelData,
+        protocol: Option<String>,
+        external_id: Option<String>,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        let now = context.now();
+
         let metadata = MessageMetadata {
             from: from.to_string(),
             to: to.to_string(),
             kind: MessageKind::Cancel,
-            id: MessageKind::Cancel.typesafe_id()?,
+            id: context.typesafe_id(&MessageKind::Cancel.to_string(), now)?,
             exchange_id: exchange_id.to_string(),
             external_id,
             protocol: protocol.unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string()),
-            created_at: Utc::now().to_rfc3339(),
+            created_at: now.to_rfc3339(),
         };
 
         let cancel = Self {
             metadata: metadata.clone(),
             data: data.clone(),
             signature: String::default(),
+            original: None,
         };
 
         Ok(cancel)
     }
 
+    /// The typed view as JSON, without the fields preserved from parsing.
+    fn typed_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(RawEnvelope {
+            metadata: serde_json::to_value(&self.metadata)?,
+            data: serde_json::to_value(&self.data)?,
+            private_data: None,
+            signature: self.signature.clone(),
+        })
+    }
+
+    /// The typed view as JSON, with the fields preserved from parsing restored.
+    fn preserved_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(OriginalJson::restore(
+            self.original.as_ref(),
+            self.typed_envelope()?,
+        ))
+    }
+
     /// Signs the Cancel message using the provided Bearer DID.
     ///
     /// # Arguments
@@ -80,11 +210,8 @@ impl Cancel {
     ///
     /// An empty result, or an error if the signing process fails.
     pub fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
-        self.signature = crate::signature::sign(
-            bearer_did,
-            &serde_json::to_value(&self.metadata)?,
-            &serde_json::to_value(&self.data)?,
-        )?;
+        let envelope = self.preserved_envelope()?;
+        self.signature = crate::signature::sign(bearer_did, &envelope.metadata, &envelope.data)?;
         Ok(())
     }
 
@@ -97,21 +224,34 @@ impl Cancel {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub async fn verify(&self) -> Result<()> {
-        // verify resource json schema
-        crate::json_schemas::validate_from_str(MESSAGE_JSON_SCHEMA, self)?;
+        metrics::observe("verify_cancel", self.metadata.pfi_did(), async {
+            let protocol = ProtocolVersion::from_str(&self.metadata.protocol)?;
 
-        // verify data json schema
-        crate::json_schemas::validate_from_str(CANCEL_DATA_JSON_SCHEMA, &self.data)?;
+            // verify id prefixes match the kinds they claim
+            self.metadata.verify_ids()?;
 
-        // verify signature
-        crate::signature::verify(
-            &serde_json::to_value(self.metadata.clone())?,
-            &serde_json::to_value(self.data.clone())?,
-            &self.signature,
-        )
-        .await?;
+            // verify resource json schema
+            let typed = self.typed_envelope()?;
+            crate::json_schemas::validate_from_str(
+                protocol.message_schema(),
+                &typed,
+                &typed.metadata,
+            )?;
 
-        Ok(())
+            // verify data json schema
+            crate::json_schemas::validate_from_str(
+                protocol.message_data_schema(&MessageKind::Cancel)?,
+                &self.data,
+                &typed.metadata,
+            )?;
+
+            // verify signature against the data as parsed, including unmodelled fields
+            let envelope = self.preserved_envelope()?;
+            crate::signature::verify(&envelope.metadata, &envelope.data, &self.signature).await?;
+
+            Ok(())
+        })
+        .await
     }
 }
 
@@ -127,6 +267,123 @@ pub struct CancelData {
 impl FromJson for CancelData {}
 impl ToJson for CancelData {}
 
+/// Whether a Cancel is permitted for an exchange, and the offering's cancellation terms to show
+/// the user either way. Returned by [`cancel_eligibility`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CancelEligibility {
+    /// Why a Cancel is not permitted, or `None` if it is.
+    pub refusal: Option<CancelRefusal>,
+
+    /// The offering's description of its cancellation terms.
+    pub terms: Option<String>,
+
+    /// The offering's URL of a page describing its cancellation terms.
+    pub terms_url: Option<String>,
+}
+
+impl CancelEligibility {
+    pub fn is_permitted(&self) -> bool {
+        self.refusal.is_none()
+    }
+
+    /// An error if a Cancel is not permitted, for PFIs rejecting one.
+    pub fn check(&self) -> Result<()> {
+        match &self.refusal {
+            None => Ok(()),
+            Some(refusal) => Err(TbdexError::CancelNotPermitted(refusal.to_string())),
+        }
+    }
+}
+
+/// Why a Cancel is not permitted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CancelRefusal {
+    /// The offering does not allow cancellation.
+    Disabled,
+    /// The exchange's RFQ is for a different offering.
+    OfferingMismatch,
+    /// There is no Order to cancel yet.
+    NoOrder,
+    /// The exchange has already been cancelled.
+    AlreadyCancelled,
+    /// The exchange has been closed.
+    Closed,
+    /// The order has progressed past the point where it can be unwound.
+    TooLate(Status),
+}
+
+impl fmt::Display for CancelRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CancelRefusal::Disabled => write!(f, "offering does not allow cancellation"),
+            CancelRefusal::OfferingMismatch => write!(f, "exchange is for a different offering"),
+            CancelRefusal::NoOrder => write!(f, "exchange has no order to cancel"),
+            CancelRefusal::AlreadyCancelled => write!(f, "exchange is already cancelled"),
+            CancelRefusal::Closed => write!(f, "exchange is closed"),
+            CancelRefusal::TooLate(status) => write!(f, "order is already {}", status),
+        }
+    }
+}
+
+/// Decides whether Alice may cancel the exchange of the given messages under the offering's
+/// cancellation policy, for wallets deciding whether to offer it and PFIs deciding whether to
+/// honour it.
+///
+/// A Cancel is permitted once there is an Order, and until the exchange is cancelled or closed or
+/// its latest OrderStatus is past the point where it can be unwound: anything other than a payin
+/// which has not expired or a payout which is still pending.
+pub fn cancel_eligibility(offering: &Offering, messages: &[Message]) -> CancelEligibility {
+    let cancellation = &offering.data.cancellation;
+
+    CancelEligibility {
+        refusal: cancel_refusal(offering, messages),
+        terms: cancellation.terms.clone(),
+        terms_url: cancellation.terms_url.clone(),
+    }
+}
+
+fn cancel_refusal(offering: &Offering, messages: &[Message]) -> Option<CancelRefusal> {
+    if !offering.data.cancellation.enabled {
+        return Some(CancelRefusal::Disabled);
+    }
+
+    let mut has_order = false;
+    let mut latest_status = None;
+    for message in messages {
+        match message {
+            Message::Rfq(rfq) if rfq.data.offering_id != offering.metadata.id => {
+                return Some(CancelRefusal::OfferingMismatch);
+            }
+            Message::Order(_) => has_order = true,
+            Message::Cancel(_) => return Some(CancelRefusal::AlreadyCancelled),
+            Message::Close(_) => return Some(CancelRefusal::Closed),
+            Message::OrderStatus(order_status) => latest_status = Some(&order_status.data.status),
+            _ => {}
+        }
+    }
+
+    if !has_order {
+        return Some(CancelRefusal::NoOrder);
+    }
+
+    match latest_status {
+        Some(status) if !refundable(status) => Some(CancelRefusal::TooLate(status.clone())),
+        _ => None,
+    }
+}
+
+/// Whether the order can still be unwound, by refunding its payin if it was made.
+fn refundable(status: &Status) -> bool {
+    matches!(
+        status,
+        Status::PayinPending
+            | Status::PayinInitiated
+            | Status::PayinSettled
+            | Status::PayinFailed
+            | Status::PayoutPending
+    )
+}
+
 #[cfg(test)]
 mod tbdex_test_vectors_protocol {
     use super::*;
@@ -149,3 +406,154 @@ mod tbdex_test_vectors_protocol {
         assert_eq!(test_vector.output, parsed_cancel);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        messages::{
+            close::{Close, CloseData},
+            order::Order,
+            order_status::{OrderStatus, OrderStatusData},
+        },
+        resources::offering::{CancellationDetails, OfferingData, PayinDetails, PayoutDetails},
+    };
+    use std::sync::Arc;
+
+    const WALLET: &str = "did:test:wallet";
+    const PFI: &str = "did:test:pfi";
+
+    fn offering(enabled: bool) -> Offering {
+        Offering::create(
+            PFI,
+            &OfferingData {
+                description: "Selling BTC for USD".to_string(),
+                payout_units_per_payin_unit: "0.00003".to_string(),
+                payin: PayinDetails {
+                    currency_code: "USD".to_string(),
+                    ..Default::default()
+                },
+                payout: PayoutDetails {
+                    currency_code: "BTC".to_string(),
+                    ..Default::default()
+                },
+                required_claims: None,
+                cancellation: CancellationDetails {
+                    enabled,
+                    terms_url: Some("https://pfi.example/cancellation".to_string()),
+                    terms: Some("Cancel any time before payout".to_string()),
+                },
+            },
+            None,
+        )
+        .unwrap()
+    }
+
+    fn ordered(exchange_id: &str) -> Vec<Message> {
+        vec![Message::Order(Arc::new(
+            Order::create(PFI, WALLET, exchange_id, None, None).unwrap(),
+        ))]
+    }
+
+    fn order_status(exchange_id: &str, status: Status) -> Message {
+        Message::OrderStatus(Arc::new(
+            OrderStatus::create(
+                WALLET,
+                PFI,
+                exchange_id,
+                &OrderStatusData {
+                    status,
+                    details: None,
+                },
+                None,
+                None,
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn permits_cancel_until_payout() {
+        let exchange_id = MessageKind::Rfq.typesafe_id().unwrap();
+        let offering = offering(true);
+        let mut messages = ordered(&exchange_id);
+
+        let eligibility = cancel_eligibility(&offering, &messages);
+        assert!(eligibility.is_permitted());
+        assert_eq!(Ok(()), eligibility.check());
+        assert_eq!(
+            Some("Cancel any time before payout".to_string()),
+            eligibility.terms
+        );
+        assert_eq!(
+            Some("https://pfi.example/cancellation".to_string()),
+            eligibility.terms_url
+        );
+
+        messages.push(order_status(&exchange_id, Status::PayinSettled));
+        messages.push(order_status(&exchange_id, Status::PayoutPending));
+        assert!(cancel_eligibility(&offering, &messages).is_permitted());
+
+        messages.push(order_status(&exchange_id, Status::PayoutInitiated));
+        let eligibility = cancel_eligibility(&offering, &messages);
+        assert_eq!(
+            Some(CancelRefusal::TooLate(Status::PayoutInitiated)),
+            eligibility.refusal
+        );
+        assert!(matches!(
+            eligibility.check(),
+            Err(TbdexError::CancelNotPermitted(_))
+        ));
+    }
+
+    #[test]
+    fn refuses_cancel() {
+        let exchange_id = MessageKind::Rfq.typesafe_id().unwrap();
+
+        let eligibility = cancel_eligibility(&offering(false), &ordered(&exchange_id));
+        assert_eq!(Some(CancelRefusal::Disabled), eligibility.refusal);
+        assert!(eligibility.terms.is_some());
+
+        assert_eq!(
+            Some(CancelRefusal::NoOrder),
+            cancel_eligibility(&offering(true), &[]).refusal
+        );
+
+        let mut cancelled = ordered(&exchange_id);
+        cancelled.push(Message::Cancel(Arc::new(
+            Cancel::create(
+                PFI,
+                WALLET,
+                &exchange_id,
+                &CancelData { reason: None },
+                None,
+                None,
+            )
+            .unwrap(),
+        )));
+        assert_eq!(
+            Some(CancelRefusal::AlreadyCancelled),
+            cancel_eligibility(&offering(true), &cancelled).refusal
+        );
+
+        let mut closed = ordered(&exchange_id);
+        closed.push(Message::Close(Arc::new(
+            Close::create(
+                WALLET,
+                PFI,
+                &exchange_id,
+                &CloseData {
+                    reason: None,
+                    success: Some(false),
+                },
+                None,
+                None,
+            )
+            .unwrap(),
+        )));
+        assert_eq!(
+            Some(CancelRefusal::Closed),
+            cancel_eligibility(&offering(true), &closed).refusal
+        );
+    }
+}
@@ -3,14 +3,20 @@ pub mod close;
 pub mod order;
 pub mod order_instructions;
 pub mod order_status;
+pub mod payment_details;
 pub mod quote;
 pub mod rfq;
+pub mod rfq_builder;
+#[cfg(feature = "selective-disclosure")]
+pub mod rfq_disclosure;
 
 use crate::{
-    errors::{Result, TbdexError},
-    json::{FromJson, ToJson},
+    digest::ContentHash,
+    errors::{ParseError, Result, TbdexError},
+    json::{FromJson, ToJson, UnknownFields},
 };
 use cancel::Cancel;
+use chrono::{DateTime, Utc};
 use close::Close;
 use order::Order;
 use order_instructions::OrderInstructions;
@@ -18,11 +24,10 @@ use order_status::OrderStatus;
 use quote::Quote;
 use rfq::Rfq;
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
-use std::{fmt, str::FromStr, sync::Arc};
-use std::sync::atomic::{AtomicU64, Ordering};
-use chrono::Utc;
+use std::{fmt, future::Future, str::FromStr, sync::Arc};
 use type_safe_id::{DynamicType, TypeSafeId};
-use uuid::{NoContext, Timestamp, Uuid};
+use uuid::Uuid;
+use web5::dids::bearer_did::BearerDid;
 
 #[derive(Debug, Default, Deserialize, PartialEq, Serialize, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -49,7 +54,10 @@ impl FromStr for MessageKind {
             "cancel" => Ok(MessageKind::Cancel),
             "orderstatus" => Ok(MessageKind::OrderStatus),
             "close" => Ok(MessageKind::Close),
-            _ => Err(TbdexError::Parse(format!("unknown message kind {}", s))),
+            _ => Err(TbdexError::Parse(ParseError::new(format!(
+                "unknown message kind {}",
+                s
+            )))),
         }
     }
 }
@@ -68,23 +76,66 @@ impl fmt::Display for MessageKind {
     }
 }
 
-static COUNTER: AtomicU64 = AtomicU64::new(0);
-
 impl MessageKind {
+    /// Generates a new id for a message of this kind, e.g. `rfq_01hw...`.
+    ///
+    /// Ids are UUIDv7 based, so they embed their creation time and sort in creation order.
     pub fn typesafe_id(&self) -> Result<String> {
-        let dynamic_type = DynamicType::new(&self.to_string())?;
-        let timestamp_nanos = Utc::now()
-            .timestamp_nanos_opt()
-            .ok_or_else(|| TbdexError::Generic("Failed to get timestamp nanos".to_string()))?;
+        crate::ids::new_typesafe_id(&self.to_string())
+    }
+}
+
+/// A parsed message id, such as `rfq_01hw...`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageId {
+    kind: MessageKind,
+    uuid: Uuid,
+}
+
+impl MessageId {
+    /// Generates a new id for a message of the given kind.
+    pub fn new(kind: MessageKind) -> Self {
+        Self {
+            kind,
+            uuid: crate::ids::new_uuid_v7(),
+        }
+    }
 
-        let seconds = (timestamp_nanos / 1_000_000_000) as u64;
-        let subsec_nanos = (timestamp_nanos % 1_000_000_000) as u32;
+    /// The message kind named by the id's prefix.
+    pub fn kind(&self) -> &MessageKind {
+        &self.kind
+    }
 
-        let count = COUNTER.fetch_add(1, Ordering::SeqCst);
-        let unique_seconds = seconds.wrapping_add(count);
+    pub fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
 
-        let timestamp = Timestamp::from_unix(NoContext, unique_seconds, subsec_nanos);
-        Ok(TypeSafeId::from_type_and_uuid(dynamic_type, Uuid::new_v7(timestamp)).to_string())
+    /// The time the id was generated, to millisecond precision.
+    pub fn timestamp(&self) -> Result<DateTime<Utc>> {
+        crate::ids::uuid_v7_timestamp(&self.uuid)
+    }
+}
+
+impl FromStr for MessageId {
+    type Err = TbdexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (prefix, uuid) = crate::ids::parse_typesafe_id(s)?;
+        let kind = MessageKind::from_str(&prefix)
+            .map_err(|_| TbdexError::TypeId(format!("{} is not a message id", s)))?;
+
+        Ok(Self { kind, uuid })
+    }
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dynamic_type = DynamicType::new(&self.kind.to_string()).map_err(|_| fmt::Error)?;
+        write!(
+            f,
+            "{}",
+            TypeSafeId::from_type_and_uuid(dynamic_type, self.uuid)
+        )
     }
 }
 
@@ -102,7 +153,122 @@ pub struct MessageMetadata {
     pub created_at: String,
 }
 
-#[derive(Serialize, Debug)]
+impl MessageMetadata {
+    /// Parses `id` as a [`MessageId`].
+    pub fn typed_id(&self) -> Result<MessageId> {
+        MessageId::from_str(&self.id)
+    }
+
+    /// Parses `exchange_id`, the id of the RFQ which started the exchange, as a [`MessageId`].
+    pub fn typed_exchange_id(&self) -> Result<MessageId> {
+        MessageId::from_str(&self.exchange_id)
+    }
+
+    /// The DID of the PFI in the exchange: the recipient of the messages wallets send (RFQs,
+    /// Orders and Cancels), and the sender of all others.
+    pub fn pfi_did(&self) -> &str {
+        match self.kind {
+            MessageKind::Rfq | MessageKind::Order | MessageKind::Cancel => &self.to,
+            _ => &self.from,
+        }
+    }
+
+    /// Checks that `id` is prefixed with `kind` and that `exchange_id` is an RFQ id.
+    pub(crate) fn verify_ids(&self) -> Result<()> {
+        let id = self.typed_id()?;
+        if id.kind() != &self.kind {
+            return Err(TbdexError::TypeId(format!(
+                "id {} does not match message kind {}",
+                self.id, self.kind
+            )));
+        }
+
+        let exchange_id = self.typed_exchange_id()?;
+        if exchange_id.kind() != &MessageKind::Rfq {
+            return Err(TbdexError::TypeId(format!(
+                "exchange id {} is not an rfq id",
+                self.exchange_id
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Operations shared by every tbDEX message.
+///
+/// Implemented by each message type as well as by the polymorphic [`Message`] enum and, with the
+/// `client` or `server` feature, the `WalletUpdateMessage` and `ReplyToMessage` enums of the
+/// `http` module, so that storage, logging and routing code can be written once for any message.
+pub trait TbdexMessage {
+    /// The message's metadata.
+    fn metadata(&self) -> &MessageMetadata;
+
+    /// The message's kind.
+    fn kind(&self) -> MessageKind {
+        self.metadata().kind.clone()
+    }
+
+    /// The message's `metadata` as JSON, including any fields preserved from parsing.
+    fn metadata_json(&self) -> Result<serde_json::Value>;
+
+    /// The message's public `data` as JSON, including any fields preserved from parsing.
+    fn data_json(&self) -> Result<serde_json::Value>;
+
+    /// Signs the message using the provided Bearer DID.
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()>;
+
+    /// Verifies the message's JSON schemas and signature.
+    ///
+    /// The future is `Send`, so that verification can be spawned onto a multithreaded runtime.
+    fn verify(&self) -> impl Future<Output = Result<()>> + Send;
+
+    /// The SHA-256 digest of the JCS canonicalized `metadata` and `data`, i.e. the payload
+    /// covered by the signature. See [`crate::digest`].
+    fn digest(&self) -> Result<ContentHash> {
+        crate::digest::content_hash(&self.metadata_json()?, &self.data_json()?)
+    }
+
+    /// The JCS canonicalized `metadata` and `data`, exactly as hashed by [`digest`](Self::digest)
+    /// and signed.
+    fn to_canonical_json(&self) -> Result<String> {
+        crate::digest::canonical_json(&self.metadata_json()?, &self.data_json()?)
+    }
+}
+
+/// Reads the `metadata.kind` of a message serialized as JSON.
+pub(crate) fn message_kind(
+    value: &serde_json::Value,
+) -> std::result::Result<MessageKind, ParseError> {
+    let kind = value
+        .get("metadata")
+        .and_then(|m| m.get("kind"))
+        .ok_or_else(|| ParseError::at("metadata.kind", "missing field `kind`"))?;
+
+    let kind = kind.as_str().ok_or_else(|| {
+        ParseError::at(
+            "metadata.kind",
+            format!("invalid type: {}, expected a string", kind),
+        )
+    })?;
+
+    MessageKind::from_str(kind)
+        .map_err(|_| ParseError::at("metadata.kind", format!("unknown message kind {}", kind)))
+}
+
+/// The DID of the PFI from the JSON metadata of a message or resource; see
+/// [`MessageMetadata::pfi_did`]. Resources are always sent by the PFI.
+pub(crate) fn pfi_did_of(metadata: &serde_json::Value) -> Option<&str> {
+    let kind = metadata["kind"].as_str().map(MessageKind::from_str);
+    match kind {
+        Some(Ok(MessageKind::Rfq | MessageKind::Order | MessageKind::Cancel)) => {
+            metadata["to"].as_str()
+        }
+        _ => metadata["from"].as_str(),
+    }
+}
+
+#[derive(Clone, Serialize, Debug)]
 #[serde(untagged)]
 pub enum Message {
     Rfq(Arc<Rfq>),
@@ -115,7 +281,102 @@ pub enum Message {
 }
 
 impl ToJson for Message {}
-impl FromJson for Message {}
+
+impl FromJson for Message {
+    fn from_json_value(value: &serde_json::Value) -> std::result::Result<Self, ParseError> {
+        Ok(match message_kind(value)? {
+            MessageKind::Rfq => Message::Rfq(Arc::new(Rfq::from_json_value(value)?)),
+            MessageKind::Quote => Message::Quote(Arc::new(Quote::from_json_value(value)?)),
+            MessageKind::Order => Message::Order(Arc::new(Order::from_json_value(value)?)),
+            MessageKind::OrderInstructions => {
+                Message::OrderInstructions(Arc::new(OrderInstructions::from_json_value(value)?))
+            }
+            MessageKind::Cancel => Message::Cancel(Arc::new(Cancel::from_json_value(value)?)),
+            MessageKind::OrderStatus => {
+                Message::OrderStatus(Arc::new(OrderStatus::from_json_value(value)?))
+            }
+            MessageKind::Close => Message::Close(Arc::new(Close::from_json_value(value)?)),
+        })
+    }
+}
+
+impl UnknownFields for Message {
+    fn unknown_fields(&self) -> &[String] {
+        match self {
+            Message::Rfq(rfq) => rfq.unknown_fields(),
+            Message::Quote(quote) => quote.unknown_fields(),
+            Message::Order(order) => order.unknown_fields(),
+            Message::OrderInstructions(order_instructions) => order_instructions.unknown_fields(),
+            Message::Cancel(cancel) => cancel.unknown_fields(),
+            Message::OrderStatus(order_status) => order_status.unknown_fields(),
+            Message::Close(close) => close.unknown_fields(),
+        }
+    }
+}
+
+impl TbdexMessage for Message {
+    fn metadata(&self) -> &MessageMetadata {
+        match self {
+            Message::Rfq(rfq) => &rfq.metadata,
+            Message::Quote(quote) => &quote.metadata,
+            Message::Order(order) => &order.metadata,
+            Message::OrderInstructions(order_instructions) => &order_instructions.metadata,
+            Message::Cancel(cancel) => &cancel.metadata,
+            Message::OrderStatus(order_status) => &order_status.metadata,
+            Message::Close(close) => &close.metadata,
+        }
+    }
+
+    fn metadata_json(&self) -> Result<serde_json::Value> {
+        match self {
+            Message::Rfq(rfq) => rfq.metadata_json(),
+            Message::Quote(quote) => quote.metadata_json(),
+            Message::Order(order) => order.metadata_json(),
+            Message::OrderInstructions(order_instructions) => order_instructions.metadata_json(),
+            Message::Cancel(cancel) => cancel.metadata_json(),
+            Message::OrderStatus(order_status) => order_status.metadata_json(),
+            Message::Close(close) => close.metadata_json(),
+        }
+    }
+
+    fn data_json(&self) -> Result<serde_json::Value> {
+        match self {
+            Message::Rfq(rfq) => rfq.data_json(),
+            Message::Quote(quote) => quote.data_json(),
+            Message::Order(order) => order.data_json(),
+            Message::OrderInstructions(order_instructions) => order_instructions.data_json(),
+            Message::Cancel(cancel) => cancel.data_json(),
+            Message::OrderStatus(order_status) => order_status.data_json(),
+            Message::Close(close) => close.data_json(),
+        }
+    }
+
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        match self {
+            Message::Rfq(rfq) => Arc::make_mut(rfq).sign(bearer_did),
+            Message::Quote(quote) => Arc::make_mut(quote).sign(bearer_did),
+            Message::Order(order) => Arc::make_mut(order).sign(bearer_did),
+            Message::OrderInstructions(order_instructions) => {
+                Arc::make_mut(order_instructions).sign(bearer_did)
+            }
+            Message::Cancel(cancel) => Arc::make_mut(cancel).sign(bearer_did),
+            Message::OrderStatus(order_status) => Arc::make_mut(order_status).sign(bearer_did),
+            Message::Close(close) => Arc::make_mut(close).sign(bearer_did),
+        }
+    }
+
+    async fn verify(&self) -> Result<()> {
+        match self {
+            Message::Rfq(rfq) => rfq.verify().await,
+            Message::Quote(quote) => quote.verify().await,
+            Message::Order(order) => order.verify().await,
+            Message::OrderInstructions(order_instructions) => order_instructions.verify().await,
+            Message::Cancel(cancel) => cancel.verify().await,
+            Message::OrderStatus(order_status) => order_status.verify().await,
+            Message::Close(close) => close.verify().await,
+        }
+    }
+}
 
 impl<'de> Deserialize<'de> for Message {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
@@ -139,83 +400,7 @@ impl<'de> Deserialize<'de> for Message {
             {
                 let value: serde_json::Value = Deserialize::deserialize(deserializer)?;
 
-                let kind_str = value
-                    .get("metadata")
-                    .and_then(|m| m.get("kind"))
-                    .and_then(|k| k.as_str());
-
-                match kind_str {
-                    Some(k) => match MessageKind::from_str(k) {
-                        Ok(kind) => match kind {
-                            MessageKind::Rfq => {
-                                if let Ok(rfq) = serde_json::from_value::<Rfq>(value.clone()) {
-                                    Ok(Message::Rfq(Arc::new(rfq)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize rfq"))
-                                }
-                            }
-                            MessageKind::Quote => {
-                                if let Ok(quote) = serde_json::from_value::<Quote>(value.clone()) {
-                                    Ok(Message::Quote(Arc::new(quote)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize quote"))
-                                }
-                            }
-                            MessageKind::Order => {
-                                if let Ok(order) = serde_json::from_value::<Order>(value.clone()) {
-                                    Ok(Message::Order(Arc::new(order)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize order"))
-                                }
-                            }
-                            MessageKind::OrderInstructions => {
-                                if let Ok(order_instructions) =
-                                    serde_json::from_value::<OrderInstructions>(value.clone())
-                                {
-                                    Ok(Message::OrderInstructions(Arc::new(order_instructions)))
-                                } else {
-                                    Err(serde::de::Error::custom(
-                                        "failed to deserialize order_instructions",
-                                    ))
-                                }
-                            }
-                            MessageKind::Cancel => {
-                                if let Ok(cancel) = serde_json::from_value::<Cancel>(value.clone())
-                                {
-                                    Ok(Message::Cancel(Arc::new(cancel)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize cancel"))
-                                }
-                            }
-                            MessageKind::OrderStatus => {
-                                if let Ok(order_status) =
-                                    serde_json::from_value::<OrderStatus>(value.clone())
-                                {
-                                    Ok(Message::OrderStatus(Arc::new(order_status)))
-                                } else {
-                                    Err(serde::de::Error::custom(
-                                        "failed to deserialize order_status",
-                                    ))
-                                }
-                            }
-                            MessageKind::Close => {
-                                if let Ok(close) = serde_json::from_value::<Close>(value.clone()) {
-                                    Ok(Message::Close(Arc::new(close)))
-                                } else {
-                                    Err(serde::de::Error::custom("failed to deserialize close"))
-                                }
-                            }
-                        },
-                        Err(_) => Err(serde::de::Error::custom(format!(
-                            "unexpected message kind {}",
-                            k
-                        ))),
-                    },
-                    None => Err(serde::de::Error::custom(format!(
-                        "unexpected message kind {:?}",
-                        kind_str
-                    ))),
-                }
+                Message::from_json_value(&value).map_err(serde::de::Error::custom)
             }
 
             fn visit_none<E>(self) -> std::result::Result<Self::Value, E>
@@ -229,3 +414,171 @@ impl<'de> Deserialize<'de> for Message {
         deserializer.deserialize_option(MessageVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{exchange_id, signed_close};
+    use web5::dids::methods::did_jwk::DidJwk;
+
+    #[test]
+    fn can_use_messages_generically() {
+        let bearer_did = DidJwk::create(None).unwrap();
+        let close = signed_close(&bearer_did, &exchange_id(), "done");
+
+        let mut message = Message::Close(Arc::new(close.clone()));
+        assert_eq!(MessageKind::Close, message.kind());
+        assert_eq!(&close.metadata, TbdexMessage::metadata(&message));
+        assert_eq!(close.digest().unwrap(), message.digest().unwrap());
+        futures::executor::block_on(message.verify()).unwrap();
+
+        let other_did = DidJwk::create(None).unwrap();
+        message.sign(&other_did).unwrap();
+        assert!(futures::executor::block_on(message.verify()).is_err());
+    }
+
+    #[test]
+    fn preserves_unknown_fields() {
+        let bearer_did = DidJwk::create(None).unwrap();
+        let close = signed_close(&bearer_did, &exchange_id(), "done");
+
+        // a newer sender adds fields this crate does not model, and signs over them
+        let mut value = serde_json::to_value(&close).unwrap();
+        value["metadata"]["newMetadataField"] = serde_json::json!("x");
+        value["data"]["newDataField"] = serde_json::json!({"nested": true});
+        value["signature"] = serde_json::json!(crate::signature::sign(
+            &bearer_did,
+            &value["metadata"],
+            &value["data"]
+        )
+        .unwrap());
+        let json = value.to_string();
+
+        let parsed = Close::from_json_string(&json).unwrap();
+        assert_eq!(
+            vec!["metadata.newMetadataField", "data.newDataField"],
+            parsed.unknown_fields()
+        );
+        futures::executor::block_on(parsed.verify()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::to_value(Message::from_json_string(&json).unwrap()).unwrap()
+        );
+
+        // changes to the typed view are still caught
+        let mut tampered = parsed.clone();
+        tampered.data.reason = Some("tampered".to_string());
+        assert!(futures::executor::block_on(tampered.verify()).is_err());
+
+        assert!(matches!(
+            Close::from_json_string_strict(&json),
+            Err(TbdexError::UnknownFields(_))
+        ));
+        assert!(Close::from_json_string_strict(&close.to_json_string().unwrap()).is_ok());
+    }
+
+    fn parse_error<T: FromJson>(json: &serde_json::Value) -> ParseError {
+        match T::from_json_string(&json.to_string()) {
+            Err(TbdexError::Parse(e)) => e,
+            Err(e) => panic!("expected parse error, got {:?}", e),
+            Ok(_) => panic!("expected parse error"),
+        }
+    }
+
+    #[test]
+    fn reports_path_to_invalid_fields() {
+        let bearer_did = DidJwk::create(None).unwrap();
+        let close =
+            serde_json::to_value(signed_close(&bearer_did, &exchange_id(), "done")).unwrap();
+
+        let mut missing = close.clone();
+        missing["metadata"]
+            .as_object_mut()
+            .unwrap()
+            .remove("createdAt");
+        let error = parse_error::<Close>(&missing);
+        assert_eq!(Some("metadata.createdAt".to_string()), error.path);
+        assert_eq!("missing field `createdAt`", error.reason);
+        assert_eq!(error, parse_error::<Message>(&missing));
+
+        let mut wrong_type = close.clone();
+        wrong_type["data"]["success"] = serde_json::json!("yes");
+        let error = parse_error::<Message>(&wrong_type);
+        assert_eq!(Some("data.success".to_string()), error.path);
+        assert!(error.reason.starts_with("invalid type"));
+
+        let mut unknown_kind = close.clone();
+        unknown_kind["metadata"]["kind"] = serde_json::json!("invoice");
+        let error = parse_error::<Message>(&unknown_kind);
+        assert_eq!(Some("metadata.kind".to_string()), error.path);
+    }
+
+    #[cfg(any(feature = "client", feature = "server"))]
+    #[test]
+    fn reports_path_to_invalid_fields_in_bodies() {
+        let bearer_did = DidJwk::create(None).unwrap();
+        let close =
+            serde_json::to_value(signed_close(&bearer_did, &exchange_id(), "done")).unwrap();
+
+        let mut missing = close.clone();
+        missing["metadata"]
+            .as_object_mut()
+            .unwrap()
+            .remove("createdAt");
+
+        let body = serde_json::json!({ "data": [close, missing] });
+        let error = parse_error::<crate::http::exchanges::GetExchangeResponseBody>(&body);
+        assert_eq!(Some("data[1].metadata.createdAt".to_string()), error.path);
+    }
+
+    #[test]
+    fn parses_message_ids() {
+        let id = MessageKind::Quote.typesafe_id().unwrap();
+        let parsed = MessageId::from_str(&id).unwrap();
+
+        assert_eq!(&MessageKind::Quote, parsed.kind());
+        assert_eq!(id, parsed.to_string());
+        assert!(parsed.timestamp().unwrap() <= Utc::now() + chrono::Duration::seconds(1));
+
+        let offering_id = crate::resources::ResourceKind::Offering
+            .typesafe_id()
+            .unwrap();
+        assert!(MessageId::from_str(&offering_id).is_err());
+    }
+
+    #[test]
+    fn verify_checks_id_prefixes() {
+        let bearer_did = DidJwk::create(None).unwrap();
+
+        let mut close = signed_close(&bearer_did, &exchange_id(), "done");
+        close.metadata.id = MessageKind::Quote.typesafe_id().unwrap();
+        close.sign(&bearer_did).unwrap();
+        assert!(matches!(
+            futures::executor::block_on(close.verify()),
+            Err(TbdexError::TypeId(_))
+        ));
+
+        let mut close = signed_close(&bearer_did, &exchange_id(), "done");
+        close.metadata.exchange_id = close.metadata.id.clone();
+        close.sign(&bearer_did).unwrap();
+        assert!(matches!(
+            futures::executor::block_on(close.verify()),
+            Err(TbdexError::TypeId(_))
+        ));
+    }
+
+    #[test]
+    fn digest_covers_metadata_and_data() {
+        let bearer_did = DidJwk::create(None).unwrap();
+        let close = signed_close(&bearer_did, &exchange_id(), "done");
+
+        let mut changed = close.clone();
+        changed.data.reason = Some("changed".to_string());
+
+        assert_ne!(close.digest().unwrap(), changed.digest().unwrap());
+        assert_eq!(
+            close.digest().unwrap(),
+            ContentHash::of(&close.to_canonical_json().unwrap())
+        );
+    }
+}
@@ -1,18 +1,24 @@
-use super::{MessageKind, MessageMetadata, Result};
+use super::{MessageKind, MessageMetadata, Result, TbdexMessage};
 use crate::{
-    json::{FromJson, ToJson},
-    json_schemas::generated::{MESSAGE_JSON_SCHEMA, ORDER_DATA_JSON_SCHEMA},
+    context::CreationContext,
+    errors::ParseError,
+    json::{
+        deserialize_envelope, parse_envelope, parse_value, FromJson, OriginalJson, RawEnvelope,
+        ToJson, UnknownFields,
+    },
+    metrics,
+    protocol::ProtocolVersion,
     DEFAULT_PROTOCOL_VERSION,
 };
-use chrono::Utc;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
 use web5::dids::bearer_did::BearerDid;
 
 /// Represents an Order message in the tbDEX protocol.
 ///
 /// An Order message is sent by Alice to a PFI to execute a transaction based on a previously provided quote.
 /// It includes metadata about the message and the signature to ensure its integrity and authenticity.
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Order {
     /// Metadata about the message, including sender, recipient, and protocol information.
     pub metadata: MessageMetadata,
@@ -22,10 +28,77 @@ pub struct Order {
 
     /// The signature verifying the authenticity and integrity of the Order message.
     pub signature: String,
+
+    /// The JSON as parsed, which preserves fields not modelled above.
+    pub(crate) original: Option<OriginalJson>,
 }
 
 impl ToJson for Order {}
-impl FromJson for Order {}
+impl FromJson for Order {
+    fn from_json_value(value: &serde_json::Value) -> std::result::Result<Self, ParseError> {
+        parse_envelope(value)
+    }
+}
+
+impl UnknownFields for Order {
+    fn unknown_fields(&self) -> &[String] {
+        OriginalJson::unknown_fields(self.original.as_ref())
+    }
+}
+
+impl Serialize for Order {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.preserved_envelope()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Order {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_envelope(deserializer)
+    }
+}
+
+impl TryFrom<RawEnvelope> for Order {
+    type Error = ParseError;
+
+    fn try_from(raw: RawEnvelope) -> std::result::Result<Self, ParseError> {
+        let mut order = Self {
+            metadata: parse_value(&raw.metadata).map_err(|e| e.within("metadata"))?,
+            data: parse_value(&raw.data).map_err(|e| e.within("data"))?,
+            signature: raw.signature.clone(),
+            original: None,
+        };
+        let typed = order
+            .typed_envelope()
+            .map_err(|e| ParseError::new(e.to_string()))?;
+        order.original = Some(OriginalJson::capture(raw, &typed));
+        Ok(order)
+    }
+}
+
+impl TbdexMessage for Order {
+    fn metadata(&self) -> &MessageMetadata {
+        &self.metadata
+    }
+
+    fn metadata_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.metadata)
+    }
+
+    fn data_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.data)
+    }
+
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        Order::sign(self, bearer_did)
+    }
+
+    async fn verify(&self) -> Result<()> {
+        Order::verify(self).await
+    }
+}
 
 impl Order {
     /// Creates a new Order message.
@@ -48,15 +121,49 @@ impl Order {
         protocol: Option<String>,
         external_id: Option<String>,
     ) -> Result<Self> {
+        Self::create_with_context(
+            to,
+            from,
+            exchange_id,
+            protocol,
+            external_id,
+            &CreationContext::default(),
+        )
+    }
+
+    /// Creates a new Order message, taking its id and timestamp from the given context.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The DID of the recipient (the PFI).
+    /// * `from` - The DID of the sender (Alice).
+    /// * `exchange_id` - The exchange ID shared between Alice and the PFI.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `external_id` - Optional external ID for additional identification.
+    /// * `context` - The clock and id source to create the message with.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Order`, as from [`Order::create`] with the default context.
+    pub fn create_with_context(
+        to: &str,
+        from: &str,
+        exchange_id: &str,
+        protocol: Option<String>,
+        external_id: Option<String>,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        let now = context.now();
+
         let metadata = MessageMetadata {
             from: from.to_string(),
             to: to.to_string(),
             kind: MessageKind::Order,
-            id: MessageKind::Order.typesafe_id()?,
+            id: context.typesafe_id(&MessageKind::Order.to_string(), now)?,
             exchange_id: exchange_id.to_string(),
             external_id,
             protocol: protocol.unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string()),
-            created_at: Utc::now().to_rfc3339(),
+            created_at: now.to_rfc3339(),
         };
 
         let data = OrderData {};
@@ -65,11 +172,30 @@ impl Order {
             metadata: metadata.clone(),
             data: data.clone(),
             signature: String::default(),
+            original: None,
         };
 
         Ok(order)
     }
 
+    /// The typed view as JSON, without the fields preserved from parsing.
+    fn typed_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(RawEnvelope {
+            metadata: serde_json::to_value(&self.metadata)?,
+            data: serde_json::to_value(&self.data)?,
+            private_data: None,
+            signature: self.signature.clone(),
+        })
+    }
+
+    /// The typed view as JSON, with the fields preserved from parsing restored.
+    fn preserved_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(OriginalJson::restore(
+            self.original.as_ref(),
+            self.typed_envelope()?,
+        ))
+    }
+
     /// Signs the Order message using the provided Bearer DID.
     ///
     /// # Arguments
@@ -80,11 +206,8 @@ impl Order {
     ///
     /// An empty result, or an error if the signing process fails.
     pub fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
-        self.signature = crate::signature::sign(
-            bearer_did,
-            &serde_json::to_value(&self.metadata)?,
-            &serde_json::to_value(&self.data)?,
-        )?;
+        let envelope = self.preserved_envelope()?;
+        self.signature = crate::signature::sign(bearer_did, &envelope.metadata, &envelope.data)?;
         Ok(())
     }
 
@@ -97,21 +220,34 @@ impl Order {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub async fn verify(&self) -> Result<()> {
-        // verify resource json schema
-        crate::json_schemas::validate_from_str(MESSAGE_JSON_SCHEMA, self)?;
+        metrics::observe("verify_order", self.metadata.pfi_did(), async {
+            let protocol = ProtocolVersion::from_str(&self.metadata.protocol)?;
 
-        // verify data json schema
-        crate::json_schemas::validate_from_str(ORDER_DATA_JSON_SCHEMA, &self.data)?;
+            // verify id prefixes match the kinds they claim
+            self.metadata.verify_ids()?;
 
-        // verify signature
-        crate::signature::verify(
-            &serde_json::to_value(self.metadata.clone())?,
-            &serde_json::to_value(&OrderData {})?,
-            &self.signature,
-        )
-        .await?;
+            // verify resource json schema
+            let typed = self.typed_envelope()?;
+            crate::json_schemas::validate_from_str(
+                protocol.message_schema(),
+                &typed,
+                &typed.metadata,
+            )?;
 
-        Ok(())
+            // verify data json schema
+            crate::json_schemas::validate_from_str(
+                protocol.message_data_schema(&MessageKind::Order)?,
+                &self.data,
+                &typed.metadata,
+            )?;
+
+            // verify signature against the data as parsed, including unmodelled fields
+            let envelope = self.preserved_envelope()?;
+            crate::signature::verify(&envelope.metadata, &envelope.data, &self.signature).await?;
+
+            Ok(())
+        })
+        .await
     }
 }
 
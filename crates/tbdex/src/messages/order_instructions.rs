@@ -1,18 +1,24 @@
-use super::{MessageKind, MessageMetadata, Result};
+use super::{MessageKind, MessageMetadata, Result, TbdexMessage};
 use crate::{
-    json::{FromJson, ToJson},
-    json_schemas::generated::{MESSAGE_JSON_SCHEMA, ORDER_INSTRUCTIONS_DATA_JSON_SCHEMA},
+    context::CreationContext,
+    errors::ParseError,
+    json::{
+        deserialize_envelope, parse_envelope, parse_value, FromJson, OriginalJson, RawEnvelope,
+        ToJson, UnknownFields,
+    },
+    metrics,
+    protocol::ProtocolVersion,
     DEFAULT_PROTOCOL_VERSION,
 };
-use chrono::Utc;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
 use web5::dids::bearer_did::BearerDid;
 
 /// Represents an Order Instructions message in the tbDEX protocol.
 ///
 /// An Order Instructions message is sent from a PFI to Alice, providing
 /// detailed instructions on how to make a payin or receive a payout.
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct OrderInstructions {
     /// Metadata about the message, including sender, recipient, and protocol information.
     pub metadata: MessageMetadata,
@@ -22,10 +28,77 @@ pub struct OrderInstructions {
 
     /// The signature verifying the authenticity and integrity of the Order Instructions message.
     pub signature: String,
+
+    /// The JSON as parsed, which preserves fields not modelled above.
+    pub(crate) original: Option<OriginalJson>,
 }
 
 impl ToJson for OrderInstructions {}
-impl FromJson for OrderInstructions {}
+impl FromJson for OrderInstructions {
+    fn from_json_value(value: &serde_json::Value) -> std::result::Result<Self, ParseError> {
+        parse_envelope(value)
+    }
+}
+
+impl UnknownFields for OrderInstructions {
+    fn unknown_fields(&self) -> &[String] {
+        OriginalJson::unknown_fields(self.original.as_ref())
+    }
+}
+
+impl Serialize for OrderInstructions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.preserved_envelope()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OrderInstructions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_envelope(deserializer)
+    }
+}
+
+impl TryFrom<RawEnvelope> for OrderInstructions {
+    type Error = ParseError;
+
+    fn try_from(raw: RawEnvelope) -> std::result::Result<Self, ParseError> {
+        let mut order_instructions = Self {
+            metadata: parse_value(&raw.metadata).map_err(|e| e.within("metadata"))?,
+            data: parse_value(&raw.data).map_err(|e| e.within("data"))?,
+            signature: raw.signature.clone(),
+            original: None,
+        };
+        let typed = order_instructions
+            .typed_envelope()
+            .map_err(|e| ParseError::new(e.to_string()))?;
+        order_instructions.original = Some(OriginalJson::capture(raw, &typed));
+        Ok(order_instructions)
+    }
+}
+
+impl TbdexMessage for OrderInstructions {
+    fn metadata(&self) -> &MessageMetadata {
+        &self.metadata
+    }
+
+    fn metadata_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.metadata)
+    }
+
+    fn data_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.data)
+    }
+
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        OrderInstructions::sign(self, bearer_did)
+    }
+
+    async fn verify(&self) -> Result<()> {
+        OrderInstructions::verify(self).await
+    }
+}
 
 impl OrderInstructions {
     /// Creates a new Order Instructions message.
@@ -50,26 +123,82 @@ impl OrderInstructions {
         protocol: Option<String>,
         external_id: Option<String>,
     ) -> Result<Self> {
+        Self::create_with_context(
+            to,
+            from,
+            exchange_id,
+            data,
+            protocol,
+            external_id,
+            &CreationContext::default(),
+        )
+    }
+
+    /// Creates a new OrderInstructions message, taking its id and timestamp from the given context.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The DID of the recipient (Alice).
+    /// * `from` - The DID of the sender (the PFI).
+    /// * `exchange_id` - The exchange ID shared between Alice and the PFI.
+    /// * `data` - The data containing payment instructions for payin and payout.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `external_id` - Optional external ID for additional identification.
+    /// * `context` - The clock and id source to create the message with.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `OrderInstructions`, as from [`OrderInstructions::create`] with the default context.
+    pub fn create_with_context(
+        to: &str,
+        from: &str,
+        exchange_id: &str,
+        data: &OrderInstructionsData,
+        protocol: Option<String>,
+        external_id: Option<String>,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        let now = context.now();
+
         let metadata = MessageMetadata {
             from: from.to_string(),
             to: to.to_string(),
             kind: MessageKind::OrderInstructions,
-            id: MessageKind::OrderInstructions.typesafe_id()?,
+            id: context.typesafe_id(&MessageKind::OrderInstructions.to_string(), now)?,
             exchange_id: exchange_id.to_string(),
             external_id,
             protocol: protocol.unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string()),
-            created_at: Utc::now().to_rfc3339(),
+            created_at: now.to_rfc3339(),
         };
 
         let order_instructions = Self {
             metadata: metadata.clone(),
             data: data.clone(),
             signature: String::default(),
+            original: None,
         };
 
         Ok(order_instructions)
     }
 
+    /// The typed view as JSON, without the fields preserved from parsing.
+    fn typed_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(RawEnvelope {
+            metadata: serde_json::to_value(&self.metadata)?,
+            data: serde_json::to_value(&self.data)?,
+            private_data: None,
+            signature: self.signature.clone(),
+        })
+    }
+
+    /// The typed view as JSON, with the fields preserved from parsing restored.
+    fn preserved_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(OriginalJson::restore(
+            self.original.as_ref(),
+            self.typed_envelope()?,
+        ))
+    }
+
     /// Signs the Order Instructions message using the provided Bearer DID.
     ///
     /// # Arguments
@@ -80,11 +209,8 @@ impl OrderInstructions {
     ///
     /// An empty result, or an error if the signing process fails.
     pub fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
-        self.signature = crate::signature::sign(
-            bearer_did,
-            &serde_json::to_value(&self.metadata)?,
-            &serde_json::to_value(&self.data)?,
-        )?;
+        let envelope = self.preserved_envelope()?;
+        self.signature = crate::signature::sign(bearer_did, &envelope.metadata, &envelope.data)?;
         Ok(())
     }
 
@@ -97,21 +223,39 @@ impl OrderInstructions {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub async fn verify(&self) -> Result<()> {
-        // verify resource json schema
-        crate::json_schemas::validate_from_str(MESSAGE_JSON_SCHEMA, self)?;
+        metrics::observe(
+            "verify_order_instructions",
+            self.metadata.pfi_did(),
+            async {
+                let protocol = ProtocolVersion::from_str(&self.metadata.protocol)?;
 
-        // verify data json schema
-        crate::json_schemas::validate_from_str(ORDER_INSTRUCTIONS_DATA_JSON_SCHEMA, &self.data)?;
+                // verify id prefixes match the kinds they claim
+                self.metadata.verify_ids()?;
 
-        // verify signature
-        crate::signature::verify(
-            &serde_json::to_value(self.metadata.clone())?,
-            &serde_json::to_value(self.data.clone())?,
-            &self.signature,
-        )
-        .await?;
+                // verify resource json schema
+                let typed = self.typed_envelope()?;
+                crate::json_schemas::validate_from_str(
+                    protocol.message_schema(),
+                    &typed,
+                    &typed.metadata,
+                )?;
 
-        Ok(())
+                // verify data json schema
+                crate::json_schemas::validate_from_str(
+                    protocol.message_data_schema(&MessageKind::OrderInstructions)?,
+                    &self.data,
+                    &typed.metadata,
+                )?;
+
+                // verify signature against the data as parsed, including unmodelled fields
+                let envelope = self.preserved_envelope()?;
+                crate::signature::verify(&envelope.metadata, &envelope.data, &self.signature)
+                    .await?;
+
+                Ok(())
+            },
+        )
+        .await
     }
 }
 
@@ -1,21 +1,25 @@
 use std::{fmt, str::FromStr};
 
-use super::{MessageKind, MessageMetadata, Result};
+use super::{MessageKind, MessageMetadata, Result, TbdexMessage};
 use crate::{
-    errors::TbdexError,
-    json::{FromJson, ToJson},
-    json_schemas::generated::{MESSAGE_JSON_SCHEMA, ORDER_STATUS_DATA_JSON_SCHEMA},
+    context::CreationContext,
+    errors::{ParseError, TbdexError},
+    json::{
+        deserialize_envelope, parse_envelope, parse_value, FromJson, OriginalJson, RawEnvelope,
+        ToJson, UnknownFields,
+    },
+    metrics,
+    protocol::ProtocolVersion,
     DEFAULT_PROTOCOL_VERSION,
 };
-use chrono::Utc;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use web5::dids::bearer_did::BearerDid;
 
 /// Represents an Order Status message in the tbDEX protocol.
 ///
 /// An Order Status message is sent from a PFI to Alice to communicate
 /// the current status of an ongoing order or exchange process.
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct OrderStatus {
     /// Metadata about the message, including sender, recipient, and protocol information.
     pub metadata: MessageMetadata,
@@ -25,10 +29,77 @@ pub struct OrderStatus {
 
     /// The signature verifying the authenticity and integrity of the Order Status message.
     pub signature: String,
+
+    /// The JSON as parsed, which preserves fields not modelled above.
+    pub(crate) original: Option<OriginalJson>,
 }
 
 impl ToJson for OrderStatus {}
-impl FromJson for OrderStatus {}
+impl FromJson for OrderStatus {
+    fn from_json_value(value: &serde_json::Value) -> std::result::Result<Self, ParseError> {
+        parse_envelope(value)
+    }
+}
+
+impl UnknownFields for OrderStatus {
+    fn unknown_fields(&self) -> &[String] {
+        OriginalJson::unknown_fields(self.original.as_ref())
+    }
+}
+
+impl Serialize for OrderStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.preserved_envelope()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OrderStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_envelope(deserializer)
+    }
+}
+
+impl TryFrom<RawEnvelope> for OrderStatus {
+    type Error = ParseError;
+
+    fn try_from(raw: RawEnvelope) -> std::result::Result<Self, ParseError> {
+        let mut order_status = Self {
+            metadata: parse_value(&raw.metadata).map_err(|e| e.within("metadata"))?,
+            data: parse_value(&raw.data).map_err(|e| e.within("data"))?,
+            signature: raw.signature.clone(),
+            original: None,
+        };
+        let typed = order_status
+            .typed_envelope()
+            .map_err(|e| ParseError::new(e.to_string()))?;
+        order_status.original = Some(OriginalJson::capture(raw, &typed));
+        Ok(order_status)
+    }
+}
+
+impl TbdexMessage for OrderStatus {
+    fn metadata(&self) -> &MessageMetadata {
+        &self.metadata
+    }
+
+    fn metadata_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.metadata)
+    }
+
+    fn data_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.data)
+    }
+
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        OrderStatus::sign(self, bearer_did)
+    }
+
+    async fn verify(&self) -> Result<()> {
+        OrderStatus::verify(self).await
+    }
+}
 
 impl OrderStatus {
     /// Creates a new Order Status message.
@@ -53,26 +124,82 @@ impl OrderStatus {
         protocol: Option<String>,
         external_id: Option<String>,
     ) -> Result<Self> {
+        Self::create_with_context(
+            to,
+            from,
+            exchange_id,
+            data,
+            protocol,
+            external_id,
+            &CreationContext::default(),
+        )
+    }
+
+    /// Creates a new OrderStatus message, taking its id and timestamp from the given context.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The DID of the recipient (Alice).
+    /// * `from` - The DID of the sender (the PFI).
+    /// * `exchange_id` - The exchange ID shared between Alice and the PFI.
+    /// * `data` - The data containing the current status of the order.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `external_id` - Optional external ID for additional identification.
+    /// * `context` - The clock and id source to create the message with.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `OrderStatus`, as from [`OrderStatus::create`] with the default context.
+    pub fn create_with_context(
+        to: &str,
+        from: &str,
+        exchange_id: &str,
+        data: &OrderStatusData,
+        protocol: Option<String>,
+        external_id: Option<String>,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        let now = context.now();
+
         let metadata = MessageMetadata {
             from: from.to_string(),
             to: to.to_string(),
             kind: MessageKind::OrderStatus,
-            id: MessageKind::OrderStatus.typesafe_id()?,
+            id: context.typesafe_id(&MessageKind::OrderStatus.to_string(), now)?,
             exchange_id: exchange_id.to_string(),
             external_id,
             protocol: protocol.unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string()),
-            created_at: Utc::now().to_rfc3339(),
+            created_at: now.to_rfc3339(),
         };
 
         let order_status = Self {
             metadata: metadata.clone(),
             data: data.clone(),
             signature: String::default(),
+            original: None,
         };
 
         Ok(order_status)
     }
 
+    /// The typed view as JSON, without the fields preserved from parsing.
+    fn typed_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(RawEnvelope {
+            metadata: serde_json::to_value(&self.metadata)?,
+            data: serde_json::to_value(&self.data)?,
+            private_data: None,
+            signature: self.signature.clone(),
+        })
+    }
+
+    /// The typed view as JSON, with the fields preserved from parsing restored.
+    fn preserved_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(OriginalJson::restore(
+            self.original.as_ref(),
+            self.typed_envelope()?,
+        ))
+    }
+
     /// Signs the Order Status message using the provided Bearer DID.
     ///
     /// # Arguments
@@ -83,11 +210,8 @@ impl OrderStatus {
     ///
     /// An empty result, or an error if the signing process fails.
     pub fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
-        self.signature = crate::signature::sign(
-            bearer_did,
-            &serde_json::to_value(&self.metadata)?,
-            &serde_json::to_value(&self.data)?,
-        )?;
+        let envelope = self.preserved_envelope()?;
+        self.signature = crate::signature::sign(bearer_did, &envelope.metadata, &envelope.data)?;
         Ok(())
     }
 
@@ -100,21 +224,34 @@ impl OrderStatus {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub async fn verify(&self) -> Result<()> {
-        // verify resource json schema
-        crate::json_schemas::validate_from_str(MESSAGE_JSON_SCHEMA, self)?;
-
-        // verify data json schema
-        crate::json_schemas::validate_from_str(ORDER_STATUS_DATA_JSON_SCHEMA, &self.data)?;
-
-        // verify signature
-        crate::signature::verify(
-            &serde_json::to_value(self.metadata.clone())?,
-            &serde_json::to_value(self.data.clone())?,
-            &self.signature,
-        )
-        .await?;
-
-        Ok(())
+        metrics::observe("verify_order_status", self.metadata.pfi_did(), async {
+            let protocol = ProtocolVersion::from_str(&self.metadata.protocol)?;
+
+            // verify id prefixes match the kinds they claim
+            self.metadata.verify_ids()?;
+
+            // verify resource json schema
+            let typed = self.typed_envelope()?;
+            crate::json_schemas::validate_from_str(
+                protocol.message_schema(),
+                &typed,
+                &typed.metadata,
+            )?;
+
+            // verify data json schema
+            crate::json_schemas::validate_from_str(
+                protocol.message_data_schema(&MessageKind::OrderStatus)?,
+                &self.data,
+                &typed.metadata,
+            )?;
+
+            // verify signature against the data as parsed, including unmodelled fields
+            let envelope = self.preserved_envelope()?;
+            crate::signature::verify(&envelope.metadata, &envelope.data, &self.signature).await?;
+
+            Ok(())
+        })
+        .await
     }
 }
 
@@ -201,7 +338,10 @@ impl FromStr for Status {
             "REFUND_INITIATED" => Ok(Status::RefundInitiated),
             "REFUND_SETTLED" => Ok(Status::RefundSettled),
             "REFUND_FAILED" => Ok(Status::RefundFailed),
-            _ => Err(TbdexError::Parse(format!("unknown status {}", s))),
+            _ => Err(TbdexError::Parse(ParseError::new(format!(
+                "unknown status {}",
+                s
+            )))),
         }
     }
 }
@@ -227,6 +367,173 @@ impl fmt::Display for Status {
     }
 }
 
+/// The stage of an order a [`Status`] belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Alice paying the PFI.
+    Payin,
+    /// The PFI paying Alice.
+    Payout,
+    /// The PFI returning Alice's payin.
+    Refund,
+}
+
+impl Status {
+    /// The phase of the order this status belongs to.
+    pub fn phase(&self) -> Phase {
+        match self {
+            Status::PayinPending
+            | Status::PayinInitiated
+            | Status::PayinSettled
+            | Status::PayinFailed
+            | Status::PayinExpired => Phase::Payin,
+            Status::PayoutPending
+            | Status::PayoutInitiated
+            | Status::PayoutSettled
+            | Status::PayoutFailed => Phase::Payout,
+            Status::RefundPending
+            | Status::RefundInitiated
+            | Status::RefundSettled
+            | Status::RefundFailed => Phase::Refund,
+        }
+    }
+
+    /// How far through its phase the status is: pending, initiated, then an outcome.
+    fn step(&self) -> u8 {
+        match self {
+            Status::PayinPending | Status::PayoutPending | Status::RefundPending => 0,
+            Status::PayinInitiated | Status::PayoutInitiated | Status::RefundInitiated => 1,
+            _ => 2,
+        }
+    }
+
+    /// Whether no status may follow this one.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Status::PayinExpired
+                | Status::PayoutSettled
+                | Status::RefundSettled
+                | Status::RefundFailed
+        )
+    }
+
+    /// Whether the order has succeeded, i.e. the payout has settled.
+    pub fn succeeded(&self) -> bool {
+        *self == Status::PayoutSettled
+    }
+
+    /// Whether `next` may follow this status.
+    ///
+    /// Within a phase, statuses only move forward, possibly skipping steps: pending, initiated,
+    /// then settled, failed or (for payins) expired. A settled payin moves on to the payout. A
+    /// payin which settled or failed, or a payout which failed or has not been initiated, may be
+    /// refunded.
+    pub fn can_transition_to(&self, next: &Status) -> bool {
+        match (self.phase(), next.phase()) {
+            (Phase::Payin, Phase::Payin)
+            | (Phase::Payout, Phase::Payout)
+            | (Phase::Refund, Phase::Refund) => next.step() > self.step(),
+            (Phase::Payin, Phase::Payout) => *self == Status::PayinSettled,
+            (Phase::Payin, Phase::Refund) => {
+                matches!(self, Status::PayinSettled | Status::PayinFailed)
+            }
+            (Phase::Payout, Phase::Refund) => {
+                matches!(self, Status::PayoutPending | Status::PayoutFailed)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Checks that each status may follow the one before it, as by [`Status::can_transition_to`].
+/// A status repeated is not a transition, and is allowed.
+pub fn validate_transitions<'a>(statuses: impl IntoIterator<Item = &'a Status>) -> Result<()> {
+    let mut previous: Option<&Status> = None;
+
+    for status in statuses {
+        if let Some(previous) = previous {
+            if previous != status && !previous.can_transition_to(status) {
+                return Err(TbdexError::OrderStatusTransition(format!(
+                    "{} cannot follow {}",
+                    status, previous
+                )));
+            }
+        }
+        previous = Some(status);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_lifecycle() {
+        validate_transitions(&[
+            Status::PayinPending,
+            Status::PayinInitiated,
+            Status::PayinSettled,
+            Status::PayoutPending,
+            Status::PayoutInitiated,
+            Status::PayoutSettled,
+        ])
+        .unwrap();
+
+        validate_transitions(&[
+            Status::PayinPending,
+            Status::PayinSettled,
+            Status::PayoutPending,
+            Status::PayoutPending,
+            Status::PayoutFailed,
+            Status::RefundPending,
+            Status::RefundSettled,
+        ])
+        .unwrap();
+
+        validate_transitions(&[Status::PayinInitiated, Status::PayinExpired]).unwrap();
+    }
+
+    #[test]
+    fn rejects_illegal_transitions() {
+        for (previous, next) in [
+            (Status::PayinSettled, Status::PayinPending),
+            (Status::PayinPending, Status::PayoutPending),
+            (Status::PayinExpired, Status::RefundPending),
+            (Status::PayoutInitiated, Status::RefundPending),
+            (Status::PayoutSettled, Status::RefundPending),
+            (Status::RefundPending, Status::PayoutPending),
+            (Status::RefundFailed, Status::RefundSettled),
+        ] {
+            assert!(
+                !previous.can_transition_to(&next),
+                "{} -> {}",
+                previous,
+                next
+            );
+        }
+
+        assert_eq!(
+            Err(TbdexError::OrderStatusTransition(
+                "PAYIN_PENDING cannot follow PAYOUT_SETTLED".to_string()
+            )),
+            validate_transitions(&[Status::PayoutSettled, Status::PayinPending])
+        );
+    }
+
+    #[test]
+    fn classifies_statuses() {
+        assert_eq!(Phase::Payin, Status::PayinExpired.phase());
+        assert_eq!(Phase::Refund, Status::RefundFailed.phase());
+        assert!(Status::PayoutSettled.is_terminal());
+        assert!(Status::PayoutSettled.succeeded());
+        assert!(!Status::PayoutFailed.is_terminal());
+        assert!(!Status::RefundSettled.succeeded());
+    }
+}
+
 #[cfg(test)]
 mod tbdex_test_vectors_protocol {
     use super::*;
@@ -0,0 +1,437 @@
+//! Typed payment details, checked against a payment method's `requiredPaymentDetails`.
+//!
+//! [`PaymentDetails`] are built from any `Serialize` struct or from key-value pairs, then
+//! conformed to the JSON schema of the chosen payin or payout method: values entered as text are
+//! converted to the numbers, booleans and enum values the schema declares, and every field which
+//! still does not satisfy it is reported as a [`FieldError`].
+//!
+//! ```ignore
+//! let details = PaymentDetails::from_map([
+//!     ("accountNumber", "0123456789"),
+//!     ("accountType", "checking"),
+//! ]);
+//! let builder = builder.payout_details("BANK_ACCOUNT", details)?;
+//!
+//! // on receiving the RFQ
+//! let payout = rfq.private_data.as_ref().and_then(|pd| pd.payout.as_ref());
+//! let account: BankAccount = payout.unwrap().typed_payment_details()?.unwrap().to_struct()?;
+//! ```
+
+use crate::{
+    errors::{FieldError, Result, TbdexError},
+    json::parse_value,
+    redact::{zeroize_value, Reveal},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Number, Value};
+use std::fmt;
+
+/// The payment details for a payin or payout method, an object of named fields.
+///
+/// `Debug` output shows only the field names; see [`Reveal`]. The values are zeroized on drop.
+#[derive(Clone, Default, PartialEq)]
+pub struct PaymentDetails(Map<String, Value>);
+
+impl fmt::Debug for PaymentDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PaymentDetails")
+            .field(&self.0.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Reveal for PaymentDetails {
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PaymentDetails").field(&self.0).finish()
+    }
+}
+
+impl Drop for PaymentDetails {
+    fn drop(&mut self) {
+        self.0.values_mut().for_each(zeroize_value);
+    }
+}
+
+impl PaymentDetails {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the payment details from a struct, whose fields must serialize to the names the
+    /// schema uses, e.g. with `#[serde(rename_all = "camelCase")]`.
+    pub fn from_struct<T: Serialize>(details: &T) -> Result<Self> {
+        Self::from_value(serde_json::to_value(details)?)
+    }
+
+    /// Takes the payment details from key-value pairs, such as the fields of a form.
+    pub fn from_map<K, V>(fields: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        Self(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+
+    /// Takes the payment details from a JSON value, which must be an object.
+    pub fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(fields) => Ok(Self(fields)),
+            _ => Err(TbdexError::InvalidPaymentDetails(vec![FieldError {
+                field: String::new(),
+                reason: "payment details must be an object".to_string(),
+            }])),
+        }
+    }
+
+    /// Sets a field, returning the payment details for chaining.
+    pub fn with(mut self, field: &str, value: impl Into<Value>) -> Self {
+        self.set(field, value);
+        self
+    }
+
+    /// Sets a field, replacing any previous value.
+    pub fn set(&mut self, field: &str, value: impl Into<Value>) {
+        if let Some(mut previous) = self.0.insert(field.to_string(), value.into()) {
+            zeroize_value(&mut previous);
+        }
+    }
+
+    /// The names of the fields which are set.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Reads a field as the given type, or `None` if it is not set.
+    ///
+    /// Numbers and booleans held as text are parsed, and numbers and booleans read as a `String`
+    /// are formatted, so a field reads the same whether or not it has been conformed to a schema.
+    pub fn get<T: DeserializeOwned>(&self, field: &str) -> Result<Option<T>> {
+        let value = match self.0.get(field) {
+            None => return Ok(None),
+            Some(value) => value,
+        };
+
+        T::deserialize(value)
+            .or_else(|e| match value {
+                Value::String(s) => serde_json::from_str(s).map_err(|_| e),
+                Value::Number(_) | Value::Bool(_) => {
+                    let mut text = Value::String(value.to_string());
+                    let read = T::deserialize(&text).map_err(|_| e);
+                    zeroize_value(&mut text);
+                    read
+                }
+                _ => Err(e),
+            })
+            .map(Some)
+            .map_err(|e| unreadable(field.to_string(), &e.to_string()))
+    }
+
+    /// Reads all of the payment details into a struct.
+    pub fn to_struct<T: DeserializeOwned>(&self) -> Result<T> {
+        let mut value = self.to_value();
+        let read = parse_value(&value)
+            .map_err(|e| unreadable(e.path.clone().unwrap_or_default(), &e.reason));
+        zeroize_value(&mut value);
+        read
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.0.clone())
+    }
+
+    pub fn into_value(mut self) -> Value {
+        Value::Object(std::mem::take(&mut self.0))
+    }
+
+    /// Converts the fields to the types declared by the payment method's `requiredPaymentDetails`
+    /// schema, then validates them against it.
+    ///
+    /// Text is parsed where the schema declares a number, integer or boolean, numbers and booleans
+    /// are formatted where it declares a string, and text matching one of a field's `enum` values
+    /// regardless of case takes on that value. Every field which still does not satisfy the schema
+    /// is reported in a [`TbdexError::InvalidPaymentDetails`].
+    ///
+    /// Without a schema the payment details are returned unchanged. Schemas referring to remote
+    /// schemas are validated against only once those are fetched, when the RFQ is built or
+    /// verified.
+    pub fn conform_to(mut self, required_payment_details: Option<&Value>) -> Result<Self> {
+        let schema = match required_payment_details {
+            None => return Ok(self),
+            Some(schema) => schema,
+        };
+
+        coerce_fields(&mut self.0, schema, schema);
+
+        if crate::json_schemas::is_local(schema) {
+            let field_errors = crate::json_schemas::field_errors(schema, &self.0)?;
+            if !field_errors.is_empty() {
+                return Err(TbdexError::InvalidPaymentDetails(field_errors));
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+impl TryFrom<Value> for PaymentDetails {
+    type Error = TbdexError;
+
+    fn try_from(value: Value) -> Result<Self> {
+        Self::from_value(value)
+    }
+}
+
+impl From<PaymentDetails> for Value {
+    fn from(details: PaymentDetails) -> Self {
+        details.into_value()
+    }
+}
+
+/// The error for a field which cannot be read as the requested type. Serde's messages for
+/// invalid types, values and variants quote the value, so only those for missing fields are kept.
+fn unreadable(field: String, reason: &str) -> TbdexError {
+    let reason = match reason.starts_with("missing field") {
+        true => reason.to_string(),
+        false => "cannot be read as the requested type".to_string(),
+    };
+    TbdexError::InvalidPaymentDetails(vec![FieldError { field, reason }])
+}
+
+/// Replaces a value, zeroizing the one it replaces.
+fn replace(value: &mut Value, replacement: Value) {
+    let mut replaced = std::mem::replace(value, replacement);
+    zeroize_value(&mut replaced);
+}
+
+fn coerce_fields(fields: &mut Map<String, Value>, root: &Value, schema: &Value) {
+    let properties = match schema.get("properties").and_then(Value::as_object) {
+        None => return,
+        Some(properties) => properties,
+    };
+
+    for (name, value) in fields.iter_mut() {
+        let property = properties
+            .get(name)
+            .and_then(|property| crate::json_schemas::resolve_local_ref(root, property));
+        if let Some(property) = property {
+            coerce(value, root, property);
+        }
+    }
+}
+
+fn coerce(value: &mut Value, root: &Value, schema: &Value) {
+    let types = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+
+    if let Value::Object(fields) = value {
+        coerce_fields(fields, root, schema);
+    } else if !types.is_empty() && !types.iter().any(|t| is_of_type(value, t)) {
+        if let Some(coerced) = types.iter().find_map(|t| convert(value, t)) {
+            replace(value, coerced);
+        }
+    }
+
+    if let Some(option) = matching_enum_option(value, schema) {
+        replace(value, option.clone());
+    }
+}
+
+/// The one `enum` option equal to the text regardless of case, if the text is not itself one.
+fn matching_enum_option<'a>(value: &Value, schema: &'a Value) -> Option<&'a Value> {
+    let (text, options) = match (value, schema.get("enum")) {
+        (Value::String(text), Some(Value::Array(options))) if !options.contains(value) => {
+            (text, options)
+        }
+        _ => return None,
+    };
+
+    let mut matching = options
+        .iter()
+        .filter(|o| o.as_str().is_some_and(|o| o.eq_ignore_ascii_case(text)));
+    match (matching.next(), matching.next()) {
+        (Some(option), None) => Some(option),
+        _ => None,
+    }
+}
+
+fn is_of_type(value: &Value, json_type: &str) -> bool {
+    match (json_type, value) {
+        ("string", Value::String(_))
+        | ("number", Value::Number(_))
+        | ("boolean", Value::Bool(_))
+        | ("object", Value::Object(_))
+        | ("array", Value::Array(_))
+        | ("null", Value::Null) => true,
+        ("integer", Value::Number(n)) => n.is_i64() || n.is_u64(),
+        _ => false,
+    }
+}
+
+fn convert(value: &Value, json_type: &str) -> Option<Value> {
+    match (json_type, value) {
+        ("string", Value::Number(_) | Value::Bool(_)) => Some(Value::String(value.to_string())),
+        ("number", Value::String(s)) => s.trim().parse::<Number>().ok().map(Value::Number),
+        ("integer", Value::String(s)) => s
+            .trim()
+            .parse::<Number>()
+            .ok()
+            .filter(|n| n.is_i64() || n.is_u64())
+            .map(Value::Number),
+        ("boolean", Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "properties": {
+                "accountNumber": { "type": "string" },
+                "accountType": { "type": "string", "enum": ["CHECKING", "SAVINGS"] },
+                "routingNumber": { "type": "integer" },
+                "business": { "type": "boolean" }
+            },
+            "required": ["accountNumber", "accountType"],
+            "additionalProperties": false
+        })
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct BankAccount {
+        account_number: String,
+        account_type: AccountType,
+        routing_number: Option<u32>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "UPPERCASE")]
+    enum AccountType {
+        Checking,
+        Savings,
+    }
+
+    #[test]
+    fn converts_form_fields_to_schema_types() {
+        let details = PaymentDetails::from_map([
+            ("accountNumber", "0123456789"),
+            ("accountType", "savings"),
+            ("routingNumber", " 121000358 "),
+            ("business", "False"),
+        ])
+        .conform_to(Some(&schema()))
+        .unwrap();
+
+        assert_eq!(
+            json!({
+                "accountNumber": "0123456789",
+                "accountType": "SAVINGS",
+                "routingNumber": 121000358,
+                "business": false
+            }),
+            details.to_value()
+        );
+    }
+
+    #[test]
+    fn round_trips_structs() {
+        let account = BankAccount {
+            account_number: "0123456789".to_string(),
+            account_type: AccountType::Checking,
+            routing_number: Some(121000358),
+        };
+
+        let details = PaymentDetails::from_struct(&account)
+            .unwrap()
+            .conform_to(Some(&schema()))
+            .unwrap();
+
+        assert_eq!(account, details.to_struct::<BankAccount>().unwrap());
+        assert_eq!(
+            Some(AccountType::Checking),
+            details.get::<AccountType>("accountType").unwrap()
+        );
+        assert_eq!(None, details.get::<bool>("business").unwrap());
+    }
+
+    #[test]
+    fn reads_fields_as_typed_values() {
+        let details = PaymentDetails::new()
+            .with("routingNumber", "121000358")
+            .with("accountNumber", 123);
+
+        assert_eq!(
+            Some(121000358),
+            details.get::<u32>("routingNumber").unwrap()
+        );
+        assert_eq!(
+            Some("123".to_string()),
+            details.get::<String>("accountNumber").unwrap()
+        );
+
+        match details.get::<AccountType>("routingNumber") {
+            Err(TbdexError::InvalidPaymentDetails(errors)) => {
+                assert_eq!("routingNumber", errors[0].field);
+                assert!(!errors[0].reason.contains("121000358"));
+            }
+            other => panic!("expected InvalidPaymentDetails, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_each_invalid_field() {
+        let result = PaymentDetails::new()
+            .with("accountType", "BROKERAGE")
+            .with("routingNumber", "not a number")
+            .with("iban", "DE89370400440532013000")
+            .conform_to(Some(&schema()));
+
+        let errors = match result {
+            Err(TbdexError::InvalidPaymentDetails(errors)) => errors,
+            other => panic!("expected InvalidPaymentDetails, got {:?}", other),
+        };
+
+        // the reasons describe the schema, not the values entered
+        assert!(errors
+            .iter()
+            .all(|e| !e.reason.contains("BROKERAGE") && !e.reason.contains("not a number")));
+
+        let mut fields = errors.into_iter().map(|e| e.field).collect::<Vec<_>>();
+        fields.sort();
+        assert_eq!(
+            vec!["accountNumber", "accountType", "iban", "routingNumber"],
+            fields
+        );
+    }
+
+    #[test]
+    fn redacts_values() {
+        let details = PaymentDetails::new().with("accountNumber", "0123456789");
+
+        assert_eq!(
+            r#"PaymentDetails(["accountNumber"])"#,
+            format!("{:?}", details)
+        );
+        assert!(format!("{:?}", details.reveal()).contains("0123456789"));
+        assert!(PaymentDetails::from_value(json!(["0123456789"])).is_err());
+    }
+}
@@ -1,18 +1,22 @@
-use super::{MessageKind, MessageMetadata, Result};
+use super::{payment_details::PaymentDetails, MessageKind, MessageMetadata, Result, TbdexMessage};
 use crate::{
-    errors::TbdexError,
-    json::{FromJson, ToJson},
-    json_schemas::generated::{
-        MESSAGE_JSON_SCHEMA, RFQ_DATA_JSON_SCHEMA, RFQ_PRIVATE_DATA_JSON_SCHEMA,
+    context::CreationContext,
+    decimal,
+    errors::{ParseError, TbdexError},
+    json::{
+        deserialize_envelope, parse_envelope, parse_value, FromJson, OriginalJson, RawEnvelope,
+        ToJson, UnknownFields,
     },
+    metrics,
+    protocol::ProtocolVersion,
+    redact::{Reveal, Secret},
     resources::offering::Offering,
     DEFAULT_PROTOCOL_VERSION,
 };
 use base64::{engine::general_purpose, Engine as _};
-use chrono::Utc;
-use rand::{rngs::OsRng, RngCore};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sha2::{Digest, Sha256};
+use std::{fmt, str::FromStr};
 use web5::{credentials::VerifiableCredential, dids::bearer_did::BearerDid};
 
 /// Represents an RFQ (Request For Quote) message in the tbDEX protocol.
@@ -22,8 +26,9 @@ use web5::{credentials::VerifiableCredential, dids::bearer_did::BearerDid};
 /// and optionally private data such as sensitive payment or credential information.
 ///
 /// The signature ensures the integrity of the message.
-#[derive(Clone, Serialize, Default, Deserialize, Debug, PartialEq)]
-#[serde(rename_all = "camelCase")]
+///
+/// `Debug` output redacts the private data; see [`Reveal`].
+#[derive(Clone, Default, PartialEq)]
 pub struct Rfq {
     /// Metadata about the message, including sender, recipient, and protocol information.
     pub metadata: MessageMetadata,
@@ -32,15 +37,112 @@ pub struct Rfq {
     pub data: RfqData,
 
     /// Optional private data that may include sensitive details like payment information and credentials.
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub private_data: Option<RfqPrivateData>,
 
     /// The signature verifying the authenticity and integrity of the RFQ message.
     pub signature: String,
+
+    /// The JSON as parsed, which preserves fields not modelled above.
+    pub(crate) original: Option<OriginalJson>,
 }
 
 impl ToJson for Rfq {}
-impl FromJson for Rfq {}
+impl FromJson for Rfq {
+    fn from_json_value(value: &serde_json::Value) -> std::result::Result<Self, ParseError> {
+        parse_envelope(value)
+    }
+}
+
+// the original JSON is left out, as its unmodelled fields may include private data
+impl fmt::Debug for Rfq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rfq")
+            .field("metadata", &self.metadata)
+            .field("data", &self.data)
+            .field("private_data", &self.private_data)
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Reveal for Rfq {
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rfq")
+            .field("metadata", &self.metadata)
+            .field("data", &self.data)
+            .field(
+                "private_data",
+                &self.private_data.as_ref().map(Reveal::reveal),
+            )
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UnknownFields for Rfq {
+    fn unknown_fields(&self) -> &[String] {
+        OriginalJson::unknown_fields(self.original.as_ref())
+    }
+}
+
+impl Serialize for Rfq {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.preserved_envelope()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rfq {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_envelope(deserializer)
+    }
+}
+
+impl TryFrom<RawEnvelope> for Rfq {
+    type Error = ParseError;
+
+    fn try_from(raw: RawEnvelope) -> std::result::Result<Self, ParseError> {
+        let mut rfq = Self {
+            metadata: parse_value(&raw.metadata).map_err(|e| e.within("metadata"))?,
+            data: parse_value(&raw.data).map_err(|e| e.within("data"))?,
+            private_data: raw
+                .private_data
+                .as_ref()
+                .map(|p| parse_value(p).map_err(|e| e.within("privateData")))
+                .transpose()?,
+            signature: raw.signature.clone(),
+            original: None,
+        };
+        let typed = rfq
+            .typed_envelope()
+            .map_err(|e| ParseError::new(e.to_string()))?;
+        rfq.original = Some(OriginalJson::capture(raw, &typed));
+        Ok(rfq)
+    }
+}
+
+impl TbdexMessage for Rfq {
+    fn metadata(&self) -> &MessageMetadata {
+        &self.metadata
+    }
+
+    fn metadata_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.metadata)
+    }
+
+    fn data_json(&self) -> Result<serde_json::Value> {
+        Ok(self.preserved_envelope()?.data)
+    }
+
+    fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        Rfq::sign(self, bearer_did)
+    }
+
+    async fn verify(&self) -> Result<()> {
+        Rfq::verify(self).await
+    }
+}
 
 impl Rfq {
     /// Creates a new RFQ (Request For Quote) message.
@@ -63,7 +165,56 @@ impl Rfq {
         protocol: Option<String>,
         external_id: Option<String>,
     ) -> Result<Self> {
-        let id = MessageKind::Rfq.typesafe_id()?;
+        Self::create_with_context(
+            to,
+            from,
+            create_rfq_data,
+            protocol,
+            external_id,
+            &CreationContext::default(),
+        )
+    }
+
+    /// Creates a new RFQ message, taking its id, timestamp and private data salt from the given
+    /// context.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The DID of the recipient (the PFI).
+    /// * `from` - The DID of the sender (Alice).
+    /// * `create_rfq_data` - The data required to generate the RFQ, including payin, payout, and offering details.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `external_id` - Optional external ID for additional identification.
+    /// * `context` - The clock, id source and salt source to create the message with.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Rfq`, as from [`Rfq::create`] with the default context.
+    pub fn create_with_context(
+        to: &str,
+        from: &str,
+        create_rfq_data: &CreateRfqData,
+        protocol: Option<String>,
+        external_id: Option<String>,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        Self::create_hashed(to, from, protocol, external_id, context, |context| {
+            hash_private_data(create_rfq_data, context)
+        })
+    }
+
+    /// Creates an RFQ whose data and private data are produced by `hash_private_data`, which is
+    /// called once the metadata has been created.
+    pub(crate) fn create_hashed(
+        to: &str,
+        from: &str,
+        protocol: Option<String>,
+        external_id: Option<String>,
+        context: &CreationContext,
+        hash_private_data: impl FnOnce(&CreationContext) -> Result<(RfqData, RfqPrivateData)>,
+    ) -> Result<Self> {
+        let now = context.now();
+        let id = context.typesafe_id(&MessageKind::Rfq.to_string(), now)?;
 
         let metadata = MessageMetadata {
             from: from.to_string(),
@@ -73,21 +224,44 @@ impl Rfq {
             exchange_id: id.clone(),
             external_id,
             protocol: protocol.unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string()),
-            created_at: Utc::now().to_rfc3339(),
+            created_at: now.to_rfc3339(),
         };
 
-        let (data, private_data) = hash_private_data(create_rfq_data)?;
+        let (data, private_data) = hash_private_data(context)?;
 
         let rfq = Self {
             metadata: metadata.clone(),
             data: data.clone(),
             private_data: Some(private_data),
             signature: String::default(),
+            original: None,
         };
 
         Ok(rfq)
     }
 
+    /// The typed view as JSON, without the fields preserved from parsing.
+    fn typed_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(RawEnvelope {
+            metadata: serde_json::to_value(&self.metadata)?,
+            data: serde_json::to_value(&self.data)?,
+            private_data: self
+                .private_data
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()?,
+            signature: self.signature.clone(),
+        })
+    }
+
+    /// The typed view as JSON, with the fields preserved from parsing restored.
+    fn preserved_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(OriginalJson::restore(
+            self.original.as_ref(),
+            self.typed_envelope()?,
+        ))
+    }
+
     /// Signs the RFQ message using the provided Bearer DID.
     ///
     /// # Arguments
@@ -98,11 +272,8 @@ impl Rfq {
     ///
     /// An empty result, or an error if the signing process fails.
     pub fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
-        self.signature = crate::signature::sign(
-            bearer_did,
-            &serde_json::to_value(&self.metadata)?,
-            &serde_json::to_value(&self.data)?,
-        )?;
+        let envelope = self.preserved_envelope()?;
+        self.signature = crate::signature::sign(bearer_did, &envelope.metadata, &envelope.data)?;
         Ok(())
     }
 
@@ -115,25 +286,46 @@ impl Rfq {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub async fn verify(&self) -> Result<()> {
+        metrics::observe("verify_rfq", self.metadata.pfi_did(), async {
+            self.verify_schemas()?;
+
+            // verify signature against the data as parsed, including unmodelled fields
+            let envelope = self.preserved_envelope()?;
+            crate::signature::verify(&envelope.metadata, &envelope.data, &self.signature).await?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Verifies the id prefixes and the message, data and private data JSON schemas, but not the
+    /// signature.
+    pub(crate) fn verify_schemas(&self) -> Result<()> {
+        let protocol = ProtocolVersion::from_str(&self.metadata.protocol)?;
+
+        // verify id prefixes match the kinds they claim
+        self.metadata.verify_ids()?;
+
         // verify resource json schema
-        crate::json_schemas::validate_from_str(MESSAGE_JSON_SCHEMA, self)?;
+        let typed = self.typed_envelope()?;
+        crate::json_schemas::validate_from_str(protocol.message_schema(), &typed, &typed.metadata)?;
 
         // verify data json schema
-        crate::json_schemas::validate_from_str(RFQ_DATA_JSON_SCHEMA, &self.data)?;
+        crate::json_schemas::validate_from_str(
+            protocol.message_data_schema(&MessageKind::Rfq)?,
+            &self.data,
+            &typed.metadata,
+        )?;
 
         // verify private data json schema
         if let Some(private_data) = &self.private_data {
-            crate::json_schemas::validate_from_str(RFQ_PRIVATE_DATA_JSON_SCHEMA, private_data)?;
+            crate::json_schemas::validate_from_str(
+                protocol.rfq_private_data_schema(),
+                private_data,
+                &typed.metadata,
+            )?;
         }
 
-        // verify signature
-        crate::signature::verify(
-            &serde_json::to_value(self.metadata.clone())?,
-            &serde_json::to_value(self.data.clone())?,
-            &self.signature,
-        )
-        .await?;
-
         Ok(())
     }
 
@@ -150,160 +342,201 @@ impl Rfq {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub async fn verify_offering_requirements(&self, offering: &Offering) -> Result<()> {
-        // verify protocol version
-        if offering.metadata.protocol != self.metadata.protocol {
-            return Err(TbdexError::OfferingVerification(format!(
-                "offering has protocol version {} but rfq has protocol version {}",
-                offering.metadata.protocol, self.metadata.protocol
-            )));
-        }
+        metrics::observe(
+            "verify_offering_requirements",
+            self.metadata.pfi_did(),
+            async {
+                // verify protocol version
+                if offering.metadata.protocol != self.metadata.protocol {
+                    return Err(TbdexError::OfferingVerification(format!(
+                        "offering has protocol version {} but rfq has protocol version {}",
+                        offering.metadata.protocol, self.metadata.protocol
+                    )));
+                }
 
-        // verify offering id
-        if offering.metadata.id != self.data.offering_id {
-            return Err(TbdexError::OfferingVerification(format!(
-                "offering id is {} but rfq has offering id {}",
-                offering.metadata.id, self.data.offering_id
-            )));
-        }
+                // verify offering id
+                if offering.metadata.id != self.data.offering_id {
+                    return Err(TbdexError::OfferingVerification(format!(
+                        "offering id is {} but rfq has offering id {}",
+                        offering.metadata.id, self.data.offering_id
+                    )));
+                }
 
-        let payin_amount = self.data.payin.amount.parse::<f64>().map_err(|_| {
-            TbdexError::OfferingVerification(format!(
-                "rfq payin amount invalid decimal string {}",
-                self.data.payin.amount
-            ))
-        })?;
-
-        // verify max amount
-        if let Some(max_amount) = offering.data.payin.max.as_ref() {
-            let max_amount = max_amount.parse::<f64>().map_err(|_| {
-                TbdexError::OfferingVerification(format!(
-                    "offering max amount invalid decimal string {}",
-                    max_amount
-                ))
-            })?;
-
-            if payin_amount > max_amount {
-                return Err(TbdexError::OfferingVerification(format!(
-                    "rfq payin of {} is larger than max offering amount of {}",
-                    payin_amount, max_amount
-                )));
-            }
-        }
+                let payin_amount = decimal::to_f64(&self.data.payin.amount).ok_or_else(|| {
+                    TbdexError::OfferingVerification(format!(
+                        "rfq payin amount invalid decimal string {}",
+                        self.data.payin.amount
+                    ))
+                })?;
 
-        // verify min amount
-        if let Some(min_amount) = offering.data.payin.min.as_ref() {
-            let min_amount = min_amount.parse::<f64>().map_err(|_| {
-                TbdexError::OfferingVerification(format!(
-                    "offering min amount invalid decimal string {}",
-                    min_amount
-                ))
-            })?;
-
-            if payin_amount < min_amount {
-                return Err(TbdexError::OfferingVerification(format!(
-                    "rfq payin of {} is smaller than min offering amount of {}",
-                    payin_amount, min_amount
-                )));
-            }
-        }
+                // verify max amount
+                if let Some(max_amount) = offering.data.payin.max.as_ref() {
+                    let max_amount = decimal::to_f64(max_amount).ok_or_else(|| {
+                        TbdexError::OfferingVerification(format!(
+                            "offering max amount invalid decimal string {}",
+                            max_amount
+                        ))
+                    })?;
 
-        let private_data = match &self.private_data {
-            None => {
-                return Err(TbdexError::OfferingVerification(
-                    "cannot verify offering requirements without private data".to_string(),
-                ))
-            }
-            Some(pd) => pd,
-        };
+                    if payin_amount > max_amount {
+                        return Err(TbdexError::OfferingVerification(format!(
+                            "rfq payin of {} is larger than max offering amount of {}",
+                            payin_amount, max_amount
+                        )));
+                    }
+                }
 
-        // verify payin json schema
-        if let Some(payin_method) = offering
-            .data
-            .payin
-            .methods
-            .iter()
-            .find(|m| m.kind == self.data.payin.kind)
-        {
-            if let Some(json_schema) = &payin_method.required_payment_details {
-                let payment_details = private_data
-                    .payin
-                    .as_ref()
-                    .ok_or_else(|| {
-                        TbdexError::OfferingVerification("missing private payin data".to_string())
-                    })?
-                    .payment_details
-                    .as_ref()
-                    .ok_or_else(|| {
-                        TbdexError::OfferingVerification("missing payment details".to_string())
+                // verify min amount
+                if let Some(min_amount) = offering.data.payin.min.as_ref() {
+                    let min_amount = decimal::to_f64(min_amount).ok_or_else(|| {
+                        TbdexError::OfferingVerification(format!(
+                            "offering min amount invalid decimal string {}",
+                            min_amount
+                        ))
                     })?;
 
-                crate::json_schemas::validate(json_schema, payment_details)?;
-            }
-        } else {
-            return Err(TbdexError::OfferingVerification(format!(
-                "kind {} not found in offering",
-                self.data.payin.kind
-            )));
-        }
+                    if payin_amount < min_amount {
+                        return Err(TbdexError::OfferingVerification(format!(
+                            "rfq payin of {} is smaller than min offering amount of {}",
+                            payin_amount, min_amount
+                        )));
+                    }
+                }
 
-        // verify payout json schema
-        if let Some(payout_method) = offering
-            .data
-            .payout
-            .methods
-            .iter()
-            .find(|m| m.kind == self.data.payout.kind)
-        {
-            if let Some(json_schema) = &payout_method.required_payment_details {
-                let payment_details = private_data
-                    .payout
-                    .as_ref()
-                    .ok_or_else(|| {
-                        TbdexError::OfferingVerification("missing private payout data".to_string())
-                    })?
-                    .payment_details
-                    .as_ref()
-                    .ok_or_else(|| {
-                        TbdexError::OfferingVerification("missing payment details".to_string())
-                    })?;
+                let private_data = match &self.private_data {
+                    None => {
+                        return Err(TbdexError::OfferingVerification(
+                            "cannot verify offering requirements without private data".to_string(),
+                        ))
+                    }
+                    Some(pd) => pd,
+                };
+
+                // selectively disclosed private data is reassembled into its hashed shape
+                #[cfg(feature = "selective-disclosure")]
+                let reassembled_private_data;
+                #[cfg(feature = "selective-disclosure")]
+                let private_data = match &private_data.disclosures {
+                    None => private_data,
+                    Some(_) => {
+                        reassembled_private_data = self.reassemble_disclosed_private_data()?;
+                        &reassembled_private_data
+                    }
+                };
 
-                crate::json_schemas::validate(json_schema, payment_details)?;
-            }
-        } else {
-            return Err(TbdexError::OfferingVerification(format!(
-                "kind {} not found in offering",
-                self.data.payout.kind
-            )));
-        }
+                let metadata = serde_json::to_value(&self.metadata)?;
 
-        // verify claims
-        if let Some(required_claims) = &offering.data.required_claims {
-            let vc_jwts = required_claims
-                .select_credentials(&private_data.claims.clone().unwrap_or_default())
-                .await
-                .map_err(|_| {
-                    TbdexError::OfferingVerification("failed to select credentials".to_string())
-                })?;
+                // verify payin json schema
+                if let Some(payin_method) = offering
+                    .data
+                    .payin
+                    .methods
+                    .iter()
+                    .find(|m| m.kind == self.data.payin.kind)
+                {
+                    if let Some(json_schema) = &payin_method.required_payment_details {
+                        let payment_details = private_data
+                            .payin
+                            .as_ref()
+                            .ok_or_else(|| {
+                                TbdexError::OfferingVerification(
+                                    "missing private payin data".to_string(),
+                                )
+                            })?
+                            .payment_details
+                            .as_ref()
+                            .ok_or_else(|| {
+                                TbdexError::OfferingVerification(
+                                    "missing payment details".to_string(),
+                                )
+                            })?;
+
+                        crate::json_schemas::validate_async(
+                            json_schema,
+                            payment_details,
+                            &metadata,
+                        )
+                        .await?;
+                    }
+                } else {
+                    return Err(TbdexError::OfferingVerification(format!(
+                        "kind {} not found in offering",
+                        self.data.payin.kind
+                    )));
+                }
 
-            if vc_jwts.is_empty() {
-                return Err(TbdexError::OfferingVerification(
-                    "no matching credentials found".to_string(),
-                ));
-            }
+                // verify payout json schema
+                if let Some(payout_method) = offering
+                    .data
+                    .payout
+                    .methods
+                    .iter()
+                    .find(|m| m.kind == self.data.payout.kind)
+                {
+                    if let Some(json_schema) = &payout_method.required_payment_details {
+                        let payment_details = private_data
+                            .payout
+                            .as_ref()
+                            .ok_or_else(|| {
+                                TbdexError::OfferingVerification(
+                                    "missing private payout data".to_string(),
+                                )
+                            })?
+                            .payment_details
+                            .as_ref()
+                            .ok_or_else(|| {
+                                TbdexError::OfferingVerification(
+                                    "missing payment details".to_string(),
+                                )
+                            })?;
+
+                        crate::json_schemas::validate_async(
+                            json_schema,
+                            payment_details,
+                            &metadata,
+                        )
+                        .await?;
+                    }
+                } else {
+                    return Err(TbdexError::OfferingVerification(format!(
+                        "kind {} not found in offering",
+                        self.data.payout.kind
+                    )));
+                }
 
-            for vc_jwt in vc_jwts {
-                VerifiableCredential::from_vc_jwt(&vc_jwt, true)
-                    .await
-                    .map_err(|_| {
-                        TbdexError::OfferingVerification(format!(
-                            "vc_jwt failed verifiction {}",
-                            vc_jwt
-                        ))
-                    })?;
-            }
-        }
+                // verify claims
+                if let Some(required_claims) = &offering.data.required_claims {
+                    let vc_jwts = required_claims
+                        .select_credentials(&private_data.claims.clone().unwrap_or_default())
+                        .await
+                        .map_err(|_| {
+                            TbdexError::OfferingVerification(
+                                "failed to select credentials".to_string(),
+                            )
+                        })?;
+
+                    if vc_jwts.is_empty() {
+                        return Err(TbdexError::OfferingVerification(
+                            "no matching credentials found".to_string(),
+                        ));
+                    }
 
-        Ok(())
+                    for vc_jwt in vc_jwts {
+                        VerifiableCredential::from_vc_jwt(&vc_jwt, true)
+                            .await
+                            .map_err(|_| {
+                                TbdexError::OfferingVerification(format!(
+                                    "vc_jwt failed verifiction {}",
+                                    vc_jwt
+                                ))
+                            })?;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+        .await
     }
 
     /// Verifies that all private data provided in the RFQ is valid by checking their corresponding hashes.
@@ -315,55 +548,61 @@ impl Rfq {
     ///
     /// An empty result if verification succeeds, or an error if the verification fails.
     pub fn verify_all_private_data(&self) -> Result<()> {
-        let private_data = match &self.private_data {
-            None => {
-                return Err(TbdexError::PrivateDataVerification(
-                    "cannot verify all private data without private data".to_string(),
-                ))
+        metrics::observe_sync("verify_all_private_data", self.metadata.pfi_did(), || {
+            let private_data = match &self.private_data {
+                None => {
+                    return Err(TbdexError::PrivateDataVerification(
+                        "cannot verify all private data without private data".to_string(),
+                    ))
+                }
+                Some(pd) => pd,
+            };
+
+            if let Some(hash) = &self.data.payin.payment_details_hash {
+                if let Some(payin) = &private_data.payin {
+                    let digest = digest_private_data(&private_data.salt, &payin.payment_details)?;
+                    if &digest != hash {
+                        return Err(TbdexError::PrivateDataVerification(
+                            "private data payin hash mismatch".to_string(),
+                        ));
+                    }
+                } else {
+                    return Err(TbdexError::PrivateDataVerification(
+                        "private data missing payin".to_string(),
+                    ));
+                }
             }
-            Some(pd) => pd,
-        };
 
-        if let Some(hash) = &self.data.payin.payment_details_hash {
-            if let Some(payin) = &private_data.payin {
-                let digest = digest_private_data(&private_data.salt, &payin.payment_details)?;
-                if &digest != hash {
+            if let Some(hash) = &self.data.payout.payment_details_hash {
+                if let Some(payout) = &private_data.payout {
+                    let digest = digest_private_data(&private_data.salt, &payout.payment_details)?;
+                    if &digest != hash {
+                        return Err(TbdexError::PrivateDataVerification(
+                            "private data payout hash mismatch".to_string(),
+                        ));
+                    }
+                } else {
                     return Err(TbdexError::PrivateDataVerification(
-                        "private data payin hash mismatch".to_string(),
+                        "private data missing payout".to_string(),
                     ));
                 }
-            } else {
-                return Err(TbdexError::PrivateDataVerification(
-                    "private data missing payin".to_string(),
-                ));
             }
-        }
 
-        if let Some(hash) = &self.data.payout.payment_details_hash {
-            if let Some(payout) = &private_data.payout {
-                let digest = digest_private_data(&private_data.salt, &payout.payment_details)?;
+            if let Some(hash) = &self.data.claims_hash {
+                let digest = digest_private_data(&private_data.salt, &private_data.claims)?;
                 if &digest != hash {
                     return Err(TbdexError::PrivateDataVerification(
-                        "private data payout hash mismatch".to_string(),
+                        "private data claims hash mismatch".to_string(),
                     ));
                 }
-            } else {
-                return Err(TbdexError::PrivateDataVerification(
-                    "private data missing payout".to_string(),
-                ));
             }
-        }
 
-        if let Some(hash) = &self.data.claims_hash {
-            let digest = digest_private_data(&private_data.salt, &private_data.claims)?;
-            if &digest != hash {
-                return Err(TbdexError::PrivateDataVerification(
-                    "private data claims hash mismatch".to_string(),
-                ));
-            }
-        }
+            // verify selectively disclosed fields, requiring every digest to be disclosed
+            #[cfg(feature = "selective-disclosure")]
+            self.verify_disclosures(private_data, true)?;
 
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Verifies the present private data fields by checking their corresponding hashes.
@@ -375,97 +614,147 @@ impl Rfq {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub fn verify_present_private_data(&self) -> Result<()> {
-        if let Some(private_data) = &self.private_data {
-            let salt = match &self.private_data {
-                None => {
-                    if private_data.payin.is_some()
-                        || private_data.payout.is_some()
-                        || private_data.claims.is_some()
-                    {
-                        return Err(TbdexError::PrivateDataVerification(
-                            "private data defined but salt is not defined".to_string(),
-                        ));
-                    } else {
-                        return Ok(());
+        metrics::observe_sync(
+            "verify_present_private_data",
+            self.metadata.pfi_did(),
+            || {
+                if let Some(private_data) = &self.private_data {
+                    let salt = match &self.private_data {
+                        None => {
+                            if private_data.payin.is_some()
+                                || private_data.payout.is_some()
+                                || private_data.claims.is_some()
+                            {
+                                return Err(TbdexError::PrivateDataVerification(
+                                    "private data defined but salt is not defined".to_string(),
+                                ));
+                            } else {
+                                return Ok(());
+                            }
+                        }
+                        Some(pd) => pd.salt.clone(),
+                    };
+
+                    if let Some(payin) = &private_data.payin {
+                        if let Some(hash) = &self.data.payin.payment_details_hash {
+                            let digest = digest_private_data(&salt, &payin.payment_details)?;
+                            if &digest != hash {
+                                return Err(TbdexError::PrivateDataVerification(
+                                    "private data payin hash mismatch".to_string(),
+                                ));
+                            }
+                        } else {
+                            return Err(TbdexError::PrivateDataVerification(
+                                "private data payin defined but hash is not defined".to_string(),
+                            ));
+                        }
                     }
-                }
-                Some(pd) => pd.salt.clone(),
-            };
 
-            if let Some(payin) = &private_data.payin {
-                if let Some(hash) = &self.data.payin.payment_details_hash {
-                    let digest = digest_private_data(&salt, &payin.payment_details)?;
-                    if &digest != hash {
-                        return Err(TbdexError::PrivateDataVerification(
-                            "private data payin hash mismatch".to_string(),
-                        ));
+                    if let Some(payout) = &private_data.payout {
+                        if let Some(hash) = &self.data.payout.payment_details_hash {
+                            let digest = digest_private_data(&salt, &payout.payment_details)?;
+                            if &digest != hash {
+                                return Err(TbdexError::PrivateDataVerification(
+                                    "private data payout hash mismatch".to_string(),
+                                ));
+                            }
+                        } else {
+                            return Err(TbdexError::PrivateDataVerification(
+                                "private data payout defined but hash is not defined".to_string(),
+                            ));
+                        }
                     }
-                } else {
-                    return Err(TbdexError::PrivateDataVerification(
-                        "private data payin defined but hash is not defined".to_string(),
-                    ));
-                }
-            }
 
-            if let Some(payout) = &private_data.payout {
-                if let Some(hash) = &self.data.payout.payment_details_hash {
-                    let digest = digest_private_data(&salt, &payout.payment_details)?;
-                    if &digest != hash {
-                        return Err(TbdexError::PrivateDataVerification(
-                            "private data payout hash mismatch".to_string(),
-                        ));
+                    if let Some(claims) = &private_data.claims {
+                        if let Some(hash) = &self.data.claims_hash {
+                            let digest = digest_private_data(&salt, &claims)?;
+                            if &digest != hash {
+                                return Err(TbdexError::PrivateDataVerification(
+                                    "private data claims hash mismatch".to_string(),
+                                ));
+                            }
+                        } else {
+                            return Err(TbdexError::PrivateDataVerification(
+                                "private data claims defined but hash is not defined".to_string(),
+                            ));
+                        }
                     }
-                } else {
-                    return Err(TbdexError::PrivateDataVerification(
-                        "private data payout defined but hash is not defined".to_string(),
-                    ));
-                }
-            }
 
-            if let Some(claims) = &private_data.claims {
-                if let Some(hash) = &self.data.claims_hash {
-                    let digest = digest_private_data(&salt, &claims)?;
-                    if &digest != hash {
-                        return Err(TbdexError::PrivateDataVerification(
-                            "private data claims hash mismatch".to_string(),
-                        ));
-                    }
-                } else {
-                    return Err(TbdexError::PrivateDataVerification(
-                        "private data claims defined but hash is not defined".to_string(),
-                    ));
+                    // verify selectively disclosed fields, allowing undisclosed digests
+                    #[cfg(feature = "selective-disclosure")]
+                    self.verify_disclosures(private_data, false)?;
                 }
-            }
-        }
 
-        Ok(())
+                Ok(())
+            },
+        )
     }
 }
 
-#[derive(Clone, Deserialize)]
+/// The data from which an RFQ is created, private data included.
+///
+/// The payment details and claims are [`Secret`]s, redacted from `Debug` output and zeroized on
+/// drop; see [`Reveal`].
+#[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateRfqData {
     pub offering_id: String,
     pub payin: CreateSelectedPayinMethod,
     pub payout: CreateSelectedPayoutMethod,
-    pub claims: Vec<String>,
+    pub claims: Secret<Vec<String>>,
 }
 
 impl FromJson for CreateRfqData {}
 
-#[derive(Clone, Deserialize)]
+impl Reveal for CreateRfqData {
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateRfqData")
+            .field("offering_id", &self.offering_id)
+            .field("payin", &self.payin.reveal())
+            .field("payout", &self.payout.reveal())
+            .field("claims", &self.claims.reveal())
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateSelectedPayinMethod {
     pub kind: String,
-    pub payment_details: Option<serde_json::Value>,
+    pub payment_details: Option<Secret<serde_json::Value>>,
     pub amount: String,
 }
 
-#[derive(Clone, Deserialize)]
+impl Reveal for CreateSelectedPayinMethod {
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateSelectedPayinMethod")
+            .field("kind", &self.kind)
+            .field(
+                "payment_details",
+                &self.payment_details.as_ref().map(Reveal::reveal),
+            )
+            .field("amount", &self.amount)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateSelectedPayoutMethod {
     pub kind: String,
-    pub payment_details: Option<serde_json::Value>,
+    pub payment_details: Option<Secret<serde_json::Value>>,
+}
+
+impl Reveal for CreateSelectedPayoutMethod {
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateSelectedPayoutMethod")
+            .field("kind", &self.kind)
+            .field(
+                "payment_details",
+                &self.payment_details.as_ref().map(Reveal::reveal),
+            )
+            .finish()
+    }
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
@@ -476,6 +765,11 @@ pub struct RfqData {
     pub payout: SelectedPayoutMethod,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub claims_hash: Option<String>,
+    /// Digests of the individually disclosable claims, in place of `claims_hash`; see
+    /// [`rfq_disclosure`](super::rfq_disclosure).
+    #[cfg(feature = "selective-disclosure")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claims_digests: Option<Vec<String>>,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
@@ -484,6 +778,11 @@ pub struct SelectedPayinMethod {
     pub kind: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payment_details_hash: Option<String>,
+    /// Digests of the individually disclosable payment details fields, in place of
+    /// `payment_details_hash`; see [`rfq_disclosure`](super::rfq_disclosure).
+    #[cfg(feature = "selective-disclosure")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_details_digests: Option<Vec<String>>,
     pub amount: String,
 }
 
@@ -493,29 +792,85 @@ pub struct SelectedPayoutMethod {
     pub kind: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payment_details_hash: Option<String>,
+    /// Digests of the individually disclosable payment details fields, in place of
+    /// `payment_details_hash`; see [`rfq_disclosure`](super::rfq_disclosure).
+    #[cfg(feature = "selective-disclosure")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_details_digests: Option<Vec<String>>,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
+/// The private data of an RFQ, committed to by the hashes or digests in its data.
+///
+/// Every field is a [`Secret`], redacted from `Debug` output and zeroized on drop; see
+/// [`Reveal`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct RfqPrivateData {
-    pub salt: String,
+    pub salt: Secret<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payin: Option<PrivatePaymentDetails>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payout: Option<PrivatePaymentDetails>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub claims: Option<Vec<String>>,
+    pub claims: Option<Secret<Vec<String>>>,
+    /// The encoded disclosures of selectively disclosable private data; see
+    /// [`rfq_disclosure`](super::rfq_disclosure).
+    #[cfg(feature = "selective-disclosure")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disclosures: Option<Secret<Vec<String>>>,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
+impl Reveal for RfqPrivateData {
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("RfqPrivateData");
+        debug
+            .field("salt", &self.salt.reveal())
+            .field("payin", &self.payin.as_ref().map(Reveal::reveal))
+            .field("payout", &self.payout.as_ref().map(Reveal::reveal))
+            .field("claims", &self.claims.as_ref().map(Reveal::reveal));
+        #[cfg(feature = "selective-disclosure")]
+        debug.field(
+            "disclosures",
+            &self.disclosures.as_ref().map(Reveal::reveal),
+        );
+        debug.finish()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct PrivatePaymentDetails {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub payment_details: Option<serde_json::Value>,
+    pub payment_details: Option<Secret<serde_json::Value>>,
 }
 
-fn hash_private_data(create_rfq_data: &CreateRfqData) -> Result<(RfqData, RfqPrivateData)> {
-    let salt = generate_random_salt();
+impl PrivatePaymentDetails {
+    /// The payment details, as [`PaymentDetails`] which can be read as typed values.
+    pub fn typed_payment_details(&self) -> Result<Option<PaymentDetails>> {
+        self.payment_details
+            .as_deref()
+            .cloned()
+            .map(PaymentDetails::from_value)
+            .transpose()
+    }
+}
+
+impl Reveal for PrivatePaymentDetails {
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivatePaymentDetails")
+            .field(
+                "payment_details",
+                &self.payment_details.as_ref().map(Reveal::reveal),
+            )
+            .finish()
+    }
+}
+
+fn hash_private_data(
+    create_rfq_data: &CreateRfqData,
+    context: &CreationContext,
+) -> Result<(RfqData, RfqPrivateData)> {
+    let salt = context.salt();
 
     let payin_payment_details_hash = create_rfq_data
         .payin
@@ -540,18 +895,24 @@ fn hash_private_data(create_rfq_data: &CreateRfqData) -> Result<(RfqData, RfqPri
         payin: SelectedPayinMethod {
             kind: create_rfq_data.payin.kind.clone(),
             payment_details_hash: payin_payment_details_hash,
+            #[cfg(feature = "selective-disclosure")]
+            payment_details_digests: None,
             amount: create_rfq_data.payin.amount.clone(),
         },
         payout: SelectedPayoutMethod {
             kind: create_rfq_data.payout.kind.clone(),
             payment_details_hash: payout_payment_details_hash,
+            #[cfg(feature = "selective-disclosure")]
+            payment_details_digests: None,
         },
         claims_hash,
+        #[cfg(feature = "selective-disclosure")]
+        claims_digests: None,
     };
 
     let private_rfq_data =
         RfqPrivateData {
-            salt: salt.clone(),
+            salt: salt.into(),
             payin: create_rfq_data
                 .payin
                 .payment_details
@@ -569,17 +930,13 @@ fn hash_private_data(create_rfq_data: &CreateRfqData) -> Result<(RfqData, RfqPri
             } else {
                 None
             },
+            #[cfg(feature = "selective-disclosure")]
+            disclosures: None,
         };
 
     Ok((hashed_rfq_data, private_rfq_data))
 }
 
-fn generate_random_salt() -> String {
-    let mut salt = [0u8; 16];
-    OsRng.fill_bytes(&mut salt);
-    general_purpose::URL_SAFE_NO_PAD.encode(salt)
-}
-
 fn digest_private_data<T: Serialize>(salt: &str, value: &T) -> Result<String> {
     let digestible = serde_json::json!([salt, value]);
     let serialized = serde_jcs::to_string(&digestible)?;
@@ -607,14 +964,14 @@ mod tests {
                 offering_id: "offering_123".to_string(),
                 payin: CreateSelectedPayinMethod {
                     kind: "BTC".to_string(),
-                    payment_details: Some(serde_json::json!({"tmp": "payment-details"})),
+                    payment_details: Some(serde_json::json!({"tmp": "payment-details"}).into()),
                     amount: "101".to_string(),
                 },
                 payout: CreateSelectedPayoutMethod {
                     kind: "BTC".to_string(),
-                    payment_details: Some(serde_json::json!({"tmp": "payment-details"})),
+                    payment_details: Some(serde_json::json!({"tmp": "payment-details"}).into()),
                 },
-                claims: vec!["some-claim".to_string()],
+                claims: vec!["some-claim".to_string()].into(),
             },
             None,
             None,
@@ -634,6 +991,89 @@ mod tests {
         assert_eq!(rfq, parsed_rfq);
     }
 
+    #[test]
+    fn debug_redacts_private_data() {
+        let create_rfq_data = CreateRfqData {
+            offering_id: "offering_123".to_string(),
+            payin: CreateSelectedPayinMethod {
+                kind: "BANK".to_string(),
+                payment_details: Some(serde_json::json!({"accountNumber": "12345678"}).into()),
+                amount: "101".to_string(),
+            },
+            payout: CreateSelectedPayoutMethod {
+                kind: "BTC".to_string(),
+                payment_details: None,
+            },
+            claims: vec!["vc-jwt".to_string()].into(),
+        };
+        let rfq = Rfq::create(
+            "did:test:pfi",
+            "did:test:alice",
+            &create_rfq_data,
+            None,
+            None,
+        )
+        .unwrap();
+
+        for redacted in [
+            format!("{:?}", create_rfq_data),
+            format!("{:?}", rfq),
+            format!("{:?}", crate::messages::Message::Rfq(rfq.clone().into())),
+        ] {
+            assert!(!redacted.contains("12345678"));
+            assert!(!redacted.contains("vc-jwt"));
+            assert!(redacted.contains("[REDACTED]"));
+        }
+
+        let revealed = format!("{:?}", rfq.reveal());
+        assert!(revealed.contains("12345678"));
+        assert!(revealed.contains("vc-jwt"));
+        assert!(format!("{:?}", create_rfq_data.reveal()).contains("12345678"));
+    }
+
+    #[test]
+    fn deterministic_context_produces_identical_signed_rfqs() {
+        use chrono::{TimeZone, Utc};
+
+        let bearer_did = DidJwk::create(None).unwrap();
+        let create_rfq_data = CreateRfqData {
+            offering_id: "offering_123".to_string(),
+            payin: CreateSelectedPayinMethod {
+                kind: "BTC".to_string(),
+                payment_details: Some(serde_json::json!({"tmp": "payment-details"}).into()),
+                amount: "101".to_string(),
+            },
+            payout: CreateSelectedPayoutMethod {
+                kind: "BTC".to_string(),
+                payment_details: None,
+            },
+            claims: vec!["some-claim".to_string()].into(),
+        };
+        let now = Utc.with_ymd_and_hms(2024, 8, 1, 12, 0, 0).unwrap();
+
+        let create = |seed| {
+            let context = CreationContext::deterministic(seed, now);
+            let mut rfq = Rfq::create_with_context(
+                "did:test:pfi",
+                &bearer_did.did.uri,
+                &create_rfq_data,
+                None,
+                None,
+                &context,
+            )
+            .unwrap();
+            rfq.sign(&bearer_did).unwrap();
+            rfq.to_json_string().unwrap()
+        };
+
+        assert_eq!(create(42), create(42));
+        assert_ne!(create(42), create(43));
+
+        let rfq = Rfq::from_json_string(&create(42)).unwrap();
+        assert_eq!(now.to_rfc3339(), rfq.metadata.created_at);
+        assert_eq!(now, rfq.metadata.typed_id().unwrap().timestamp().unwrap());
+    }
+
     #[test]
     fn test_typesafe_id_sorting() {
         let resource_kind = MessageKind::Rfq;
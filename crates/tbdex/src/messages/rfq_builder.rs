@@ -0,0 +1,411 @@
+use super::{
+    payment_details::PaymentDetails,
+    rfq::{CreateRfqData, CreateSelectedPayinMethod, CreateSelectedPayoutMethod, Rfq},
+};
+use crate::{
+    context::CreationContext,
+    decimal,
+    errors::{FieldError, Result, TbdexError},
+    redact::Secret,
+    resources::offering::{Offering, PayinMethod, PayoutMethod},
+};
+/// Builds an RFQ against a specific Offering.
+///
+/// Each setter validates its input against the offering as it is applied, so that mistakes are
+/// reported where they are made rather than when the PFI rejects the RFQ. [`RfqBuilder::build`]
+/// refuses to produce an RFQ which would fail [`Rfq::verify_offering_requirements`].
+///
+/// `Debug` output redacts the payment details and claims.
+#[derive(Clone, Debug)]
+pub struct RfqBuilder {
+    offering: Offering,
+    payin_amount: Option<String>,
+    payin: Option<(String, Option<Secret<serde_json::Value>>)>,
+    payout: Option<(String, Option<Secret<serde_json::Value>>)>,
+    claims: Secret<Vec<String>>,
+    external_id: Option<String>,
+    context: CreationContext,
+}
+
+impl RfqBuilder {
+    /// Creates a new builder seeded from the given offering.
+    pub fn new(offering: &Offering) -> Self {
+        Self {
+            offering: offering.clone(),
+            payin_amount: None,
+            payin: None,
+            payout: None,
+            claims: Secret::default(),
+            external_id: None,
+            context: CreationContext::default(),
+        }
+    }
+
+    /// The payin methods allowed by the offering.
+    pub fn payin_methods(&self) -> &[PayinMethod] {
+        &self.offering.data.payin.methods
+    }
+
+    /// The payout methods allowed by the offering.
+    pub fn payout_methods(&self) -> &[PayoutMethod] {
+        &self.offering.data.payout.methods
+    }
+
+    /// Sets the payin amount, checking it against the offering's limits.
+    ///
+    /// If a payin method has already been chosen, its limits are checked as well.
+    pub fn payin_amount(mut self, amount: &str) -> Result<Self> {
+        let payin = &self.offering.data.payin;
+        check_limits(
+            amount,
+            payin.min.as_deref(),
+            payin.max.as_deref(),
+            "offering",
+        )?;
+
+        if let Some((kind, _)) = &self.payin {
+            let method = self.payin_method_for(kind)?;
+            check_limits(amount, method.min.as_deref(), method.max.as_deref(), kind)?;
+        }
+
+        self.payin_amount = Some(amount.to_string());
+        Ok(self)
+    }
+
+    /// Chooses the payin method, validating the payment details against its
+    /// `required_payment_details` schema.
+    pub fn payin_method(
+        self,
+        kind: &str,
+        payment_details: Option<serde_json::Value>,
+    ) -> Result<Self> {
+        let method = self.payin_method_for(kind)?;
+        check_payment_details(kind, &method.required_payment_details, &payment_details)?;
+
+        self.choose_payin(kind, payment_details)
+    }
+
+    /// Chooses the payin method, conforming the payment details to its `required_payment_details`
+    /// schema; see [`PaymentDetails::conform_to`].
+    pub fn payin_details(self, kind: &str, payment_details: PaymentDetails) -> Result<Self> {
+        let method = self.payin_method_for(kind)?;
+        let payment_details =
+            payment_details.conform_to(method.required_payment_details.as_ref())?;
+
+        self.choose_payin(kind, Some(payment_details.into_value()))
+    }
+
+    /// Chooses the payout method, validating the payment details against its
+    /// `required_payment_details` schema.
+    pub fn payout_method(
+        mut self,
+        kind: &str,
+        payment_details: Option<serde_json::Value>,
+    ) -> Result<Self> {
+        let method = self.payout_method_for(kind)?;
+        check_payment_details(kind, &method.required_payment_details, &payment_details)?;
+
+        self.payout = Some((kind.to_string(), payment_details.map(Secret::new)));
+        Ok(self)
+    }
+
+    /// Chooses the payout method, conforming the payment details to its
+    /// `required_payment_details` schema; see [`PaymentDetails::conform_to`].
+    pub fn payout_details(mut self, kind: &str, payment_details: PaymentDetails) -> Result<Self> {
+        let method = self.payout_method_for(kind)?;
+        let payment_details =
+            payment_details.conform_to(method.required_payment_details.as_ref())?;
+
+        self.payout = Some((kind.to_string(), Some(payment_details.into_value().into())));
+        Ok(self)
+    }
+
+    /// Selects, from the wallet's credentials, those which satisfy the offering's required claims.
+    ///
+    /// Does nothing if the offering has no required claims.
+    pub async fn select_credentials(mut self, vc_jwts: &[String]) -> Result<Self> {
+        if let Some(required_claims) = &self.offering.data.required_claims {
+            let selected = required_claims
+                .select_credentials(&vc_jwts.to_vec())
+                .await
+                .map_err(|_| {
+                    TbdexError::OfferingVerification("failed to select credentials".to_string())
+                })?;
+
+            if selected.is_empty() {
+                return Err(TbdexError::OfferingVerification(
+                    "no matching credentials found".to_string(),
+                ));
+            }
+
+            self.claims = selected.into();
+        }
+
+        Ok(self)
+    }
+
+    /// Sets an optional external ID for additional identification.
+    pub fn external_id(mut self, external_id: &str) -> Self {
+        self.external_id = Some(external_id.to_string());
+        self
+    }
+
+    /// Sets the clock, id source and salt source the RFQ is created with.
+    pub fn context(mut self, context: &CreationContext) -> Self {
+        self.context = context.clone();
+        self
+    }
+
+    /// Builds the RFQ, addressed to the offering's PFI and using the offering's protocol version.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The DID of the sender (Alice).
+    ///
+    /// # Returns
+    ///
+    /// An unsigned `Rfq`, or an error if a required value is missing or the offering would reject it.
+    pub async fn build(&self, from: &str) -> Result<Rfq> {
+        let amount = self
+            .payin_amount
+            .clone()
+            .ok_or_else(|| TbdexError::OfferingVerification("payin amount not set".to_string()))?;
+        let (payin_kind, payin_payment_details) = self
+            .payin
+            .clone()
+            .ok_or_else(|| TbdexError::OfferingVerification("payin method not set".to_string()))?;
+        let (payout_kind, payout_payment_details) = self
+            .payout
+            .clone()
+            .ok_or_else(|| TbdexError::OfferingVerification("payout method not set".to_string()))?;
+
+        if self.offering.data.required_claims.is_some() && self.claims.is_empty() {
+            return Err(TbdexError::OfferingVerification(
+                "offering requires claims but no credentials were selected".to_string(),
+            ));
+        }
+
+        let rfq = Rfq::create_with_context(
+            &self.offering.metadata.from,
+            from,
+            &CreateRfqData {
+                offering_id: self.offering.metadata.id.clone(),
+                payin: CreateSelectedPayinMethod {
+                    kind: payin_kind,
+                    payment_details: payin_payment_details,
+                    amount,
+                },
+                payout: CreateSelectedPayoutMethod {
+                    kind: payout_kind,
+                    payment_details: payout_payment_details,
+                },
+                claims: self.claims.clone(),
+            },
+            Some(self.offering.metadata.protocol.clone()),
+            self.external_id.clone(),
+            &self.context,
+        )?;
+
+        rfq.verify_schemas()?;
+        rfq.verify_offering_requirements(&self.offering).await?;
+
+        Ok(rfq)
+    }
+
+    fn choose_payin(
+        mut self,
+        kind: &str,
+        payment_details: Option<serde_json::Value>,
+    ) -> Result<Self> {
+        if let Some(amount) = &self.payin_amount {
+            let method = self.payin_method_for(kind)?;
+            check_limits(amount, method.min.as_deref(), method.max.as_deref(), kind)?;
+        }
+
+        self.payin = Some((kind.to_string(), payment_details.map(Secret::new)));
+        Ok(self)
+    }
+
+    fn payin_method_for(&self, kind: &str) -> Result<&PayinMethod> {
+        self.offering
+            .data
+            .payin
+            .methods
+            .iter()
+            .find(|m| m.kind == kind)
+            .ok_or_else(|| {
+                TbdexError::OfferingVerification(format!("kind {} not found in offering", kind))
+            })
+    }
+
+    fn payout_method_for(&self, kind: &str) -> Result<&PayoutMethod> {
+        self.offering
+            .data
+            .payout
+            .methods
+            .iter()
+            .find(|m| m.kind == kind)
+            .ok_or_else(|| {
+                TbdexError::OfferingVerification(format!("kind {} not found in offering", kind))
+            })
+    }
+}
+
+fn check_limits(amount: &str, min: Option<&str>, max: Option<&str>, source: &str) -> Result<()> {
+    let parse = |value: &str, name: &str| {
+        decimal::to_f64(value).ok_or_else(|| {
+            TbdexError::OfferingVerification(format!("{} invalid decimal string {}", name, value))
+        })
+    };
+
+    let payin_amount = decimal::to_f64(amount).ok_or_else(|| {
+        let reason = match amount.starts_with('-') {
+            true => "must not be negative".to_string(),
+            false => format!("invalid decimal string {}", amount),
+        };
+        TbdexError::InvalidPaymentDetails(vec![FieldError {
+            field: "payin.amount".to_string(),
+            reason,
+        }])
+    })?;
+
+    if let Some(max) = max {
+        if payin_amount > parse(max, "max amount")? {
+            return Err(TbdexError::OfferingVerification(format!(
+                "payin of {} is larger than {} max amount of {}",
+                amount, source, max
+            )));
+        }
+    }
+
+    if let Some(min) = min {
+        if payin_amount < parse(min, "min amount")? {
+            return Err(TbdexError::OfferingVerification(format!(
+                "payin of {} is smaller than {} min amount of {}",
+                amount, source, min
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_payment_details(
+    kind: &str,
+    required_payment_details: &Option<serde_json::Value>,
+    payment_details: &Option<serde_json::Value>,
+) -> Result<()> {
+    if let Some(json_schema) = required_payment_details {
+        let payment_details = payment_details.as_ref().ok_or_else(|| {
+            TbdexError::OfferingVerification(format!("missing payment details for {}", kind))
+        })?;
+
+        // schemas referring to remote schemas are validated against once fetched, in build()
+        if crate::json_schemas::is_local(json_schema) {
+            let field_errors = crate::json_schemas::field_errors(json_schema, payment_details)?;
+            if !field_errors.is_empty() {
+                return Err(TbdexError::InvalidPaymentDetails(field_errors));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::offering;
+    use futures::executor::block_on;
+    use serde_json::json;
+
+    #[test]
+    fn can_build() {
+        let offering = offering();
+
+        let builder = RfqBuilder::new(&offering)
+            .payin_amount("100")
+            .unwrap()
+            .payin_method("DEBIT_CARD", Some(json!({"cardNumber": "4242"})))
+            .unwrap()
+            .payout_method("BTC_ADDRESS", None)
+            .unwrap();
+        let rfq = block_on(builder.build("did:test:alice")).unwrap();
+
+        assert_eq!(offering.metadata.id, rfq.data.offering_id);
+        assert_eq!(offering.metadata.from, rfq.metadata.to);
+    }
+
+    #[test]
+    fn rejects_amount_out_of_limits() {
+        assert!(RfqBuilder::new(&offering()).payin_amount("1").is_err());
+        assert!(RfqBuilder::new(&offering()).payin_amount("1001").is_err());
+    }
+
+    #[test]
+    fn rejects_amount_which_is_not_a_decimal_string() {
+        for amount in ["-5", "1e2", "abc"] {
+            match RfqBuilder::new(&offering()).payin_amount(amount) {
+                Err(TbdexError::InvalidPaymentDetails(errors)) => {
+                    assert_eq!("payin.amount", errors[0].field)
+                }
+                other => panic!("expected InvalidPaymentDetails, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_method() {
+        assert!(RfqBuilder::new(&offering())
+            .payin_method("SEPA", None)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_payment_details() {
+        let builder = RfqBuilder::new(&offering());
+        assert!(builder
+            .clone()
+            .payin_method("DEBIT_CARD", Some(json!({"cvv": "123"})))
+            .is_err());
+        assert!(builder.payin_method("DEBIT_CARD", None).is_err());
+    }
+
+    #[test]
+    fn can_build_with_typed_payment_details() {
+        let builder = RfqBuilder::new(&offering())
+            .payin_amount("100")
+            .unwrap()
+            .payin_details(
+                "DEBIT_CARD",
+                PaymentDetails::new().with("cardNumber", 4242424242424242u64),
+            )
+            .unwrap()
+            .payout_details("BTC_ADDRESS", PaymentDetails::new())
+            .unwrap();
+        let rfq = block_on(builder.build("did:test:alice")).unwrap();
+
+        let payin = rfq.private_data.as_ref().unwrap().payin.as_ref().unwrap();
+        let details = payin.typed_payment_details().unwrap().unwrap();
+        assert_eq!(
+            Some("4242424242424242".to_string()),
+            details.get("cardNumber").unwrap()
+        );
+
+        let result = RfqBuilder::new(&offering())
+            .payin_details("DEBIT_CARD", PaymentDetails::new().with("cvv", "123"));
+        match result {
+            Err(TbdexError::InvalidPaymentDetails(errors)) => {
+                assert_eq!("cardNumber", errors[0].field)
+            }
+            other => panic!("expected InvalidPaymentDetails, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn refuses_incomplete_build() {
+        let builder = RfqBuilder::new(&offering()).payin_amount("100").unwrap();
+
+        assert!(block_on(builder.build("did:test:alice")).is_err());
+    }
+}
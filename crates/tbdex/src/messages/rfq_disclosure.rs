@@ -0,0 +1,681 @@
+//! Per-field selective disclosure of RFQ private data, behind the `selective-disclosure`
+//! feature.
+//!
+//! This is an extension to the tbDEX protocol: the spec's RFQ commits to each of the payin
+//! details, payout details and claims with a single hash, and defines none of `claimsDigests`,
+//! `paymentDetailsDigests` or `privateData.disclosures`. Both the wallet and the PFI must opt in
+//! to it. Disclosures are encoded as in SD-JWT
+//! ([draft-ietf-oauth-selective-disclosure-jwt](https://datatracker.ietf.org/doc/draft-ietf-oauth-selective-disclosure-jwt/),
+//! section 4.2).
+
+use super::rfq::{
+    CreateRfqData, PrivatePaymentDetails, Rfq, RfqData, RfqPrivateData, SelectedPayinMethod,
+    SelectedPayoutMethod,
+};
+use crate::{
+    context::CreationContext,
+    errors::{Result, TbdexError},
+};
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// Identifies which part of the RFQ a selectively disclosable value belongs to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DisclosureTarget {
+    /// A single field of the payin payment details.
+    Payin,
+    /// A single field of the payout payment details.
+    Payout,
+    /// A single credential (VC JWT) of the claims.
+    Claims,
+}
+
+/// A salted, individually hashed piece of RFQ private data.
+///
+/// Follows the SD-JWT disclosure encoding: a payment details field is the base64url encoding of
+/// the JCS serialized array `[salt, name, value]`, and a credential is `[salt, value]`. The
+/// digest of a disclosure is the base64url encoded SHA-256 of its encoded form, and is the value
+/// committed to in the signed `data` of the RFQ.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disclosure {
+    /// The random salt unique to this disclosure.
+    pub salt: String,
+
+    /// The field name for payment details disclosures, or `None` for credential disclosures.
+    pub name: Option<String>,
+
+    /// The disclosed value.
+    pub value: Value,
+
+    encoded: String,
+}
+
+impl Disclosure {
+    /// Creates a disclosure for a single named field.
+    pub fn new_field(name: &str, value: &Value) -> Result<Self> {
+        Self::new_field_with_context(name, value, &CreationContext::default())
+    }
+
+    /// Creates a disclosure for a single named field, salted from the given context.
+    pub fn new_field_with_context(
+        name: &str,
+        value: &Value,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        let salt = context.salt();
+        let encoded = encode(&serde_json::json!([salt, name, value]))?;
+        Ok(Self {
+            salt,
+            name: Some(name.to_string()),
+            value: value.clone(),
+            encoded,
+        })
+    }
+
+    /// Creates a disclosure for a single array element, such as a credential.
+    pub fn new_element(value: &Value) -> Result<Self> {
+        Self::new_element_with_context(value, &CreationContext::default())
+    }
+
+    /// Creates a disclosure for a single array element, salted from the given context.
+    pub fn new_element_with_context(value: &Value, context: &CreationContext) -> Result<Self> {
+        let salt = context.salt();
+        let encoded = encode(&serde_json::json!([salt, value]))?;
+        Ok(Self {
+            salt,
+            name: None,
+            value: value.clone(),
+            encoded,
+        })
+    }
+
+    /// Parses an encoded disclosure as found in `privateData.disclosures`.
+    pub fn parse(encoded: &str) -> Result<Self> {
+        let bytes = general_purpose::URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|e| TbdexError::SelectiveDisclosure(format!("invalid base64url {}", e)))?;
+        let array = match serde_json::from_slice::<Value>(&bytes)? {
+            Value::Array(array) => array,
+            _ => {
+                return Err(TbdexError::SelectiveDisclosure(
+                    "disclosure is not a json array".to_string(),
+                ))
+            }
+        };
+
+        let salt = match array.first() {
+            Some(Value::String(salt)) => salt.clone(),
+            _ => {
+                return Err(TbdexError::SelectiveDisclosure(
+                    "disclosure salt must be a string".to_string(),
+                ))
+            }
+        };
+
+        let (name, value) = match array.len() {
+            2 => (None, array[1].clone()),
+            3 => match &array[1] {
+                Value::String(name) => (Some(name.clone()), array[2].clone()),
+                _ => {
+                    return Err(TbdexError::SelectiveDisclosure(
+                        "disclosure name must be a string".to_string(),
+                    ))
+                }
+            },
+            n => {
+                return Err(TbdexError::SelectiveDisclosure(format!(
+                    "disclosure has wrong number of elements {}",
+                    n
+                )))
+            }
+        };
+
+        Ok(Self {
+            salt,
+            name,
+            value,
+            encoded: encoded.to_string(),
+        })
+    }
+
+    /// The encoded form of the disclosure, as carried in `privateData.disclosures`.
+    pub fn encoded(&self) -> &str {
+        &self.encoded
+    }
+
+    /// The digest of the disclosure, as committed to in the RFQ's `data`.
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.encoded.as_bytes());
+        general_purpose::URL_SAFE_NO_PAD.encode(hasher.finalize())
+    }
+}
+
+/// The subset of selectively disclosable private data to reveal to a recipient.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DisclosureSelection {
+    /// Names of the payin payment details fields to disclose.
+    pub payin_fields: Vec<String>,
+
+    /// Names of the payout payment details fields to disclose.
+    pub payout_fields: Vec<String>,
+
+    /// VC JWTs of the claims to disclose.
+    pub claims: Vec<String>,
+}
+
+impl Rfq {
+    /// Creates a new RFQ message whose private data is selectively disclosable.
+    ///
+    /// Rather than hashing the payin details, payout details and claims each as a whole, every
+    /// field of the payment details and every credential is given its own salt and digest. The
+    /// resulting RFQ can later be narrowed with [`Rfq::disclose`] so that only chosen fields are
+    /// revealed, while the signature over `data` remains valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The DID of the recipient (the PFI).
+    /// * `from` - The DID of the sender (Alice).
+    /// * `create_rfq_data` - The data required to generate the RFQ; payment details must be JSON objects.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `external_id` - Optional external ID for additional identification.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Rfq` carrying one disclosure per private field, with an empty signature.
+    pub fn create_with_selective_disclosure(
+        to: &str,
+        from: &str,
+        create_rfq_data: &CreateRfqData,
+        protocol: Option<String>,
+        external_id: Option<String>,
+    ) -> Result<Self> {
+        Self::create_with_selective_disclosure_and_context(
+            to,
+            from,
+            create_rfq_data,
+            protocol,
+            external_id,
+            &CreationContext::default(),
+        )
+    }
+
+    /// Creates a new RFQ message whose private data is selectively disclosable, taking its id,
+    /// timestamp and disclosure salts from the given context.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The DID of the recipient (the PFI).
+    /// * `from` - The DID of the sender (Alice).
+    /// * `create_rfq_data` - The data required to generate the RFQ; payment details must be JSON objects.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `external_id` - Optional external ID for additional identification.
+    /// * `context` - The clock, id source and salt source to create the message with.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Rfq`, as from [`Rfq::create_with_selective_disclosure`] with the
+    /// default context.
+    pub fn create_with_selective_disclosure_and_context(
+        to: &str,
+        from: &str,
+        create_rfq_data: &CreateRfqData,
+        protocol: Option<String>,
+        external_id: Option<String>,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        Rfq::create_hashed(to, from, protocol, external_id, context, |context| {
+            hash_private_data_selectively(create_rfq_data, context)
+        })
+    }
+
+    /// Produces a copy of the RFQ whose private data contains only the selected disclosures.
+    ///
+    /// The signature is carried over unchanged, since `data` is not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `selection` - The payment details fields and credentials to disclose.
+    ///
+    /// # Returns
+    ///
+    /// The narrowed RFQ, or an error if a selected field or credential has no disclosure.
+    pub fn disclose(&self, selection: &DisclosureSelection) -> Result<Rfq> {
+        let private_data = self.private_data.as_ref().ok_or_else(|| {
+            TbdexError::SelectiveDisclosure("rfq has no private data".to_string())
+        })?;
+
+        let disclosures = self.classify_disclosures(private_data)?;
+
+        let mut selected = Vec::new();
+        let mut select = |target: DisclosureTarget, wanted: &Vec<String>| -> Result<()> {
+            for w in wanted {
+                let disclosure = disclosures
+                    .iter()
+                    .find(|(t, d)| {
+                        *t == target
+                            && match &target {
+                                DisclosureTarget::Claims => d.value.as_str() == Some(w.as_str()),
+                                _ => d.name.as_deref() == Some(w.as_str()),
+                            }
+                    })
+                    .ok_or_else(|| {
+                        TbdexError::SelectiveDisclosure(format!(
+                            "no {:?} disclosure found for {}",
+                            target, w
+                        ))
+                    })?;
+                selected.push(disclosure.1.encoded().to_string());
+            }
+            Ok(())
+        };
+        select(DisclosureTarget::Payin, &selection.payin_fields)?;
+        select(DisclosureTarget::Payout, &selection.payout_fields)?;
+        select(DisclosureTarget::Claims, &selection.claims)?;
+
+        let mut rfq = self.clone();
+        rfq.private_data = Some(RfqPrivateData {
+            salt: private_data.salt.clone(),
+            payin: None,
+            payout: None,
+            claims: None,
+            disclosures: Some(selected.into()),
+        });
+
+        Ok(rfq)
+    }
+
+    /// Returns the disclosed private data of the given target.
+    ///
+    /// For payin and payout this is an object of the disclosed payment details fields; for claims
+    /// it is an array of the disclosed VC JWTs. Returns `None` when nothing of that target is disclosed.
+    pub fn disclosed(&self, target: DisclosureTarget) -> Result<Option<Value>> {
+        let private_data = match &self.private_data {
+            None => return Ok(None),
+            Some(pd) => pd,
+        };
+
+        let disclosures = self
+            .classify_disclosures(private_data)?
+            .into_iter()
+            .filter(|(t, _)| *t == target)
+            .map(|(_, d)| d)
+            .collect::<Vec<Disclosure>>();
+
+        if disclosures.is_empty() {
+            return Ok(None);
+        }
+
+        let value = match target {
+            DisclosureTarget::Claims => {
+                Value::Array(disclosures.into_iter().map(|d| d.value).collect())
+            }
+            _ => Value::Object(
+                disclosures
+                    .into_iter()
+                    .filter_map(|d| d.name.map(|name| (name, d.value)))
+                    .collect::<Map<String, Value>>(),
+            ),
+        };
+
+        Ok(Some(value))
+    }
+
+    /// Reassembles the disclosed fields into the shape of whole-object hashed private data, so
+    /// that it can be checked against an offering's requirements.
+    pub(crate) fn reassemble_disclosed_private_data(&self) -> Result<RfqPrivateData> {
+        let salt = self
+            .private_data
+            .as_ref()
+            .map(|pd| pd.salt.clone())
+            .unwrap_or_default();
+
+        let payment_details = |target| -> Result<Option<PrivatePaymentDetails>> {
+            Ok(self.disclosed(target)?.map(|pd| PrivatePaymentDetails {
+                payment_details: Some(pd.into()),
+            }))
+        };
+
+        let claims = match self.disclosed(DisclosureTarget::Claims)? {
+            Some(claims) => Some(serde_json::from_value::<Vec<String>>(claims)?.into()),
+            None => None,
+        };
+
+        Ok(RfqPrivateData {
+            salt,
+            payin: payment_details(DisclosureTarget::Payin)?,
+            payout: payment_details(DisclosureTarget::Payout)?,
+            claims,
+            disclosures: None,
+        })
+    }
+
+    /// Verifies each disclosure against the digests committed to in `data`.
+    ///
+    /// Every disclosure must match exactly one digest. When `require_all` is set, every committed
+    /// digest must also have a matching disclosure. An RFQ which commits to some of its private
+    /// data with hashes and to some with digests is rejected.
+    pub(crate) fn verify_disclosures(
+        &self,
+        private_data: &RfqPrivateData,
+        require_all: bool,
+    ) -> Result<()> {
+        let hashed = self.data.payin.payment_details_hash.is_some()
+            || self.data.payout.payment_details_hash.is_some()
+            || self.data.claims_hash.is_some()
+            || private_data.payin.is_some()
+            || private_data.payout.is_some()
+            || private_data.claims.is_some();
+        let digested =
+            !committed_digests(&self.data).is_empty() || private_data.disclosures.is_some();
+        if hashed && digested {
+            return Err(TbdexError::PrivateDataVerification(
+                "private data mixes hashes with selectively disclosed digests".to_string(),
+            ));
+        }
+
+        let disclosures = self.classify_disclosures(private_data).map_err(|e| {
+            TbdexError::PrivateDataVerification(format!("invalid disclosures: {}", e))
+        })?;
+
+        if require_all {
+            let disclosed = disclosures
+                .iter()
+                .map(|(_, d)| d.digest())
+                .collect::<HashSet<String>>();
+            let committed = committed_digests(&self.data)
+                .into_iter()
+                .flat_map(|(_, digests)| digests.iter().cloned())
+                .collect::<Vec<String>>();
+
+            if let Some(missing) = committed.iter().find(|d| !disclosed.contains(*d)) {
+                return Err(TbdexError::PrivateDataVerification(format!(
+                    "private data missing disclosure for digest {}",
+                    missing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn classify_disclosures(
+        &self,
+        private_data: &RfqPrivateData,
+    ) -> Result<Vec<(DisclosureTarget, Disclosure)>> {
+        let encoded_disclosures = match private_data.disclosures.as_deref() {
+            None => return Ok(vec![]),
+            Some(d) => d,
+        };
+
+        let committed = committed_digests(&self.data);
+
+        let mut seen = HashSet::new();
+        let mut classified = Vec::with_capacity(encoded_disclosures.len());
+        for encoded in encoded_disclosures {
+            let disclosure = Disclosure::parse(encoded)?;
+            let digest = disclosure.digest();
+
+            if !seen.insert(digest.clone()) {
+                return Err(TbdexError::SelectiveDisclosure(format!(
+                    "duplicate disclosure for digest {}",
+                    digest
+                )));
+            }
+
+            let target = committed
+                .iter()
+                .find(|(_, digests)| digests.contains(&digest))
+                .map(|(target, _)| target.clone())
+                .ok_or_else(|| {
+                    TbdexError::SelectiveDisclosure(format!(
+                        "disclosure digest {} not committed to in rfq data",
+                        digest
+                    ))
+                })?;
+
+            let well_formed = match target {
+                DisclosureTarget::Claims => {
+                    disclosure.name.is_none() && disclosure.value.is_string()
+                }
+                _ => disclosure.name.is_some(),
+            };
+            if !well_formed {
+                return Err(TbdexError::SelectiveDisclosure(format!(
+                    "disclosure with digest {} is malformed for {:?}",
+                    digest, target
+                )));
+            }
+
+            classified.push((target, disclosure));
+        }
+
+        Ok(classified)
+    }
+}
+
+fn committed_digests(data: &RfqData) -> Vec<(DisclosureTarget, &Vec<String>)> {
+    let mut committed = Vec::new();
+    if let Some(digests) = &data.payin.payment_details_digests {
+        committed.push((DisclosureTarget::Payin, digests));
+    }
+    if let Some(digests) = &data.payout.payment_details_digests {
+        committed.push((DisclosureTarget::Payout, digests));
+    }
+    if let Some(digests) = &data.claims_digests {
+        committed.push((DisclosureTarget::Claims, digests));
+    }
+    committed
+}
+
+fn hash_private_data_selectively(
+    create_rfq_data: &CreateRfqData,
+    context: &CreationContext,
+) -> Result<(RfqData, RfqPrivateData)> {
+    let mut disclosures = Vec::new();
+
+    let mut disclose_fields = |payment_details: Option<&Value>| -> Result<Option<Vec<String>>> {
+        let fields = match payment_details {
+            None => return Ok(None),
+            Some(Value::Object(fields)) => fields,
+            Some(_) => {
+                return Err(TbdexError::SelectiveDisclosure(
+                    "payment details must be a json object".to_string(),
+                ))
+            }
+        };
+
+        let mut digests = Vec::with_capacity(fields.len());
+        for (name, value) in fields {
+            let disclosure = Disclosure::new_field_with_context(name, value, context)?;
+            digests.push(disclosure.digest());
+            disclosures.push(disclosure.encoded().to_string());
+        }
+        // sorted so that the order of digests reveals nothing about the fields
+        digests.sort();
+        Ok(Some(digests))
+    };
+
+    let payin_digests = disclose_fields(create_rfq_data.payin.payment_details.as_deref())?;
+    let payout_digests = disclose_fields(create_rfq_data.payout.payment_details.as_deref())?;
+
+    let claims_digests = if create_rfq_data.claims.is_empty() {
+        None
+    } else {
+        let mut digests = Vec::with_capacity(create_rfq_data.claims.len());
+        for claim in create_rfq_data.claims.iter() {
+            let disclosure =
+                Disclosure::new_element_with_context(&Value::String(claim.clone()), context)?;
+            digests.push(disclosure.digest());
+            disclosures.push(disclosure.encoded().to_string());
+        }
+        digests.sort();
+        Some(digests)
+    };
+
+    let data = RfqData {
+        offering_id: create_rfq_data.offering_id.clone(),
+        payin: SelectedPayinMethod {
+            kind: create_rfq_data.payin.kind.clone(),
+            payment_details_hash: None,
+            payment_details_digests: payin_digests,
+            amount: create_rfq_data.payin.amount.clone(),
+        },
+        payout: SelectedPayoutMethod {
+            kind: create_rfq_data.payout.kind.clone(),
+            payment_details_hash: None,
+            payment_details_digests: payout_digests,
+        },
+        claims_hash: None,
+        claims_digests,
+    };
+
+    let private_data = RfqPrivateData {
+        // retained because the private data schema requires it; each disclosure carries its own salt
+        salt: context.salt().into(),
+        payin: None,
+        payout: None,
+        claims: None,
+        disclosures: Some(disclosures.into()),
+    };
+
+    Ok((data, private_data))
+}
+
+fn encode(value: &Value) -> Result<String> {
+    let serialized = serde_jcs::to_string(value)?;
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(serialized.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::rfq::{CreateSelectedPayinMethod, CreateSelectedPayoutMethod};
+
+    fn create_rfq() -> Rfq {
+        Rfq::create_with_selective_disclosure(
+            "did:test:pfi",
+            "did:test:alice",
+            &CreateRfqData {
+                offering_id: "offering_123".to_string(),
+                payin: CreateSelectedPayinMethod {
+                    kind: "USD_BANK_TRANSFER".to_string(),
+                    payment_details: Some(
+                        serde_json::json!({
+                            "accountNumber": "1234567890",
+                            "address": "1 Main St"
+                        })
+                        .into(),
+                    ),
+                    amount: "101".to_string(),
+                },
+                payout: CreateSelectedPayoutMethod {
+                    kind: "BTC_ADDRESS".to_string(),
+                    payment_details: Some(serde_json::json!({"btcAddress": "bc1q..."}).into()),
+                },
+                claims: vec!["vc-jwt-1".to_string(), "vc-jwt-2".to_string()].into(),
+            },
+            None,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn can_create_and_verify_all() {
+        let rfq = create_rfq();
+
+        assert_eq!(
+            2,
+            rfq.data
+                .payin
+                .payment_details_digests
+                .as_ref()
+                .unwrap()
+                .len()
+        );
+        assert_eq!(2, rfq.data.claims_digests.as_ref().unwrap().len());
+
+        rfq.verify_all_private_data().unwrap();
+        rfq.verify_present_private_data().unwrap();
+    }
+
+    #[test]
+    fn can_disclose_single_field() {
+        let rfq = create_rfq();
+
+        let disclosed = rfq
+            .disclose(&DisclosureSelection {
+                payin_fields: vec!["accountNumber".to_string()],
+                claims: vec!["vc-jwt-2".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(rfq.data, disclosed.data);
+        disclosed.verify_present_private_data().unwrap();
+        assert!(disclosed.verify_all_private_data().is_err());
+
+        assert_eq!(
+            Some(serde_json::json!({"accountNumber": "1234567890"})),
+            disclosed.disclosed(DisclosureTarget::Payin).unwrap()
+        );
+        assert_eq!(None, disclosed.disclosed(DisclosureTarget::Payout).unwrap());
+        assert_eq!(
+            Some(serde_json::json!(["vc-jwt-2"])),
+            disclosed.disclosed(DisclosureTarget::Claims).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_selection() {
+        let rfq = create_rfq();
+
+        let result = rfq.disclose(&DisclosureSelection {
+            payout_fields: vec!["accountNumber".to_string()],
+            ..Default::default()
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_disclosure() {
+        let mut rfq = create_rfq();
+
+        let forged = Disclosure::new_field("accountNumber", &serde_json::json!("0000")).unwrap();
+        rfq.private_data
+            .as_mut()
+            .unwrap()
+            .disclosures
+            .as_mut()
+            .unwrap()
+            .push(forged.encoded().to_string());
+
+        assert!(rfq.verify_present_private_data().is_err());
+    }
+
+    #[test]
+    fn rejects_mixed_hashes_and_digests() {
+        let mut hashed_data = create_rfq();
+        hashed_data.data.claims_hash = Some("hash".to_string());
+        assert!(hashed_data.verify_present_private_data().is_err());
+
+        let mut hashed_private_data = create_rfq();
+        hashed_private_data.private_data.as_mut().unwrap().claims =
+            Some(vec!["vc-jwt-1".to_string()].into());
+        assert!(hashed_private_data.verify_present_private_data().is_err());
+        assert!(hashed_private_data.verify_all_private_data().is_err());
+    }
+
+    #[test]
+    fn disclosure_round_trips() {
+        let disclosure = Disclosure::new_field("name", &serde_json::json!("value")).unwrap();
+        let parsed = Disclosure::parse(disclosure.encoded()).unwrap();
+
+        assert_eq!(disclosure, parsed);
+        assert_eq!(disclosure.digest(), parsed.digest());
+    }
+}
@@ -0,0 +1,395 @@
+//! Hooks for measuring the `http_client` functions and the `verify` paths.
+//!
+//! Install an [`Observer`] with [`set_observer`] and each operation reports its name, the PFI it
+//! concerned, how long it took and whether it failed. [`PrometheusObserver`] aggregates these for
+//! export in the Prometheus text exposition format.
+
+use crate::errors::TbdexError;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    future::Future,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use web5::errors::Web5Error;
+
+/// What kind of failure an operation ended in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorCategory {
+    /// The PFI could not be reached or responded with an error.
+    Http,
+    /// A DID could not be resolved.
+    Resolution,
+    /// A signature was invalid. Other errors from web5's JOSE and crypto code land here too.
+    Signature,
+    /// A value did not match its JSON schema, or had unknown fields.
+    Schema,
+    /// A value could not be parsed.
+    Parse,
+    /// The message claimed a protocol version which is not supported.
+    Protocol,
+    /// An RFQ did not satisfy its offering, or an offering was malformed.
+    OfferingRequirements,
+    /// Private data did not match the hashes or digests committed to.
+    PrivateData,
+    /// Anything else.
+    Other,
+}
+
+impl ErrorCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCategory::Http => "http",
+            ErrorCategory::Resolution => "resolution",
+            ErrorCategory::Signature => "signature",
+            ErrorCategory::Schema => "schema",
+            ErrorCategory::Parse => "parse",
+            ErrorCategory::Protocol => "protocol",
+            ErrorCategory::OfferingRequirements => "offering_requirements",
+            ErrorCategory::PrivateData => "private_data",
+            ErrorCategory::Other => "other",
+        }
+    }
+}
+
+impl From<&TbdexError> for ErrorCategory {
+    fn from(error: &TbdexError) -> Self {
+        match error {
+            TbdexError::Http(_) | TbdexError::HttpClient(_) => ErrorCategory::Http,
+            #[cfg(feature = "client")]
+            TbdexError::HttpStdError(_) | TbdexError::ErrorResponseBody(_) => ErrorCategory::Http,
+            TbdexError::Web5Error(Web5Error::Resolution(_)) => ErrorCategory::Resolution,
+            TbdexError::Web5Error(_) | TbdexError::Jose(_) => ErrorCategory::Signature,
+            TbdexError::JsonSchema(_)
+            | TbdexError::UnknownFields(_)
+            | TbdexError::InvalidPaymentDetails(_) => ErrorCategory::Schema,
+            TbdexError::Json(_) | TbdexError::Parse(_) | TbdexError::TypeId(_) => {
+                ErrorCategory::Parse
+            }
+            TbdexError::UnsupportedProtocolVersion(_) | TbdexError::OrderStatusTransition(_) => {
+                ErrorCategory::Protocol
+            }
+            TbdexError::OfferingVerification(_)
+            | TbdexError::OfferingLint(_)
+            | TbdexError::CancelNotPermitted(_) => ErrorCategory::OfferingRequirements,
+            TbdexError::PrivateDataVerification(_) | TbdexError::SelectiveDisclosure(_) => {
+                ErrorCategory::PrivateData
+            }
+            TbdexError::AsyncRuntime(_) | TbdexError::Generic(_) => ErrorCategory::Other,
+        }
+    }
+}
+
+/// How an operation ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Outcome {
+    Success,
+    Failure(ErrorCategory),
+}
+
+/// A completed operation, as reported to an [`Observer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation<'a> {
+    /// The name of the operation, e.g. `get_offerings`, `verify_rfq` or
+    /// `verify_offering_requirements`.
+    pub name: &'static str,
+    /// The DID of the PFI the operation concerned: the recipient of messages sent by wallets and
+    /// the sender of everything else.
+    pub pfi_did: &'a str,
+    pub duration: Duration,
+    pub outcome: Outcome,
+}
+
+/// Receives every completed operation. Called synchronously, so it should return quickly.
+pub trait Observer: Send + Sync {
+    fn observe(&self, operation: &Operation);
+}
+
+lazy_static::lazy_static! {
+    static ref OBSERVER: RwLock<Option<Arc<dyn Observer>>> = RwLock::new(None);
+}
+
+/// Installs the observer for all subsequent operations in this process, replacing any other.
+pub fn set_observer(observer: Arc<dyn Observer>) {
+    *OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = Some(observer);
+}
+
+/// Removes the observer installed with [`set_observer`].
+pub fn clear_observer() {
+    *OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+// tests run in parallel, so each observes only its own thread rather than the whole process
+#[cfg(test)]
+thread_local! {
+    static TEST_OBSERVER: std::cell::RefCell<Option<Arc<dyn Observer>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Runs `f` with the observer installed for the current thread only.
+#[cfg(test)]
+fn with_test_observer<T>(observer: Arc<dyn Observer>, f: impl FnOnce() -> T) -> T {
+    TEST_OBSERVER.with(|o| *o.borrow_mut() = Some(observer));
+    let result = f();
+    TEST_OBSERVER.with(|o| *o.borrow_mut() = None);
+    result
+}
+
+fn current_observer() -> Option<Arc<dyn Observer>> {
+    #[cfg(test)]
+    if let Some(observer) = TEST_OBSERVER.with(|o| o.borrow().clone()) {
+        return Some(observer);
+    }
+
+    OBSERVER.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn report<T>(name: &'static str, pfi_did: &str, started: Instant, result: &Result<T, TbdexError>) {
+    if let Some(observer) = current_observer() {
+        observer.observe(&Operation {
+            name,
+            pfi_did,
+            duration: started.elapsed(),
+            outcome: match result {
+                Ok(_) => Outcome::Success,
+                Err(e) => Outcome::Failure(e.into()),
+            },
+        });
+    }
+}
+
+/// Runs the operation, reporting it to the observer once it completes.
+pub(crate) async fn observe<T>(
+    name: &'static str,
+    pfi_did: &str,
+    operation: impl Future<Output = Result<T, TbdexError>>,
+) -> Result<T, TbdexError> {
+    let started = Instant::now();
+    let result = operation.await;
+    report(name, pfi_did, started, &result);
+    result
+}
+
+/// As [`observe`], for operations which do not wait.
+pub(crate) fn observe_sync<T>(
+    name: &'static str,
+    pfi_did: &str,
+    operation: impl FnOnce() -> Result<T, TbdexError>,
+) -> Result<T, TbdexError> {
+    let started = Instant::now();
+    let result = operation();
+    report(name, pfi_did, started, &result);
+    result
+}
+
+/// The upper bounds, in seconds, of the duration histogram's buckets.
+pub const DURATION_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+#[derive(Default)]
+struct Series {
+    bucket_counts: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum_seconds: f64,
+}
+
+/// An [`Observer`] which aggregates operations for Prometheus.
+///
+/// [`PrometheusObserver::render`] returns, in the text exposition format:
+///
+/// * `tbdex_operation_duration_seconds`, a histogram labelled by `operation`, `pfi_did` and
+///   `outcome` (`success` or `failure`)
+/// * `tbdex_operation_errors_total`, a counter labelled by `operation`, `pfi_did` and `category`
+#[derive(Default)]
+pub struct PrometheusObserver {
+    durations: Mutex<BTreeMap<(&'static str, String, bool), Series>>,
+    errors: Mutex<BTreeMap<(&'static str, String, ErrorCategory), u64>>,
+}
+
+impl PrometheusObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders everything observed so far.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        output
+            .push_str("# HELP tbdex_operation_duration_seconds How long tbDEX operations take.\n");
+        output.push_str("# TYPE tbdex_operation_duration_seconds histogram\n");
+        let durations = self.durations.lock().unwrap_or_else(|e| e.into_inner());
+        for ((name, pfi_did, succeeded), series) in durations.iter() {
+            let labels = format!(
+                "operation=\"{}\",pfi_did=\"{}\",outcome=\"{}\"",
+                name,
+                escape_label(pfi_did),
+                if *succeeded { "success" } else { "failure" }
+            );
+            let mut cumulative = 0;
+            for (bound, count) in DURATION_BUCKETS.iter().zip(series.bucket_counts) {
+                cumulative += count;
+                let _ = writeln!(
+                    output,
+                    "tbdex_operation_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                output,
+                "tbdex_operation_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, series.count
+            );
+            let _ = writeln!(
+                output,
+                "tbdex_operation_duration_seconds_sum{{{}}} {}",
+                labels, series.sum_seconds
+            );
+            let _ = writeln!(
+                output,
+                "tbdex_operation_duration_seconds_count{{{}}} {}",
+                labels, series.count
+            );
+        }
+        drop(durations);
+
+        output.push_str("# HELP tbdex_operation_errors_total Failed tbDEX operations by cause.\n");
+        output.push_str("# TYPE tbdex_operation_errors_total counter\n");
+        let errors = self.errors.lock().unwrap_or_else(|e| e.into_inner());
+        for ((name, pfi_did, category), count) in errors.iter() {
+            let _ = writeln!(
+                output,
+                "tbdex_operation_errors_total{{operation=\"{}\",pfi_did=\"{}\",category=\"{}\"}} {}",
+                name,
+                escape_label(pfi_did),
+                category.as_str(),
+                count
+            );
+        }
+
+        output
+    }
+}
+
+impl Observer for PrometheusObserver {
+    fn observe(&self, operation: &Operation) {
+        let seconds = operation.duration.as_secs_f64();
+        let succeeded = operation.outcome == Outcome::Success;
+
+        {
+            let mut durations = self.durations.lock().unwrap_or_else(|e| e.into_inner());
+            let series = durations
+                .entry((operation.name, operation.pfi_did.to_string(), succeeded))
+                .or_default();
+            if let Some(bucket) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+                series.bucket_counts[bucket] += 1;
+            }
+            series.count += 1;
+            series.sum_seconds += seconds;
+        }
+
+        if let Outcome::Failure(category) = operation.outcome {
+            let mut errors = self.errors.lock().unwrap_or_else(|e| e.into_inner());
+            *errors
+                .entry((operation.name, operation.pfi_did.to_string(), category))
+                .or_default() += 1;
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categorizes_errors() {
+        assert_eq!(
+            ErrorCategory::Schema,
+            ErrorCategory::from(&TbdexError::JsonSchema("bad".to_string()))
+        );
+        assert_eq!(
+            ErrorCategory::Http,
+            ErrorCategory::from(&TbdexError::Http("500".to_string()))
+        );
+        assert_eq!(
+            ErrorCategory::PrivateData,
+            ErrorCategory::from(&TbdexError::PrivateDataVerification("bad".to_string()))
+        );
+    }
+
+    #[test]
+    fn renders_prometheus_text() {
+        let observer = PrometheusObserver::new();
+        let operation = |duration_ms, outcome| Operation {
+            name: "get_offerings",
+            pfi_did: "did:test:pfi",
+            duration: Duration::from_millis(duration_ms),
+            outcome,
+        };
+        observer.observe(&operation(3, Outcome::Success));
+        observer.observe(&operation(30, Outcome::Success));
+        observer.observe(&operation(7, Outcome::Failure(ErrorCategory::Signature)));
+
+        let rendered = observer.render();
+        let labels = r#"operation="get_offerings",pfi_did="did:test:pfi""#;
+        assert!(rendered.contains(&format!(
+            "tbdex_operation_duration_seconds_bucket{{{},outcome=\"success\",le=\"0.005\"}} 1",
+            labels
+        )));
+        assert!(rendered.contains(&format!(
+            "tbdex_operation_duration_seconds_bucket{{{},outcome=\"success\",le=\"+Inf\"}} 2",
+            labels
+        )));
+        assert!(rendered.contains(&format!(
+            "tbdex_operation_duration_seconds_count{{{},outcome=\"failure\"}} 1",
+            labels
+        )));
+        assert!(rendered.contains(&format!(
+            "tbdex_operation_errors_total{{{},category=\"signature\"}} 1",
+            labels
+        )));
+    }
+
+    #[test]
+    fn observes_verification() {
+        use crate::resources::balance::{Balance, BalanceData};
+        use web5::dids::methods::did_jwk::DidJwk;
+
+        let bearer_did = DidJwk::create(None).unwrap();
+        let mut tampered = Balance::create(
+            &bearer_did.did.uri,
+            &BalanceData {
+                currency_code: "USD".to_string(),
+                available: "100.00".to_string(),
+            },
+            None,
+        )
+        .unwrap();
+        tampered.sign(&bearer_did).unwrap();
+        tampered.data.available = "1000000.00".to_string();
+
+        let observer = Arc::new(PrometheusObserver::new());
+        with_test_observer(observer.clone(), || {
+            assert!(futures::executor::block_on(tampered.verify()).is_err());
+        });
+
+        assert!(observer.render().contains(&format!(
+            "tbdex_operation_errors_total{{operation=\"verify_balance\",pfi_did=\"{}\",category=\"signature\"}} 1",
+            bearer_did.did.uri
+        )));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(r#"a\"b\\c\n"#, escape_label("a\"b\\c\n"));
+    }
+}
@@ -0,0 +1,237 @@
+pub mod v1;
+
+use crate::{
+    errors::{Result, TbdexError},
+    json_schemas::{
+        generated::{v1_0, v2_0},
+        ProtocolSchema,
+    },
+    messages::MessageKind,
+    resources::ResourceKind,
+};
+use std::{fmt, str::FromStr};
+
+/// A version of the tbDEX protocol supported by this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProtocolVersion {
+    /// tbDEX 1.0. Messages and resources whose data model differs from the current one must be
+    /// converted with the functions in [`v1`] before use.
+    V1_0,
+
+    /// tbDEX 2.0, the version modelled by the types in this crate.
+    V2_0,
+}
+
+impl ProtocolVersion {
+    /// The version created by default and modelled by this crate's message and resource types.
+    pub const CURRENT: ProtocolVersion = ProtocolVersion::V2_0;
+
+    /// All versions this crate can verify or upgrade.
+    pub fn supported() -> &'static [ProtocolVersion] {
+        &[ProtocolVersion::V1_0, ProtocolVersion::V2_0]
+    }
+
+    /// The version string as it appears in `metadata.protocol`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_0 => "1.0",
+            ProtocolVersion::V2_0 => "2.0",
+        }
+    }
+
+    /// The JSON schema of the message envelope for this version.
+    pub(crate) fn message_schema(&self) -> ProtocolSchema {
+        self.schema(match self {
+            ProtocolVersion::V1_0 => v1_0::MESSAGE_JSON_SCHEMA,
+            ProtocolVersion::V2_0 => v2_0::MESSAGE_JSON_SCHEMA,
+        })
+    }
+
+    /// The JSON schema of the resource envelope for this version.
+    pub(crate) fn resource_schema(&self) -> ProtocolSchema {
+        self.schema(match self {
+            ProtocolVersion::V1_0 => v1_0::RESOURCE_JSON_SCHEMA,
+            ProtocolVersion::V2_0 => v2_0::RESOURCE_JSON_SCHEMA,
+        })
+    }
+
+    /// The JSON schema of RFQ private data for this version.
+    pub(crate) fn rfq_private_data_schema(&self) -> ProtocolSchema {
+        self.schema(match self {
+            ProtocolVersion::V1_0 => v1_0::RFQ_PRIVATE_DATA_JSON_SCHEMA,
+            ProtocolVersion::V2_0 => v2_0::RFQ_PRIVATE_DATA_JSON_SCHEMA,
+        })
+    }
+
+    /// The JSON schema of the `data` of the given message kind, when that message kind shares
+    /// the current data model in this version.
+    pub(crate) fn message_data_schema(&self, kind: &MessageKind) -> Result<ProtocolSchema> {
+        match (self, kind) {
+            (ProtocolVersion::V1_0, MessageKind::Quote | MessageKind::OrderStatus) => {
+                Err(TbdexError::UnsupportedProtocolVersion(format!(
+                    "{} data model differs in protocol version {}, upgrade it with protocol::v1",
+                    kind, self
+                )))
+            }
+            _ => self.original_message_data_schema(kind),
+        }
+    }
+
+    /// The JSON schema of the `data` of the given message kind as defined by this version,
+    /// whether or not it shares the current data model.
+    pub(crate) fn original_message_data_schema(
+        &self,
+        kind: &MessageKind,
+    ) -> Result<ProtocolSchema> {
+        let schema = match (self, kind) {
+            (ProtocolVersion::V1_0, MessageKind::Rfq) => v1_0::RFQ_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V1_0, MessageKind::Quote) => v1_0::QUOTE_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V1_0, MessageKind::Order) => v1_0::ORDER_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V1_0, MessageKind::OrderStatus) => {
+                v1_0::ORDER_STATUS_DATA_JSON_SCHEMA
+            }
+            (ProtocolVersion::V1_0, MessageKind::Close) => v1_0::CLOSE_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V1_0, MessageKind::OrderInstructions | MessageKind::Cancel) => {
+                return Err(TbdexError::UnsupportedProtocolVersion(format!(
+                    "{} is not defined in protocol version {}",
+                    kind, self
+                )))
+            }
+            (ProtocolVersion::V2_0, MessageKind::Rfq) => v2_0::RFQ_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V2_0, MessageKind::Quote) => v2_0::QUOTE_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V2_0, MessageKind::Order) => v2_0::ORDER_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V2_0, MessageKind::OrderInstructions) => {
+                v2_0::ORDER_INSTRUCTIONS_DATA_JSON_SCHEMA
+            }
+            (ProtocolVersion::V2_0, MessageKind::Cancel) => v2_0::CANCEL_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V2_0, MessageKind::OrderStatus) => {
+                v2_0::ORDER_STATUS_DATA_JSON_SCHEMA
+            }
+            (ProtocolVersion::V2_0, MessageKind::Close) => v2_0::CLOSE_DATA_JSON_SCHEMA,
+        };
+        Ok(self.schema(schema))
+    }
+
+    /// The JSON schema of the `data` of the given resource kind, when that resource kind shares
+    /// the current data model in this version.
+    pub(crate) fn resource_data_schema(&self, kind: &ResourceKind) -> Result<ProtocolSchema> {
+        match (self, kind) {
+            (ProtocolVersion::V1_0, ResourceKind::Offering) => {
+                Err(TbdexError::UnsupportedProtocolVersion(format!(
+                    "{} data model differs in protocol version {}, upgrade it with protocol::v1",
+                    kind, self
+                )))
+            }
+            _ => self.original_resource_data_schema(kind),
+        }
+    }
+
+    /// The JSON schema of the `data` of the given resource kind as defined by this version,
+    /// whether or not it shares the current data model.
+    pub(crate) fn original_resource_data_schema(
+        &self,
+        kind: &ResourceKind,
+    ) -> Result<ProtocolSchema> {
+        let schema = match (self, kind) {
+            (ProtocolVersion::V1_0, ResourceKind::Offering) => v1_0::OFFERING_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V1_0, ResourceKind::Balance) => {
+                return Err(TbdexError::UnsupportedProtocolVersion(format!(
+                    "{} is not defined in protocol version {}",
+                    kind, self
+                )))
+            }
+            (ProtocolVersion::V2_0, ResourceKind::Offering) => v2_0::OFFERING_DATA_JSON_SCHEMA,
+            (ProtocolVersion::V2_0, ResourceKind::Balance) => v2_0::BALANCE_DATA_JSON_SCHEMA,
+        };
+        Ok(self.schema(schema))
+    }
+
+    /// Pairs one of this version's schemas with its `definitions.json`.
+    fn schema(&self, schema: &'static str) -> ProtocolSchema {
+        ProtocolSchema {
+            schema,
+            definitions: match self {
+                ProtocolVersion::V1_0 => v1_0::DEFINITIONS_JSON_SCHEMA,
+                ProtocolVersion::V2_0 => v2_0::DEFINITIONS_JSON_SCHEMA,
+            },
+        }
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = TbdexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "1.0" => Ok(ProtocolVersion::V1_0),
+            "2.0" => Ok(ProtocolVersion::V2_0),
+            _ => Err(TbdexError::UnsupportedProtocolVersion(format!(
+                "unsupported protocol version {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_supported_versions() {
+        for version in ProtocolVersion::supported() {
+            assert_eq!(
+                *version,
+                ProtocolVersion::from_str(version.as_str()).unwrap()
+            );
+        }
+
+        assert_eq!(
+            crate::DEFAULT_PROTOCOL_VERSION,
+            ProtocolVersion::CURRENT.as_str()
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        assert!(ProtocolVersion::from_str("3.0").is_err());
+        assert!(ProtocolVersion::from_str("").is_err());
+    }
+
+    #[test]
+    fn selects_schemas_by_version() {
+        assert!(ProtocolVersion::V2_0
+            .message_data_schema(&MessageKind::Cancel)
+            .is_ok());
+        assert!(ProtocolVersion::V1_0
+            .message_data_schema(&MessageKind::Cancel)
+            .is_err());
+        assert!(ProtocolVersion::V1_0
+            .message_data_schema(&MessageKind::Quote)
+            .is_err());
+        assert!(ProtocolVersion::V1_0
+            .message_data_schema(&MessageKind::Rfq)
+            .is_ok());
+        assert!(ProtocolVersion::V1_0
+            .resource_data_schema(&ResourceKind::Offering)
+            .is_err());
+        assert!(ProtocolVersion::V1_0
+            .original_message_data_schema(&MessageKind::Quote)
+            .is_ok());
+        assert!(ProtocolVersion::V1_0
+            .original_resource_data_schema(&ResourceKind::Offering)
+            .is_ok());
+
+        // each version is validated against its own schema set
+        assert_ne!(
+            ProtocolVersion::V1_0.message_schema().schema,
+            ProtocolVersion::V2_0.message_schema().schema
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Conversion of tbDEX 1.0 messages and resources to the current data model.
+//!
+//! Only lossless conversions are performed; anything which cannot be represented in the current
+//! data model without dropping or inventing information is rejected. The signature of the
+//! original covers its 1.0 `data`, so a converted message or resource is returned as an
+//! [`Upgraded`] which keeps the original JSON, protocol version and signature alongside it.
+
+use super::ProtocolVersion;
+use crate::{
+    errors::{ParseError, Result, TbdexError},
+    json_schemas::validate_from_str,
+    messages::{
+        close::Close, order::Order, order_status::OrderStatus, quote::Quote, rfq::Rfq, Message,
+        MessageKind,
+    },
+    resources::{offering::Offering, ResourceKind},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{ops::Deref, str::FromStr, sync::Arc};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuoteDetails {
+    currency_code: String,
+    amount: String,
+    fee: Option<String>,
+    payment_instruction: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuoteData {
+    expires_at: String,
+    payout_units_per_payin_unit: Option<String>,
+    payin: QuoteDetails,
+    payout: QuoteDetails,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderStatusData {
+    order_status: String,
+}
+
+/// A message or resource converted to the current data model, along with the JSON it was
+/// converted from.
+///
+/// The converted value keeps the `metadata.protocol` and `signature` of the original, but its
+/// signature covers the original `data` rather than the converted one; use [`Upgraded::verify`]
+/// to check it.
+#[derive(Clone, Debug)]
+pub struct Upgraded<T> {
+    value: T,
+    original: Value,
+    protocol: ProtocolVersion,
+}
+
+impl<T> Upgraded<T> {
+    /// The protocol version of the original.
+    pub fn protocol(&self) -> ProtocolVersion {
+        self.protocol
+    }
+
+    /// The signature of the original, computed over its `metadata` and `data`.
+    pub fn signature(&self) -> &str {
+        self.original
+            .get("signature")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+    }
+
+    /// The JSON the value was converted from.
+    pub fn original_json(&self) -> &Value {
+        &self.original
+    }
+
+    /// Verifies the signature of the original against its `metadata` and `data`.
+    pub async fn verify(&self) -> Result<()> {
+        verify_original(&self.original).await
+    }
+
+    /// Discards the original and returns the converted value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Upgraded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Converts a JSON serialized message of any supported protocol version to the current model.
+///
+/// Messages already at the current version are parsed unchanged.
+pub fn upgrade_message(json: &str) -> Result<Upgraded<Message>> {
+    let original = serde_json::from_str::<Value>(json)?;
+    let protocol = protocol_of(&original)?;
+
+    let kind = original
+        .pointer("/metadata/kind")
+        .and_then(|k| k.as_str())
+        .ok_or_else(|| TbdexError::Parse(ParseError::at("metadata.kind", "missing field")))
+        .and_then(MessageKind::from_str)?;
+
+    if protocol == ProtocolVersion::CURRENT {
+        let value = serde_json::from_value::<Message>(original.clone())?;
+        return Ok(Upgraded {
+            value,
+            original,
+            protocol,
+        });
+    }
+
+    let data = original
+        .get("data")
+        .ok_or_else(|| TbdexError::Parse(ParseError::at("data", "missing field")))?;
+    validate_from_str(
+        protocol.original_message_data_schema(&kind)?,
+        data,
+        &original["metadata"],
+    )?;
+
+    let mut value = original.clone();
+    let value = match kind {
+        MessageKind::Rfq => Message::Rfq(Arc::new(serde_json::from_value::<Rfq>(value)?)),
+        MessageKind::Quote => {
+            value["data"] = upgrade_quote_data(data.clone())?;
+            Message::Quote(Arc::new(serde_json::from_value::<Quote>(value)?))
+        }
+        MessageKind::Order => Message::Order(Arc::new(serde_json::from_value::<Order>(value)?)),
+        MessageKind::OrderStatus => {
+            value["data"] = upgrade_order_status_data(data.clone())?;
+            Message::OrderStatus(Arc::new(serde_json::from_value::<OrderStatus>(value)?))
+        }
+        MessageKind::Close => Message::Close(Arc::new(serde_json::from_value::<Close>(value)?)),
+        MessageKind::OrderInstructions | MessageKind::Cancel => {
+            return Err(TbdexError::UnsupportedProtocolVersion(format!(
+                "{} is not defined in protocol version {}",
+                kind, protocol
+            )))
+        }
+    };
+
+    Ok(Upgraded {
+        value,
+        original,
+        protocol,
+    })
+}
+
+/// Converts a JSON serialized Offering of any supported protocol version to the current model.
+///
+/// Offerings already at the current version are parsed unchanged.
+pub fn upgrade_offering(json: &str) -> Result<Upgraded<Offering>> {
+    let original = serde_json::from_str::<Value>(json)?;
+    let protocol = protocol_of(&original)?;
+
+    let mut value = original.clone();
+    if protocol != ProtocolVersion::CURRENT {
+        validate_from_str(
+            protocol.original_resource_data_schema(&ResourceKind::Offering)?,
+            &original["data"],
+            &original["metadata"],
+        )?;
+
+        let data = value
+            .get_mut("data")
+            .and_then(|d| d.as_object_mut())
+            .ok_or_else(|| TbdexError::Parse(ParseError::at("data", "missing field")))?;
+
+        // cancellation did not exist in 1.0, so an offering never permitted it
+        if !data.contains_key("cancellation") {
+            data.insert("cancellation".to_string(), json!({ "enabled": false }));
+        }
+    }
+
+    Ok(Upgraded {
+        value: serde_json::from_value::<Offering>(value)?,
+        original,
+        protocol,
+    })
+}
+
+/// Verifies the signature of a JSON serialized message against its original `metadata` and
+/// `data`, then converts it to the current model.
+pub async fn verify_and_upgrade_message(json: &str) -> Result<Upgraded<Message>> {
+    let upgraded = upgrade_message(json)?;
+    upgraded.verify().await?;
+    Ok(upgraded)
+}
+
+/// Verifies the signature of a JSON serialized Offering against its original `metadata` and
+/// `data`, then converts it to the current model.
+pub async fn verify_and_upgrade_offering(json: &str) -> Result<Upgraded<Offering>> {
+    let upgraded = upgrade_offering(json)?;
+    upgraded.verify().await?;
+    Ok(upgraded)
+}
+
+async fn verify_original(value: &Value) -> Result<()> {
+    let field = |name: &str| {
+        value
+            .get(name)
+            .ok_or_else(|| TbdexError::Parse(ParseError::at(name, "missing field")))
+    };
+    let signature = field("signature")?
+        .as_str()
+        .ok_or_else(|| TbdexError::Parse(ParseError::at("signature", "expected a string")))?;
+
+    crate::signature::verify(field("metadata")?, field("data")?, signature).await
+}
+
+fn protocol_of(value: &Value) -> Result<ProtocolVersion> {
+    let protocol = value
+        .pointer("/metadata/protocol")
+        .and_then(|p| p.as_str())
+        .ok_or_else(|| TbdexError::Parse(ParseError::at("metadata.protocol", "missing field")))?;
+
+    ProtocolVersion::from_str(protocol)
+}
+
+fn upgrade_quote_data(data: Value) -> Result<Value> {
+    let data = serde_json::from_value::<QuoteData>(data)?;
+
+    let payout_units_per_payin_unit = data.payout_units_per_payin_unit.ok_or_else(|| {
+        TbdexError::UnsupportedProtocolVersion(
+            "quote without payoutUnitsPerPayinUnit cannot be upgraded losslessly".to_string(),
+        )
+    })?;
+
+    Ok(json!({
+        "expiresAt": data.expires_at,
+        "payoutUnitsPerPayinUnit": payout_units_per_payin_unit,
+        "payin": upgrade_quote_details(data.payin)?,
+        "payout": upgrade_quote_details(data.payout)?,
+    }))
+}
+
+fn upgrade_quote_details(details: QuoteDetails) -> Result<Value> {
+    // payment instructions moved to the OrderInstructions message in 2.0
+    if details.payment_instruction.is_some() {
+        return Err(TbdexError::UnsupportedProtocolVersion(
+            "quote with paymentInstruction cannot be upgraded losslessly".to_string(),
+        ));
+    }
+
+    let total = match &details.fee {
+        Some(fee) => crate::decimal::add(&details.amount, fee)?,
+        None => details.amount.clone(),
+    };
+
+    let mut upgraded = json!({
+        "currencyCode": details.currency_code,
+        "subtotal": details.amount,
+        "total": total,
+    });
+    if let Some(fee) = details.fee {
+        upgraded["fee"] = json!(fee);
+    }
+
+    Ok(upgraded)
+}
+
+fn upgrade_order_status_data(data: Value) -> Result<Value> {
+    let data = serde_json::from_value::<OrderStatusData>(data)?;
+
+    // 1.0 order statuses were free-form, only those naming a 2.0 status convert losslessly
+    let status =
+        crate::messages::order_status::Status::from_str(&data.order_status).map_err(|_| {
+            TbdexError::UnsupportedProtocolVersion(format!(
+                "order status {} cannot be upgraded losslessly",
+                data.order_status
+            ))
+        })?;
+
+    Ok(json!({ "status": status }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(kind: &str, data: Value) -> String {
+        json!({
+            "metadata": {
+                "from": "did:test:pfi",
+                "to": "did:test:alice",
+                "kind": kind,
+                "id": format!("{}_01ha835rhefwmagsknrrhvaa0k", kind),
+                "exchangeId": "rfq_01ha835rhefwmagsknrrhvaa0k",
+                "protocol": "1.0",
+                "createdAt": "2024-01-01T00:00:00Z"
+            },
+            "data": data,
+            "signature": "signature"
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn upgrades_quote() {
+        let json = message(
+            "quote",
+            json!({
+                "expiresAt": "2024-01-02T00:00:00Z",
+                "payoutUnitsPerPayinUnit": "2",
+                "payin": { "currencyCode": "USD", "amount": "99.5", "fee": "0.75" },
+                "payout": { "currencyCode": "MXN", "amount": "199" }
+            }),
+        );
+
+        let upgraded = upgrade_message(&json).unwrap();
+        assert_eq!(ProtocolVersion::V1_0, upgraded.protocol());
+        assert_eq!("signature", upgraded.signature());
+        assert_eq!("99.5", upgraded.original_json()["data"]["payin"]["amount"]);
+
+        let quote = match upgraded.into_inner() {
+            Message::Quote(quote) => quote,
+            _ => panic!("expected quote"),
+        };
+
+        assert_eq!("1.0", quote.metadata.protocol);
+        assert_eq!("signature", quote.signature);
+        assert_eq!("99.5", quote.data.payin.subtotal);
+        assert_eq!("100.25", quote.data.payin.total);
+        assert_eq!("199", quote.data.payout.total);
+    }
+
+    #[test]
+    fn rejects_lossy_quote() {
+        let json = message(
+            "quote",
+            json!({
+                "expiresAt": "2024-01-02T00:00:00Z",
+                "payoutUnitsPerPayinUnit": "2",
+                "payin": {
+                    "currencyCode": "USD",
+                    "amount": "100",
+                    "paymentInstruction": { "link": "https://pay.example" }
+                },
+                "payout": { "currencyCode": "MXN", "amount": "200" }
+            }),
+        );
+
+        assert!(upgrade_message(&json).is_err());
+    }
+
+    #[test]
+    fn upgrades_order_status() {
+        let json = message("orderstatus", json!({ "orderStatus": "PAYOUT_SETTLED" }));
+        assert!(matches!(
+            upgrade_message(&json).unwrap().into_inner(),
+            Message::OrderStatus(_)
+        ));
+
+        let json = message("orderstatus", json!({ "orderStatus": "done!" }));
+        assert!(upgrade_message(&json).is_err());
+    }
+
+    #[test]
+    fn rejects_kinds_missing_from_v1() {
+        let json = message("cancel", json!({}));
+        assert!(upgrade_message(&json).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let json = message("close", json!({})).replace("\"1.0\"", "\"3.0\"");
+        assert!(upgrade_message(&json).is_err());
+    }
+}
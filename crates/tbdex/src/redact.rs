@@ -0,0 +1,199 @@
+//! Keeping private data out of `Debug` output and memory.
+//!
+//! Types carrying private data, such as [`Rfq`](crate::messages::rfq::Rfq) and its
+//! [`RfqPrivateData`](crate::messages::rfq::RfqPrivateData), print [`REDACTED`] in place of it.
+//! Formatting [`Reveal::reveal`] instead opts in to printing everything:
+//!
+//! ```text
+//! println!("{:?}", rfq);          // private_data: Some(RfqPrivateData { salt: [REDACTED], .. })
+//! println!("{:?}", rfq.reveal()); // the payment details and VC JWTs in full
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+use zeroize::Zeroize;
+
+/// Printed in place of private data.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A type whose `Debug` output redacts private data.
+pub trait Reveal {
+    /// Formats the value in full, private data included.
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Returns a view of the value whose `Debug` output includes its private data.
+    fn reveal(&self) -> Revealed<'_, Self> {
+        Revealed(self)
+    }
+}
+
+/// A value whose private data is shown when formatted, returned by [`Reveal::reveal`].
+pub struct Revealed<'a, T: ?Sized>(&'a T);
+
+impl<T: Reveal + ?Sized> fmt::Debug for Revealed<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_revealed(f)
+    }
+}
+
+/// Debug-formats as [`REDACTED`], standing in for a private field.
+#[cfg(any(feature = "client", test))]
+pub(crate) struct Redacted;
+
+#[cfg(any(feature = "client", test))]
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Overwrites every string in the value, then empties it.
+///
+/// Numbers and object keys cannot be overwritten in place, and are only dropped.
+pub(crate) fn zeroize_value(value: &mut Value) {
+    match value {
+        Value::String(s) => s.zeroize(),
+        Value::Array(values) => values.iter_mut().for_each(zeroize_value),
+        Value::Object(map) => map.values_mut().for_each(zeroize_value),
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    *value = Value::Null;
+}
+
+/// A value which can be overwritten in memory, as [`Secret`] does on drop.
+pub trait Wipe {
+    fn wipe(&mut self);
+}
+
+impl Wipe for String {
+    fn wipe(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Wipe for Vec<String> {
+    fn wipe(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Wipe for Value {
+    fn wipe(&mut self) {
+        zeroize_value(self);
+    }
+}
+
+/// A private value: `Debug` formats it as [`REDACTED`] and it is wiped on drop.
+///
+/// It serializes as the value itself, and dereferences to it.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T: Wipe>(T);
+
+impl<T: Wipe> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Takes the value out, leaving it to the caller to wipe.
+    pub fn into_inner(mut self) -> T
+    where
+        T: Default,
+    {
+        std::mem::take(&mut self.0)
+    }
+}
+
+impl<T: Wipe> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Wipe> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Wipe> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Wipe> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Wipe + fmt::Debug> Reveal for Secret<T> {
+    fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: Wipe> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct Password(String);
+
+    impl fmt::Debug for Password {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Password").field(&Redacted).finish()
+        }
+    }
+
+    impl Reveal for Password {
+        fn fmt_revealed(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Password").field(&self.0).finish()
+        }
+    }
+
+    #[test]
+    fn reveals_only_on_request() {
+        let password = Password("hunter2".to_string());
+
+        assert_eq!("Password([REDACTED])", format!("{:?}", password));
+        assert_eq!("Password(\"hunter2\")", format!("{:?}", password.reveal()));
+    }
+
+    #[test]
+    fn zeroizes_nested_values() {
+        let mut value = json!({"card": {"number": "4242"}, "cvv": ["123"]});
+        zeroize_value(&mut value);
+        assert_eq!(Value::Null, value);
+    }
+
+    #[test]
+    fn secrets_serialize_transparently() {
+        let secret = Secret::new(json!({"accountNumber": "0123456789"}));
+
+        assert_eq!("[REDACTED]", format!("{:?}", secret));
+        assert!(format!("{:?}", secret.reveal()).contains("0123456789"));
+        assert_eq!(
+            r#"{"accountNumber":"0123456789"}"#,
+            serde_json::to_string(&secret).unwrap()
+        );
+        assert_eq!(
+            secret,
+            serde_json::from_str(r#"{"accountNumber":"0123456789"}"#).unwrap()
+        );
+        assert_eq!("0123456789", secret["accountNumber"]);
+    }
+}
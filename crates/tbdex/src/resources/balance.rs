@@ -1,18 +1,25 @@
 use super::{ResourceKind, ResourceMetadata, Result};
 use crate::{
-    json::{FromJson, ToJson},
-    json_schemas::generated::{BALANCE_DATA_JSON_SCHEMA, RESOURCE_JSON_SCHEMA},
+    context::CreationContext,
+    digest::ContentHash,
+    errors::ParseError,
+    json::{
+        deserialize_envelope, parse_envelope, parse_value, FromJson, OriginalJson, RawEnvelope,
+        ToJson, UnknownFields,
+    },
+    metrics,
+    protocol::ProtocolVersion,
     DEFAULT_PROTOCOL_VERSION,
 };
-use chrono::Utc;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
 use web5::dids::bearer_did::BearerDid;
 
 /// Represents a Balance resource in the tbDEX protocol.
 ///
 /// A Balance resource is used to communicate the amount of a particular currency
 /// held by a PFI on behalf of a customer. It includes metadata, balance data, and a signature.
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Balance {
     /// Metadata about the resource, including sender, type of resource, and protocol information.
     pub metadata: ResourceMetadata,
@@ -22,10 +29,55 @@ pub struct Balance {
 
     /// The signature verifying the authenticity and integrity of the Balance resource.
     pub signature: String,
+
+    /// The JSON as parsed, which preserves fields not modelled above.
+    pub(crate) original: Option<OriginalJson>,
 }
 
 impl ToJson for Balance {}
-impl FromJson for Balance {}
+impl FromJson for Balance {
+    fn from_json_value(value: &serde_json::Value) -> std::result::Result<Self, ParseError> {
+        parse_envelope(value)
+    }
+}
+
+impl UnknownFields for Balance {
+    fn unknown_fields(&self) -> &[String] {
+        OriginalJson::unknown_fields(self.original.as_ref())
+    }
+}
+
+impl Serialize for Balance {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.preserved_envelope()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Balance {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_envelope(deserializer)
+    }
+}
+
+impl TryFrom<RawEnvelope> for Balance {
+    type Error = ParseError;
+
+    fn try_from(raw: RawEnvelope) -> std::result::Result<Self, ParseError> {
+        let mut balance = Self {
+            metadata: parse_value(&raw.metadata).map_err(|e| e.within("metadata"))?,
+            data: parse_value(&raw.data).map_err(|e| e.within("data"))?,
+            signature: raw.signature.clone(),
+            original: None,
+        };
+        let typed = balance
+            .typed_envelope()
+            .map_err(|e| ParseError::new(e.to_string()))?;
+        balance.original = Some(OriginalJson::capture(raw, &typed));
+        Ok(balance)
+    }
+}
 
 impl ToJson for Vec<Balance> {}
 
@@ -42,12 +94,34 @@ impl Balance {
     ///
     /// A new instance of `Balance` containing the metadata, data, and an empty signature.
     pub fn create(from: &str, data: &BalanceData, protocol: Option<String>) -> Result<Self> {
-        let now = Utc::now().to_rfc3339();
+        Self::create_with_context(from, data, protocol, &CreationContext::default())
+    }
+
+    /// Creates a new Balance resource, taking its id and timestamps from the given context.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The DID of the sender (the PFI).
+    /// * `data` - The data containing the currency code and available balance.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `context` - The clock and id source to create the resource with.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Balance`, as from [`Balance::create`] with the default context.
+    pub fn create_with_context(
+        from: &str,
+        data: &BalanceData,
+        protocol: Option<String>,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        let created_at = context.now();
+        let now = created_at.to_rfc3339();
 
         let metadata = ResourceMetadata {
             kind: ResourceKind::Balance,
             from: from.to_string(),
-            id: ResourceKind::Balance.typesafe_id()?,
+            id: context.typesafe_id(&ResourceKind::Balance.to_string(), created_at)?,
             protocol: protocol.unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string()),
             created_at: now.clone(),
             updated_at: Some(now),
@@ -57,11 +131,44 @@ impl Balance {
             metadata: metadata.clone(),
             data: data.clone(),
             signature: String::default(),
+            original: None,
         };
 
         Ok(balance)
     }
 
+    /// The typed view as JSON, without the fields preserved from parsing.
+    fn typed_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(RawEnvelope {
+            metadata: serde_json::to_value(&self.metadata)?,
+            data: serde_json::to_value(&self.data)?,
+            private_data: None,
+            signature: self.signature.clone(),
+        })
+    }
+
+    /// The typed view as JSON, with the fields preserved from parsing restored.
+    fn preserved_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(OriginalJson::restore(
+            self.original.as_ref(),
+            self.typed_envelope()?,
+        ))
+    }
+
+    /// The SHA-256 digest of the JCS canonicalized `metadata` and `data`, i.e. the payload
+    /// covered by the signature. See [`crate::digest`].
+    pub fn digest(&self) -> Result<ContentHash> {
+        let envelope = self.preserved_envelope()?;
+        crate::digest::content_hash(&envelope.metadata, &envelope.data)
+    }
+
+    /// The JCS canonicalized `metadata` and `data`, exactly as hashed by
+    /// [`digest`](Self::digest) and signed.
+    pub fn to_canonical_json(&self) -> Result<String> {
+        let envelope = self.preserved_envelope()?;
+        crate::digest::canonical_json(&envelope.metadata, &envelope.data)
+    }
+
     /// Signs the Balance resource using the provided Bearer DID.
     ///
     /// # Arguments
@@ -72,11 +179,8 @@ impl Balance {
     ///
     /// An empty result, or an error if the signing process fails.
     pub fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
-        self.signature = crate::signature::sign(
-            bearer_did,
-            &serde_json::to_value(&self.metadata)?,
-            &serde_json::to_value(&self.data)?,
-        )?;
+        let envelope = self.preserved_envelope()?;
+        self.signature = crate::signature::sign(bearer_did, &envelope.metadata, &envelope.data)?;
         Ok(())
     }
 
@@ -89,21 +193,34 @@ impl Balance {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub async fn verify(&self) -> Result<()> {
-        // verify resource json schema
-        crate::json_schemas::validate_from_str(RESOURCE_JSON_SCHEMA, self)?;
+        metrics::observe("verify_balance", &self.metadata.from, async {
+            let protocol = ProtocolVersion::from_str(&self.metadata.protocol)?;
 
-        // verify data json schema
-        crate::json_schemas::validate_from_str(BALANCE_DATA_JSON_SCHEMA, &self.data)?;
+            // verify id prefixes match the kinds they claim
+            self.metadata.verify_ids()?;
 
-        // verify signature
-        crate::signature::verify(
-            &serde_json::to_value(self.metadata.clone())?,
-            &serde_json::to_value(self.data.clone())?,
-            &self.signature,
-        )
-        .await?;
+            // verify resource json schema
+            let typed = self.typed_envelope()?;
+            crate::json_schemas::validate_from_str(
+                protocol.resource_schema(),
+                &typed,
+                &typed.metadata,
+            )?;
 
-        Ok(())
+            // verify data json schema
+            crate::json_schemas::validate_from_str(
+                protocol.resource_data_schema(&ResourceKind::Balance)?,
+                &self.data,
+                &typed.metadata,
+            )?;
+
+            // verify signature against the data as parsed, including unmodelled fields
+            let envelope = self.preserved_envelope()?;
+            crate::signature::verify(&envelope.metadata, &envelope.data, &self.signature).await?;
+
+            Ok(())
+        })
+        .await
     }
 }
 
@@ -1,13 +1,16 @@
 pub mod balance;
 pub mod offering;
+#[cfg(feature = "server")]
+pub mod offering_lint;
+pub mod offering_revision;
+pub mod payment_form;
 
-use std::{fmt, str::FromStr};
-use std::sync::atomic::{AtomicU64, Ordering};
-use chrono::Utc;
-use crate::errors::{Result, TbdexError};
+use crate::errors::{ParseError, Result, TbdexError};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 use type_safe_id::{DynamicType, TypeSafeId};
-use uuid::{NoContext, Timestamp, Uuid};
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize, PartialEq, Serialize, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -23,7 +26,10 @@ impl FromStr for ResourceKind {
         match s {
             "offering" => Ok(ResourceKind::Offering),
             "balance" => Ok(ResourceKind::Balance),
-            _ => Err(TbdexError::Parse(format!("invalid resource kind {}", s))),
+            _ => Err(TbdexError::Parse(ParseError::new(format!(
+                "invalid resource kind {}",
+                s
+            )))),
         }
     }
 }
@@ -37,23 +43,66 @@ impl fmt::Display for ResourceKind {
     }
 }
 
-static COUNTER: AtomicU64 = AtomicU64::new(0);
-
 impl ResourceKind {
+    /// Generates a new id for a resource of this kind, e.g. `offering_01hw...`.
+    ///
+    /// Ids are UUIDv7 based, so they embed their creation time and sort in creation order.
     pub fn typesafe_id(&self) -> Result<String> {
-        let dynamic_type = DynamicType::new(&self.to_string())?;
-        let timestamp_nanos = Utc::now()
-            .timestamp_nanos_opt()
-            .ok_or_else(|| TbdexError::Generic("Failed to get timestamp nanos".to_string()))?;
+        crate::ids::new_typesafe_id(&self.to_string())
+    }
+}
+
+/// A parsed resource id, such as `offering_01hw...`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceId {
+    kind: ResourceKind,
+    uuid: Uuid,
+}
+
+impl ResourceId {
+    /// Generates a new id for a resource of the given kind.
+    pub fn new(kind: ResourceKind) -> Self {
+        Self {
+            kind,
+            uuid: crate::ids::new_uuid_v7(),
+        }
+    }
+
+    /// The resource kind named by the id's prefix.
+    pub fn kind(&self) -> &ResourceKind {
+        &self.kind
+    }
+
+    pub fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    /// The time the id was generated, to millisecond precision.
+    pub fn timestamp(&self) -> Result<DateTime<Utc>> {
+        crate::ids::uuid_v7_timestamp(&self.uuid)
+    }
+}
+
+impl FromStr for ResourceId {
+    type Err = TbdexError;
 
-        let seconds = (timestamp_nanos / 1_000_000_000) as u64;
-        let subsec_nanos = (timestamp_nanos % 1_000_000_000) as u32;
+    fn from_str(s: &str) -> Result<Self> {
+        let (prefix, uuid) = crate::ids::parse_typesafe_id(s)?;
+        let kind = ResourceKind::from_str(&prefix)
+            .map_err(|_| TbdexError::TypeId(format!("{} is not a resource id", s)))?;
 
-        let count = COUNTER.fetch_add(1, Ordering::SeqCst);
-        let unique_seconds = seconds.wrapping_add(count);
+        Ok(Self { kind, uuid })
+    }
+}
 
-        let timestamp = Timestamp::from_unix(NoContext, unique_seconds, subsec_nanos);
-        Ok(TypeSafeId::from_type_and_uuid(dynamic_type, Uuid::new_v7(timestamp)).to_string())
+impl fmt::Display for ResourceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dynamic_type = DynamicType::new(&self.kind.to_string()).map_err(|_| fmt::Error)?;
+        write!(
+            f,
+            "{}",
+            TypeSafeId::from_type_and_uuid(dynamic_type, self.uuid)
+        )
     }
 }
 
@@ -68,3 +117,22 @@ pub struct ResourceMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<String>,
 }
+
+impl ResourceMetadata {
+    /// Parses `id` as a [`ResourceId`].
+    pub fn typed_id(&self) -> Result<ResourceId> {
+        ResourceId::from_str(&self.id)
+    }
+
+    /// Checks that `id` is prefixed with `kind`.
+    pub(crate) fn verify_ids(&self) -> Result<()> {
+        if self.typed_id()?.kind() != &self.kind {
+            return Err(TbdexError::TypeId(format!(
+                "id {} does not match resource kind {}",
+                self.id, self.kind
+            )));
+        }
+
+        Ok(())
+    }
+}
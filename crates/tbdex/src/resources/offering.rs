@@ -1,11 +1,18 @@
-use super::{ResourceKind, ResourceMetadata, Result};
+use super::{payment_form::PaymentForm, ResourceKind, ResourceMetadata, Result};
 use crate::{
-    json::{FromJson, ToJson},
-    json_schemas::generated::{OFFERING_DATA_JSON_SCHEMA, RESOURCE_JSON_SCHEMA},
+    context::CreationContext,
+    digest::ContentHash,
+    errors::ParseError,
+    json::{
+        deserialize_envelope, parse_envelope, parse_value, FromJson, OriginalJson, RawEnvelope,
+        ToJson, UnknownFields,
+    },
+    metrics,
+    protocol::ProtocolVersion,
     DEFAULT_PROTOCOL_VERSION,
 };
-use chrono::Utc;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
 use web5::{
     credentials::presentation_definition::PresentationDefinition, dids::bearer_did::BearerDid,
 };
@@ -14,7 +21,7 @@ use web5::{
 ///
 /// An Offering resource is created by a PFI to define the requirements for a given exchange,
 /// including payin/payout details, rates, and other conditions.
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Offering {
     /// Metadata about the resource, including sender, type of resource, and protocol information.
     pub metadata: ResourceMetadata,
@@ -24,10 +31,55 @@ pub struct Offering {
 
     /// The signature verifying the authenticity and integrity of the Offering resource.
     pub signature: String,
+
+    /// The JSON as parsed, which preserves fields not modelled above.
+    pub(crate) original: Option<OriginalJson>,
 }
 
 impl ToJson for Offering {}
-impl FromJson for Offering {}
+impl FromJson for Offering {
+    fn from_json_value(value: &serde_json::Value) -> std::result::Result<Self, ParseError> {
+        parse_envelope(value)
+    }
+}
+
+impl UnknownFields for Offering {
+    fn unknown_fields(&self) -> &[String] {
+        OriginalJson::unknown_fields(self.original.as_ref())
+    }
+}
+
+impl Serialize for Offering {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.preserved_envelope()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Offering {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserialize_envelope(deserializer)
+    }
+}
+
+impl TryFrom<RawEnvelope> for Offering {
+    type Error = ParseError;
+
+    fn try_from(raw: RawEnvelope) -> std::result::Result<Self, ParseError> {
+        let mut offering = Self {
+            metadata: parse_value(&raw.metadata).map_err(|e| e.within("metadata"))?,
+            data: parse_value(&raw.data).map_err(|e| e.within("data"))?,
+            signature: raw.signature.clone(),
+            original: None,
+        };
+        let typed = offering
+            .typed_envelope()
+            .map_err(|e| ParseError::new(e.to_string()))?;
+        offering.original = Some(OriginalJson::capture(raw, &typed));
+        Ok(offering)
+    }
+}
 
 impl ToJson for Vec<Offering> {}
 
@@ -44,12 +96,34 @@ impl Offering {
     ///
     /// A new instance of `Offering` containing the metadata, data, and an empty signature.
     pub fn create(from: &str, data: &OfferingData, protocol: Option<String>) -> Result<Self> {
-        let now = Utc::now().to_rfc3339();
+        Self::create_with_context(from, data, protocol, &CreationContext::default())
+    }
+
+    /// Creates a new Offering resource, taking its id and timestamps from the given context.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The DID of the sender (the PFI).
+    /// * `data` - The data containing the offering details.
+    /// * `protocol` - Optional protocol version; defaults to the current version if not provided.
+    /// * `context` - The clock and id source to create the resource with.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `Offering`, as from [`Offering::create`] with the default context.
+    pub fn create_with_context(
+        from: &str,
+        data: &OfferingData,
+        protocol: Option<String>,
+        context: &CreationContext,
+    ) -> Result<Self> {
+        let created_at = context.now();
+        let now = created_at.to_rfc3339();
 
         let metadata = ResourceMetadata {
             kind: ResourceKind::Offering,
             from: from.to_string(),
-            id: ResourceKind::Offering.typesafe_id()?,
+            id: context.typesafe_id(&ResourceKind::Offering.to_string(), created_at)?,
             protocol: protocol.unwrap_or_else(|| DEFAULT_PROTOCOL_VERSION.to_string()),
             created_at: now.clone(),
             updated_at: Some(now),
@@ -59,11 +133,44 @@ impl Offering {
             metadata: metadata.clone(),
             data: data.clone(),
             signature: String::default(),
+            original: None,
         };
 
         Ok(offering)
     }
 
+    /// The typed view as JSON, without the fields preserved from parsing.
+    fn typed_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(RawEnvelope {
+            metadata: serde_json::to_value(&self.metadata)?,
+            data: serde_json::to_value(&self.data)?,
+            private_data: None,
+            signature: self.signature.clone(),
+        })
+    }
+
+    /// The typed view as JSON, with the fields preserved from parsing restored.
+    fn preserved_envelope(&self) -> serde_json::Result<RawEnvelope> {
+        Ok(OriginalJson::restore(
+            self.original.as_ref(),
+            self.typed_envelope()?,
+        ))
+    }
+
+    /// The SHA-256 digest of the JCS canonicalized `metadata` and `data`, i.e. the payload
+    /// covered by the signature. See [`crate::digest`].
+    pub fn digest(&self) -> Result<ContentHash> {
+        let envelope = self.preserved_envelope()?;
+        crate::digest::content_hash(&envelope.metadata, &envelope.data)
+    }
+
+    /// The JCS canonicalized `metadata` and `data`, exactly as hashed by
+    /// [`digest`](Self::digest) and signed.
+    pub fn to_canonical_json(&self) -> Result<String> {
+        let envelope = self.preserved_envelope()?;
+        crate::digest::canonical_json(&envelope.metadata, &envelope.data)
+    }
+
     /// Signs the Offering resource using the provided Bearer DID.
     ///
     /// # Arguments
@@ -74,11 +181,8 @@ impl Offering {
     ///
     /// An empty result, or an error if the signing process fails.
     pub fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
-        self.signature = crate::signature::sign(
-            bearer_did,
-            &serde_json::to_value(&self.metadata)?,
-            &serde_json::to_value(&self.data)?,
-        )?;
+        let envelope = self.preserved_envelope()?;
+        self.signature = crate::signature::sign(bearer_did, &envelope.metadata, &envelope.data)?;
         Ok(())
     }
 
@@ -91,21 +195,34 @@ impl Offering {
     ///
     /// An empty result if verification succeeds, or an error if verification fails.
     pub async fn verify(&self) -> Result<()> {
-        // verify resource json schema
-        crate::json_schemas::validate_from_str(RESOURCE_JSON_SCHEMA, self)?;
-
-        // verify data json schema
-        crate::json_schemas::validate_from_str(OFFERING_DATA_JSON_SCHEMA, &self.data)?;
-
-        // verify signature
-        crate::signature::verify(
-            &serde_json::to_value(self.metadata.clone())?,
-            &serde_json::to_value(self.data.clone())?,
-            &self.signature,
-        )
-        .await?;
-
-        Ok(())
+        metrics::observe("verify_offering", &self.metadata.from, async {
+            let protocol = ProtocolVersion::from_str(&self.metadata.protocol)?;
+
+            // verify id prefixes match the kinds they claim
+            self.metadata.verify_ids()?;
+
+            // verify resource json schema
+            let typed = self.typed_envelope()?;
+            crate::json_schemas::validate_from_str(
+                protocol.resource_schema(),
+                &typed,
+                &typed.metadata,
+            )?;
+
+            // verify data json schema
+            crate::json_schemas::validate_from_str(
+                protocol.resource_data_schema(&ResourceKind::Offering)?,
+                &self.data,
+                &typed.metadata,
+            )?;
+
+            // verify signature against the data as parsed, including unmodelled fields
+            let envelope = self.preserved_envelope()?;
+            crate::signature::verify(&envelope.metadata, &envelope.data, &self.signature).await?;
+
+            Ok(())
+        })
+        .await
     }
 }
 
@@ -196,6 +313,16 @@ pub struct PayinMethod {
     pub max: Option<String>,
 }
 
+impl PayinMethod {
+    /// The form for the method's `required_payment_details`, or `None` if it requires none.
+    pub fn payment_form(&self) -> Result<Option<PaymentForm>> {
+        self.required_payment_details
+            .as_ref()
+            .map(PaymentForm::from_schema)
+            .transpose()
+    }
+}
+
 /// Represents the details of the payout for an Offering.
 ///
 /// This includes the currency code, optional limits, and available methods for receiving the payout.
@@ -258,6 +385,16 @@ pub struct PayoutMethod {
     pub estimated_settlement_time: i64,
 }
 
+impl PayoutMethod {
+    /// The form for the method's `required_payment_details`, or `None` if it requires none.
+    pub fn payment_form(&self) -> Result<Option<PaymentForm>> {
+        self.required_payment_details
+            .as_ref()
+            .map(PaymentForm::from_schema)
+            .transpose()
+    }
+}
+
 /// Represents the cancellation policy for an Offering.
 ///
 /// This includes whether cancellation is enabled and optional terms describing the cancellation policy.
@@ -325,13 +462,27 @@ mod tests {
         let parsed_offering = Offering::from_json_string(&offering_json_string).unwrap();
 
         assert_eq!(offering, parsed_offering);
+        assert_eq!(
+            offering.digest().unwrap(),
+            parsed_offering.digest().unwrap()
+        );
+
+        let mut changed = parsed_offering.clone();
+        changed.data.payout_units_per_payin_unit = "1.6".to_string();
+        assert_ne!(offering.digest().unwrap(), changed.digest().unwrap());
+        assert_eq!(
+            changed.digest().unwrap(),
+            ContentHash::of(&changed.to_canonical_json().unwrap())
+        );
     }
 
     #[test]
     fn test_typesafe_id_sorting() {
         let resource_kind = ResourceKind::Offering;
 
-        let ids: Vec<String> = (0..1000).map(|_| resource_kind.typesafe_id().unwrap()).collect();
+        let ids: Vec<String> = (0..1000)
+            .map(|_| resource_kind.typesafe_id().unwrap())
+            .collect();
 
         let mut sorted_ids = ids.clone();
         sorted_ids.sort();
@@ -0,0 +1,440 @@
+use super::offering::{Offering, OfferingData};
+use crate::{
+    decimal,
+    errors::{Result, TbdexError},
+};
+use std::{collections::HashSet, fmt};
+use web5::credentials::presentation_definition::PresentationDefinition;
+
+/// How serious a lint finding is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LintSeverity {
+    /// The offering is broken; wallets will be unable to transact against it.
+    Error,
+
+    /// The offering is usable but likely not what the PFI intended.
+    Warning,
+}
+
+/// A single problem found while linting an Offering.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintIssue {
+    /// Whether the issue should block publishing.
+    pub severity: LintSeverity,
+
+    /// The JSON path within the offering, e.g. `data.payin.methods[1].kind`.
+    pub path: String,
+
+    /// A human-readable description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            LintSeverity::Error => "error",
+            LintSeverity::Warning => "warning",
+        };
+        write!(f, "{} at {}: {}", severity, self.path, self.message)
+    }
+}
+
+/// The result of linting an Offering.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LintReport {
+    /// All findings, in the order they were found.
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    /// The findings which should block publishing.
+    pub fn errors(&self) -> Vec<&LintIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == LintSeverity::Error)
+            .collect()
+    }
+
+    /// The findings which are suspicious but not fatal.
+    pub fn warnings(&self) -> Vec<&LintIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == LintSeverity::Warning)
+            .collect()
+    }
+
+    /// Whether the report contains no errors.
+    pub fn is_ok(&self) -> bool {
+        self.errors().is_empty()
+    }
+
+    /// Converts the report into an error if it contains any errors.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_ok() {
+            return Ok(());
+        }
+
+        Err(TbdexError::OfferingLint(
+            self.errors()
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+        ))
+    }
+
+    fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(LintIssue {
+            severity: LintSeverity::Error,
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn warning(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(LintIssue {
+            severity: LintSeverity::Warning,
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+}
+
+impl Offering {
+    /// Checks the Offering for semantic problems which the JSON schema cannot express.
+    ///
+    /// This complements [`Offering::verify`]; it does not check the signature, and it never
+    /// fails, instead returning every finding as a structured error or warning.
+    ///
+    /// # Returns
+    ///
+    /// A `LintReport` listing every problem found.
+    pub fn lint(&self) -> LintReport {
+        let mut report = LintReport::default();
+        lint_data(&self.data, &mut report);
+        report
+    }
+}
+
+/// Lints a JSON serialized Offering.
+///
+/// Unlike [`Offering::lint`] this also reports a `requiredClaims` which does not parse as a
+/// presentation definition, rather than failing to deserialize the whole offering.
+pub fn lint_offering_json(json: &str) -> LintReport {
+    let mut report = LintReport::default();
+
+    let mut value = match serde_json::from_str::<serde_json::Value>(json) {
+        Ok(value) => value,
+        Err(e) => {
+            report.error("", format!("invalid json {}", e));
+            return report;
+        }
+    };
+
+    // lint required claims independently so that a bad presentation definition
+    // does not mask all other findings
+    if let Some(required_claims) = value
+        .get_mut("data")
+        .and_then(|d| d.as_object_mut())
+        .and_then(|d| d.remove("requiredClaims"))
+    {
+        if let Err(e) = serde_json::from_value::<PresentationDefinition>(required_claims) {
+            report.error(
+                "data.requiredClaims",
+                format!("invalid presentation definition {}", e),
+            );
+        }
+    }
+
+    match crate::json::parse_value::<Offering>(&value) {
+        Ok(offering) => lint_data(&offering.data, &mut report),
+        Err(e) => report.error(
+            e.path.unwrap_or_default(),
+            format!("invalid offering {}", e.reason),
+        ),
+    }
+
+    report
+}
+
+fn lint_data(data: &OfferingData, report: &mut LintReport) {
+    match decimal::to_f64(&data.payout_units_per_payin_unit) {
+        None => report.error(
+            "data.payoutUnitsPerPayinUnit",
+            format!(
+                "invalid decimal string {}",
+                data.payout_units_per_payin_unit
+            ),
+        ),
+        Some(rate) if rate <= 0.0 => report.error(
+            "data.payoutUnitsPerPayinUnit",
+            format!("rate must be positive but is {}", rate),
+        ),
+        _ => {}
+    }
+
+    let payin_limits = lint_limits(
+        "data.payin",
+        data.payin.min.as_deref(),
+        data.payin.max.as_deref(),
+        report,
+    );
+    let payout_limits = lint_limits(
+        "data.payout",
+        data.payout.min.as_deref(),
+        data.payout.max.as_deref(),
+        report,
+    );
+
+    if data.payin.methods.is_empty() {
+        report.error("data.payin.methods", "no payin methods");
+    }
+    if data.payout.methods.is_empty() {
+        report.error("data.payout.methods", "no payout methods");
+    }
+
+    let mut side = MethodSide::new(payin_limits, report);
+    for (i, method) in data.payin.methods.iter().enumerate() {
+        let path = format!("data.payin.methods[{}]", i);
+        lint_method(
+            &path,
+            &method.kind,
+            method.required_payment_details.as_ref(),
+            method.fee.as_deref(),
+            (method.min.as_deref(), method.max.as_deref()),
+            &mut side,
+        );
+    }
+
+    let mut side = MethodSide::new(payout_limits, report);
+    for (i, method) in data.payout.methods.iter().enumerate() {
+        let path = format!("data.payout.methods[{}]", i);
+        lint_method(
+            &path,
+            &method.kind,
+            method.required_payment_details.as_ref(),
+            method.fee.as_deref(),
+            (method.min.as_deref(), method.max.as_deref()),
+            &mut side,
+        );
+
+        if method.estimated_settlement_time < 0 {
+            side.report.error(
+                format!("{}.estimatedSettlementTime", path),
+                "estimated settlement time is negative",
+            );
+        }
+    }
+
+    if let Some(required_claims) = &data.required_claims {
+        if required_claims.input_descriptors.is_empty() {
+            report.warning(
+                "data.requiredClaims.inputDescriptors",
+                "presentation definition has no input descriptors",
+            );
+        }
+
+        let mut ids = HashSet::new();
+        for (i, input_descriptor) in required_claims.input_descriptors.iter().enumerate() {
+            if !ids.insert(input_descriptor.id.clone()) {
+                report.error(
+                    format!("data.requiredClaims.inputDescriptors[{}].id", i),
+                    format!("duplicate input descriptor id {}", input_descriptor.id),
+                );
+            }
+        }
+    }
+
+    let cancellation = &data.cancellation;
+    if cancellation.enabled && cancellation.terms.is_none() && cancellation.terms_url.is_none() {
+        report.error(
+            "data.cancellation",
+            "cancellation is enabled but neither terms nor termsUrl are provided",
+        );
+    }
+    if let Some(terms_url) = &cancellation.terms_url {
+        if url::Url::parse(terms_url).is_err() {
+            report.error(
+                "data.cancellation.termsUrl",
+                format!("invalid url {}", terms_url),
+            );
+        }
+    }
+    if !cancellation.enabled && (cancellation.terms.is_some() || cancellation.terms_url.is_some()) {
+        report.warning(
+            "data.cancellation",
+            "cancellation terms are provided but cancellation is disabled",
+        );
+    }
+}
+
+type Limits = (Option<f64>, Option<f64>);
+
+fn lint_limits(
+    path: &str,
+    min: Option<&str>,
+    max: Option<&str>,
+    report: &mut LintReport,
+) -> Limits {
+    let mut parse = |field: &str, value: Option<&str>| {
+        let value = value?;
+        let parsed = decimal::to_f64(value);
+        if parsed.is_none() {
+            report.error(
+                format!("{}.{}", path, field),
+                format!("invalid decimal string {}", value),
+            );
+        }
+        parsed
+    };
+
+    let min = parse("min", min);
+    let max = parse("max", max);
+
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            report.error(
+                path.to_string(),
+                format!("min {} is greater than max {}", min, max),
+            );
+        }
+    }
+
+    (min, max)
+}
+
+/// What the payment methods of one side of an offering are linted against.
+struct MethodSide<'a> {
+    /// The kinds of the methods linted so far.
+    kinds: HashSet<String>,
+    /// The overall limits of the side.
+    limits: Limits,
+    report: &'a mut LintReport,
+}
+
+impl<'a> MethodSide<'a> {
+    fn new(limits: Limits, report: &'a mut LintReport) -> Self {
+        Self {
+            kinds: HashSet::new(),
+            limits,
+            report,
+        }
+    }
+}
+
+fn lint_method(
+    path: &str,
+    kind: &str,
+    required_payment_details: Option<&serde_json::Value>,
+    fee: Option<&str>,
+    (min, max): (Option<&str>, Option<&str>),
+    side: &mut MethodSide,
+) {
+    let (outer_min, outer_max) = side.limits;
+    let report = &mut *side.report;
+
+    if kind.is_empty() {
+        report.error(format!("{}.kind", path), "method kind is empty");
+    } else if !side.kinds.insert(kind.to_string()) {
+        report.error(
+            format!("{}.kind", path),
+            format!("duplicate method kind {}", kind),
+        );
+    }
+
+    if let Some(fee) = fee {
+        if decimal::to_f64(fee).is_none() {
+            report.error(
+                format!("{}.fee", path),
+                format!("invalid decimal string {}", fee),
+            );
+        }
+    }
+
+    let (min, max) = lint_limits(path, min, max, report);
+    if let (Some(min), Some(outer_max)) = (min, outer_max) {
+        if min > outer_max {
+            report.warning(
+                format!("{}.min", path),
+                format!("method min {} exceeds the overall max {}", min, outer_max),
+            );
+        }
+    }
+    if let (Some(max), Some(outer_min)) = (max, outer_min) {
+        if max < outer_min {
+            report.warning(
+                format!("{}.max", path),
+                format!("method max {} is below the overall min {}", max, outer_min),
+            );
+        }
+    }
+
+    if let Some(schema) = required_payment_details {
+        if let Err(e) = crate::json_schemas::check_schema(schema) {
+            report.error(
+                format!("{}.requiredPaymentDetails", path),
+                format!("schema does not compile {}", e),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::ToJson;
+    use crate::test_util::{offering, payout_method};
+    use serde_json::json;
+
+    #[test]
+    fn valid_offering_has_no_issues() {
+        let report = offering().lint();
+        assert_eq!(Vec::<LintIssue>::new(), report.issues);
+        assert!(report.into_result().is_ok());
+    }
+
+    #[test]
+    fn reports_semantic_errors() {
+        let mut offering = offering();
+        offering.data.payout_units_per_payin_unit = "abc".to_string();
+        offering.data.payin.min = Some("2000".to_string());
+        offering.data.payout.methods = vec![payout_method("BTC"), payout_method("BTC")];
+        offering.data.payin.methods[0].required_payment_details = Some(json!({"type": 12}));
+        offering.data.cancellation.enabled = true;
+
+        let report = offering.lint();
+        let paths = report
+            .errors()
+            .iter()
+            .map(|i| i.path.clone())
+            .collect::<Vec<String>>();
+
+        assert!(paths.contains(&"data.payoutUnitsPerPayinUnit".to_string()));
+        assert!(paths.contains(&"data.payin".to_string()));
+        assert!(paths.contains(&"data.payout.methods[1].kind".to_string()));
+        assert!(paths.contains(&"data.payin.methods[0].requiredPaymentDetails".to_string()));
+        assert!(paths.contains(&"data.cancellation".to_string()));
+        assert!(report.into_result().is_err());
+    }
+
+    #[test]
+    fn reports_empty_methods() {
+        let mut offering = offering();
+        offering.data.payout.methods = vec![];
+
+        let report = offering.lint();
+        assert_eq!("data.payout.methods", report.errors()[0].path);
+    }
+
+    #[test]
+    fn reports_unparseable_required_claims() {
+        let mut value =
+            serde_json::from_str::<serde_json::Value>(&offering().to_json_string().unwrap())
+                .unwrap();
+        value["data"]["requiredClaims"] = json!({"id": 7});
+
+        let report = lint_offering_json(&value.to_string());
+        assert_eq!(1, report.issues.len());
+        assert_eq!("data.requiredClaims", report.issues[0].path);
+    }
+}
@@ -0,0 +1,380 @@
+use super::offering::Offering;
+#[cfg(feature = "server")]
+use super::offering::OfferingData;
+#[cfg(feature = "server")]
+use crate::context::CreationContext;
+use crate::errors::{ParseError, Result, TbdexError};
+#[cfg(feature = "server")]
+use chrono::Duration;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
+use web5::dids::bearer_did::BearerDid;
+
+/// Which side of the exchange a change applies to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PaymentDirection {
+    Payin,
+    Payout,
+}
+
+/// A single difference between two revisions of the same Offering.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OfferingChange {
+    /// The description changed.
+    Description { from: String, to: String },
+
+    /// The exchange rate changed.
+    Rate { from: String, to: String },
+
+    /// The currency of one side changed.
+    CurrencyCode {
+        direction: PaymentDirection,
+        from: String,
+        to: String,
+    },
+
+    /// The overall min and/or max amount of one side changed.
+    Limits {
+        direction: PaymentDirection,
+        from: (Option<String>, Option<String>),
+        to: (Option<String>, Option<String>),
+    },
+
+    /// A payment method was added.
+    MethodAdded {
+        direction: PaymentDirection,
+        kind: String,
+    },
+
+    /// A payment method was removed.
+    MethodRemoved {
+        direction: PaymentDirection,
+        kind: String,
+    },
+
+    /// A payment method's fee, limits, schema or descriptive fields changed.
+    MethodChanged {
+        direction: PaymentDirection,
+        kind: String,
+    },
+
+    /// The required claims presentation definition changed.
+    RequiredClaims,
+
+    /// The cancellation policy changed.
+    Cancellation,
+}
+
+impl Offering {
+    /// Revises the Offering in place with new data and re-signs it.
+    ///
+    /// The `id` and `created_at` are kept, while `updated_at` is advanced to the current time,
+    /// or to just after the previous `updated_at` if the clock has not moved past it.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The revised offering data.
+    /// * `bearer_did` - The DID of the PFI which published the offering.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an error if the signer is not the publisher or signing fails.
+    #[cfg(feature = "server")]
+    pub fn update(&mut self, data: &OfferingData, bearer_did: &BearerDid) -> Result<()> {
+        self.update_with_context(data, bearer_did, &CreationContext::default())
+    }
+
+    /// Revises the Offering in place as [`Offering::update`] does, taking the current time from
+    /// the given context.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The revised offering data.
+    /// * `bearer_did` - The DID of the PFI which published the offering.
+    /// * `context` - The clock to timestamp the revision with.
+    ///
+    /// # Returns
+    ///
+    /// An empty result, or an error if the signer is not the publisher or signing fails.
+    #[cfg(feature = "server")]
+    pub fn update_with_context(
+        &mut self,
+        data: &OfferingData,
+        bearer_did: &BearerDid,
+        context: &CreationContext,
+    ) -> Result<()> {
+        if bearer_did.did.uri != self.metadata.from {
+            return Err(TbdexError::Generic(format!(
+                "offering was published by {} and cannot be updated by {}",
+                self.metadata.from, bearer_did.did.uri
+            )));
+        }
+
+        let mut updated_at = context.now();
+        if let Some(previous) = self.last_modified()? {
+            if updated_at <= previous {
+                updated_at = previous + Duration::milliseconds(1);
+            }
+        }
+
+        self.metadata.updated_at = Some(updated_at.to_rfc3339());
+        self.data = data.clone();
+        self.sign(bearer_did)
+    }
+
+    /// The time of the most recent revision, falling back to `created_at` if `updated_at` is unset.
+    pub fn last_modified(&self) -> Result<Option<DateTime<Utc>>> {
+        let timestamp = self
+            .metadata
+            .updated_at
+            .as_ref()
+            .unwrap_or(&self.metadata.created_at);
+
+        if timestamp.is_empty() {
+            return Ok(None);
+        }
+
+        let parsed = DateTime::parse_from_rfc3339(timestamp).map_err(|e| {
+            TbdexError::Parse(ParseError::new(format!(
+                "invalid offering timestamp {}: {}",
+                timestamp, e
+            )))
+        })?;
+
+        Ok(Some(parsed.with_timezone(&Utc)))
+    }
+
+    /// Computes the changes from this revision to a newer revision of the same Offering.
+    ///
+    /// # Arguments
+    ///
+    /// * `newer` - A later revision of this offering.
+    ///
+    /// # Returns
+    ///
+    /// The list of changes, empty if the data is identical, or an error if the offerings have
+    /// different ids.
+    pub fn diff(&self, newer: &Offering) -> Result<Vec<OfferingChange>> {
+        if self.metadata.id != newer.metadata.id {
+            return Err(TbdexError::Generic(format!(
+                "cannot diff offering {} against different offering {}",
+                self.metadata.id, newer.metadata.id
+            )));
+        }
+
+        let (old, new) = (&self.data, &newer.data);
+        let mut changes = vec![];
+
+        if old.description != new.description {
+            changes.push(OfferingChange::Description {
+                from: old.description.clone(),
+                to: new.description.clone(),
+            });
+        }
+
+        if old.payout_units_per_payin_unit != new.payout_units_per_payin_unit {
+            changes.push(OfferingChange::Rate {
+                from: old.payout_units_per_payin_unit.clone(),
+                to: new.payout_units_per_payin_unit.clone(),
+            });
+        }
+
+        diff_side(
+            PaymentDirection::Payin,
+            (&old.payin.currency_code, &new.payin.currency_code),
+            (
+                (old.payin.min.clone(), old.payin.max.clone()),
+                (new.payin.min.clone(), new.payin.max.clone()),
+            ),
+            method_values(&old.payin.methods, |m| &m.kind)?,
+            method_values(&new.payin.methods, |m| &m.kind)?,
+            &mut changes,
+        );
+
+        diff_side(
+            PaymentDirection::Payout,
+            (&old.payout.currency_code, &new.payout.currency_code),
+            (
+                (old.payout.min.clone(), old.payout.max.clone()),
+                (new.payout.min.clone(), new.payout.max.clone()),
+            ),
+            method_values(&old.payout.methods, |m| &m.kind)?,
+            method_values(&new.payout.methods, |m| &m.kind)?,
+            &mut changes,
+        );
+
+        if old.required_claims != new.required_claims {
+            changes.push(OfferingChange::RequiredClaims);
+        }
+
+        if old.cancellation != new.cancellation {
+            changes.push(OfferingChange::Cancellation);
+        }
+
+        Ok(changes)
+    }
+}
+
+type Limits = (Option<String>, Option<String>);
+
+fn method_values<T: serde::Serialize>(
+    methods: &[T],
+    kind: impl Fn(&T) -> &String,
+) -> Result<Vec<(String, serde_json::Value)>> {
+    methods
+        .iter()
+        .map(|m| Ok((kind(m).clone(), serde_json::to_value(m)?)))
+        .collect()
+}
+
+fn diff_side(
+    direction: PaymentDirection,
+    (old_currency, new_currency): (&String, &String),
+    (old_limits, new_limits): (Limits, Limits),
+    old_methods: Vec<(String, serde_json::Value)>,
+    new_methods: Vec<(String, serde_json::Value)>,
+    changes: &mut Vec<OfferingChange>,
+) {
+    if old_currency != new_currency {
+        changes.push(OfferingChange::CurrencyCode {
+            direction: direction.clone(),
+            from: old_currency.clone(),
+            to: new_currency.clone(),
+        });
+    }
+
+    if old_limits != new_limits {
+        changes.push(OfferingChange::Limits {
+            direction: direction.clone(),
+            from: old_limits,
+            to: new_limits,
+        });
+    }
+
+    for (kind, old_method) in &old_methods {
+        match new_methods.iter().find(|(k, _)| k == kind) {
+            None => changes.push(OfferingChange::MethodRemoved {
+                direction: direction.clone(),
+                kind: kind.clone(),
+            }),
+            Some((_, new_method)) if new_method != old_method => {
+                changes.push(OfferingChange::MethodChanged {
+                    direction: direction.clone(),
+                    kind: kind.clone(),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    for (kind, _) in &new_methods {
+        if !old_methods.iter().any(|(k, _)| k == kind) {
+            changes.push(OfferingChange::MethodAdded {
+                direction: direction.clone(),
+                kind: kind.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::offering::{
+        CancellationDetails, OfferingData, PayinDetails, PayinMethod, PayoutDetails,
+    };
+    #[cfg(feature = "server")]
+    use web5::dids::methods::did_jwk::DidJwk;
+
+    fn offering_data() -> OfferingData {
+        OfferingData {
+            description: "Selling BTC for USD".to_string(),
+            payout_units_per_payin_unit: "0.00003".to_string(),
+            payin: PayinDetails {
+                currency_code: "USD".to_string(),
+                methods: vec![PayinMethod {
+                    kind: "DEBIT_CARD".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            payout: PayoutDetails {
+                currency_code: "BTC".to_string(),
+                ..Default::default()
+            },
+            required_claims: None,
+            cancellation: CancellationDetails::default(),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "server")]
+    fn can_update() {
+        let bearer_did = DidJwk::create(None).unwrap();
+
+        let mut offering = Offering::create(&bearer_did.did.uri, &offering_data(), None).unwrap();
+        offering.sign(&bearer_did).unwrap();
+        let original = offering.clone();
+
+        let mut data = offering_data();
+        data.payout_units_per_payin_unit = "0.00004".to_string();
+        offering.update(&data, &bearer_did).unwrap();
+
+        assert_eq!(original.metadata.id, offering.metadata.id);
+        assert_eq!(original.metadata.created_at, offering.metadata.created_at);
+        assert!(offering.last_modified().unwrap() > original.last_modified().unwrap());
+        assert_ne!(original.signature, offering.signature);
+    }
+
+    #[test]
+    #[cfg(feature = "server")]
+    fn cannot_update_as_other_did() {
+        let bearer_did = DidJwk::create(None).unwrap();
+        let other_did = DidJwk::create(None).unwrap();
+
+        let mut offering = Offering::create(&bearer_did.did.uri, &offering_data(), None).unwrap();
+
+        assert!(offering.update(&offering_data(), &other_did).is_err());
+    }
+
+    #[test]
+    fn can_diff() {
+        let offering = Offering::create("did:test:pfi", &offering_data(), None).unwrap();
+
+        let mut revised = offering.clone();
+        revised.data.payout_units_per_payin_unit = "0.00004".to_string();
+        revised.data.payin.max = Some("1000".to_string());
+        revised.data.payin.methods[0].fee = Some("1.00".to_string());
+        revised.data.payin.methods.push(PayinMethod {
+            kind: "SEPA".to_string(),
+            ..Default::default()
+        });
+
+        let changes = offering.diff(&revised).unwrap();
+
+        assert_eq!(
+            vec![
+                OfferingChange::Rate {
+                    from: "0.00003".to_string(),
+                    to: "0.00004".to_string()
+                },
+                OfferingChange::Limits {
+                    direction: PaymentDirection::Payin,
+                    from: (None, None),
+                    to: (None, Some("1000".to_string()))
+                },
+                OfferingChange::MethodChanged {
+                    direction: PaymentDirection::Payin,
+                    kind: "DEBIT_CARD".to_string()
+                },
+                OfferingChange::MethodAdded {
+                    direction: PaymentDirection::Payin,
+                    kind: "SEPA".to_string()
+                },
+            ],
+            changes
+        );
+
+        assert!(offering.diff(&offering).unwrap().is_empty());
+    }
+}
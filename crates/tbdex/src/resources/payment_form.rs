@@ -0,0 +1,510 @@
+//! Input forms compiled from a payment method's `requiredPaymentDetails` schema.
+//!
+//! A [`PaymentForm`] describes, independently of any UI toolkit, the fields a wallet must ask
+//! for: their titles, types, constraints and defaults, in a stable order. Form input is then
+//! turned into payment details with [`payment_details_from_form`], which conforms it to the same
+//! schema the PFI verifies against, or checked field by field with [`validate_form`].
+
+use crate::{
+    errors::{FieldError, Result, TbdexError},
+    messages::payment_details::PaymentDetails,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The deepest nesting of objects a form is compiled from.
+const MAX_DEPTH: usize = 32;
+
+/// The kind of value a form field takes, named after the JSON schema type it comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+/// A single input of a [`PaymentForm`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormField {
+    /// The property the field fills in, with those of nested objects named by their path,
+    /// e.g. `address.postalCode`.
+    pub name: String,
+
+    /// The schema's `title` for the property, or its name if it has none.
+    pub title: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    pub field_type: FieldType,
+
+    /// Whether the field must be filled in.
+    pub required: bool,
+
+    /// The values allowed by the schema's `enum`, as text, for rendering as a choice.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<String>>,
+
+    /// A regular expression the value must match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u64>,
+
+    /// The schema's `default`, as text, for pre-filling the field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<String>,
+}
+
+/// The form for a payment method's `requiredPaymentDetails`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentForm {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    pub fields: Vec<FormField>,
+}
+
+impl PaymentForm {
+    /// Compiles the form for a `requiredPaymentDetails` schema.
+    ///
+    /// Fields are ordered by the properties' `propertyOrder` where given, then in the order of
+    /// the schema's `required` list, then by name, so the same schema always gives the same form.
+    /// Properties of nested objects become fields of their own. References within the schema are
+    /// followed; properties of array type, referring to other schemas, referring back to an
+    /// enclosing object or nested more than 32 objects deep are not supported.
+    pub fn from_schema(schema: &Value) -> Result<Self> {
+        if !schema.is_object() {
+            return Err(TbdexError::JsonSchema(
+                "payment details schema must be an object".to_string(),
+            ));
+        }
+
+        let mut fields = vec![];
+        collect_fields(schema, schema, "", true, 0, &mut vec![], &mut fields)?;
+
+        Ok(Self {
+            title: string(schema, "title"),
+            description: string(schema, "description"),
+            fields,
+        })
+    }
+}
+
+/// Turns form input, keyed by [`FormField::name`], into payment details conformed to the schema;
+/// see [`PaymentDetails::conform_to`].
+///
+/// Fields left blank are treated as not filled in.
+pub fn payment_details_from_form<K, V>(
+    schema: &Value,
+    values: impl IntoIterator<Item = (K, V)>,
+) -> Result<PaymentDetails>
+where
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let mut fields = Map::new();
+    for (name, value) in values {
+        let value = value.into();
+        if value.trim().is_empty() {
+            continue;
+        }
+
+        let mut path = name.as_ref().split('.').peekable();
+        let mut object = &mut fields;
+        while let Some(segment) = path.next() {
+            if path.peek().is_none() {
+                object.insert(segment.to_string(), Value::String(value));
+                break;
+            }
+
+            let nested = object
+                .entry(segment)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !nested.is_object() {
+                *nested = Value::Object(Map::new());
+            }
+            object = nested
+                .as_object_mut()
+                .expect("replaced with an object above");
+        }
+    }
+
+    PaymentDetails::from_value(Value::Object(fields))?.conform_to(Some(schema))
+}
+
+/// Checks form input as [`payment_details_from_form`] does, returning every field which does not
+/// satisfy the schema. An empty result means the form may be submitted.
+pub fn validate_form<K, V>(
+    schema: &Value,
+    values: impl IntoIterator<Item = (K, V)>,
+) -> Result<Vec<FieldError>>
+where
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    match payment_details_from_form(schema, values) {
+        Ok(_) => Ok(vec![]),
+        Err(TbdexError::InvalidPaymentDetails(field_errors)) => Ok(field_errors),
+        Err(e) => Err(e),
+    }
+}
+
+/// Collects the fields of an object schema, nested `depth` objects deep, where `refs` are the
+/// references followed to reach it.
+fn collect_fields<'a>(
+    root: &'a Value,
+    schema: &'a Value,
+    prefix: &str,
+    required: bool,
+    depth: usize,
+    refs: &mut Vec<&'a str>,
+    fields: &mut Vec<FormField>,
+) -> Result<()> {
+    if depth > MAX_DEPTH {
+        return Err(TbdexError::JsonSchema(format!(
+            "payment details schema nests objects more than {} deep at {}",
+            MAX_DEPTH, prefix
+        )));
+    }
+
+    let properties = match schema.get("properties").and_then(Value::as_object) {
+        None => return Ok(()),
+        Some(properties) => properties,
+    };
+    let required_names = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let mut names = properties.keys().collect::<Vec<_>>();
+    names.sort_by_key(|name| {
+        let order = properties[name.as_str()]
+            .get("propertyOrder")
+            .and_then(Value::as_i64);
+        let position = required_names.iter().position(|r| *r == name.as_str());
+        (order.unwrap_or(i64::MAX), position.unwrap_or(usize::MAX))
+    });
+
+    for name in names {
+        let followed = refs.len();
+        let property = resolve(root, &properties[name.as_str()], refs)?;
+        let path = match prefix {
+            "" => name.to_string(),
+            prefix => format!("{}.{}", prefix, name),
+        };
+        let required = required && required_names.contains(&name.as_str());
+
+        match field_type(property) {
+            Some(Ok(field_type)) => fields.push(FormField {
+                title: string(property, "title").unwrap_or_else(|| name.to_string()),
+                description: string(property, "description"),
+                field_type,
+                required,
+                options: property
+                    .get("enum")
+                    .and_then(Value::as_array)
+                    .map(|options| {
+                        options
+                            .iter()
+                            .filter(|option| !option.is_null())
+                            .map(text)
+                            .collect()
+                    }),
+                pattern: string(property, "pattern"),
+                min_length: property.get("minLength").and_then(Value::as_u64),
+                max_length: property.get("maxLength").and_then(Value::as_u64),
+                default_value: property.get("default").map(text),
+                name: path,
+            }),
+            None => collect_fields(root, property, &path, required, depth + 1, refs, fields)?,
+            Some(Err(unsupported)) => {
+                return Err(TbdexError::JsonSchema(format!(
+                    "unsupported payment details field type {} at {}",
+                    unsupported, path
+                )))
+            }
+        }
+
+        refs.truncate(followed);
+    }
+
+    Ok(())
+}
+
+/// The field type of a property, `None` for a nested object, or the unsupported type.
+fn field_type(property: &Value) -> Option<std::result::Result<FieldType, String>> {
+    let json_type = match property.get("type") {
+        Some(Value::String(t)) => t.as_str(),
+        // e.g. ["string", "null"] for an optional field
+        Some(Value::Array(ts)) => ts
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .unwrap_or("string"),
+        _ if property.get("properties").is_some() => "object",
+        _ => "string",
+    };
+
+    match json_type {
+        "string" => Some(Ok(FieldType::String)),
+        "number" => Some(Ok(FieldType::Number)),
+        "integer" => Some(Ok(FieldType::Integer)),
+        "boolean" => Some(Ok(FieldType::Boolean)),
+        "object" => None,
+        unsupported => Some(Err(unsupported.to_string())),
+    }
+}
+
+/// Follows `$ref`s to definitions within the same schema, adding them to `refs`. Following one
+/// already in `refs` would never reach a field, so it is rejected as circular.
+fn resolve<'a>(
+    root: &'a Value,
+    mut property: &'a Value,
+    refs: &mut Vec<&'a str>,
+) -> Result<&'a Value> {
+    while let Some(reference) = property.get("$ref").and_then(Value::as_str) {
+        if refs.contains(&reference) {
+            return Err(TbdexError::JsonSchema(format!(
+                "circular payment details schema reference {}",
+                reference
+            )));
+        }
+        refs.push(reference);
+
+        property = crate::json_schemas::resolve_local_ref(root, property).ok_or_else(|| {
+            TbdexError::JsonSchema(format!(
+                "unsupported payment details schema reference {}",
+                reference
+            ))
+        })?;
+    }
+
+    Ok(property)
+}
+
+fn string(schema: &Value, keyword: &str) -> Option<String> {
+    schema
+        .get(keyword)
+        .and_then(Value::as_str)
+        .map(String::from)
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": "Bank account",
+            "type": "object",
+            "properties": {
+                "accountNumber": {
+                    "title": "Account number",
+                    "type": "string",
+                    "pattern": "^[0-9]+$",
+                    "minLength": 8,
+                    "maxLength": 17
+                },
+                "accountType": { "$ref": "#/definitions/accountType" },
+                "address": {
+                    "type": "object",
+                    "properties": {
+                        "postalCode": { "type": "string" },
+                        "country": { "type": "string", "default": "US" }
+                    },
+                    "required": ["postalCode"]
+                },
+                "routingNumber": { "type": "integer", "description": "ABA routing number" }
+            },
+            "required": ["routingNumber", "accountNumber", "accountType"],
+            "additionalProperties": false,
+            "definitions": {
+                "accountType": { "type": "string", "enum": ["CHECKING", "SAVINGS"] }
+            }
+        })
+    }
+
+    #[test]
+    fn compiles_fields_in_order() {
+        let form = PaymentForm::from_schema(&schema()).unwrap();
+
+        assert_eq!(Some("Bank account".to_string()), form.title);
+        let names = form
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "routingNumber",
+                "accountNumber",
+                "accountType",
+                "address.postalCode",
+                "address.country"
+            ],
+            names
+        );
+
+        assert_eq!(
+            FormField {
+                name: "accountNumber".to_string(),
+                title: "Account number".to_string(),
+                description: None,
+                field_type: FieldType::String,
+                required: true,
+                options: None,
+                pattern: Some("^[0-9]+$".to_string()),
+                min_length: Some(8),
+                max_length: Some(17),
+                default_value: None,
+            },
+            form.fields[1]
+        );
+        assert_eq!(FieldType::Integer, form.fields[0].field_type);
+        assert_eq!(
+            Some(vec!["CHECKING".to_string(), "SAVINGS".to_string()]),
+            form.fields[2].options
+        );
+        // the address itself is optional, so none of its fields are required
+        assert!(!form.fields[3].required);
+        assert_eq!(Some("US".to_string()), form.fields[4].default_value);
+    }
+
+    #[test]
+    fn serializes_for_the_bindings() {
+        let form = PaymentForm::from_schema(&schema()).unwrap();
+        let json = serde_json::to_value(&form).unwrap();
+
+        assert_eq!(json!("integer"), json["fields"][0]["fieldType"]);
+        assert_eq!(json!(8), json["fields"][1]["minLength"]);
+        assert_eq!(form, serde_json::from_value(json).unwrap());
+    }
+
+    #[test]
+    fn fills_in_payment_details() {
+        let details = payment_details_from_form(
+            &schema(),
+            [
+                ("routingNumber", "121000358"),
+                ("accountNumber", "0123456789"),
+                ("accountType", "savings"),
+                ("address.postalCode", "94107"),
+                ("address.country", " "),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            json!({
+                "routingNumber": 121000358,
+                "accountNumber": "0123456789",
+                "accountType": "SAVINGS",
+                "address": { "postalCode": "94107" }
+            }),
+            details.to_value()
+        );
+    }
+
+    #[test]
+    fn reports_invalid_fields() {
+        let field_errors = validate_form(
+            &schema(),
+            [("routingNumber", "ABA"), ("accountNumber", "12")],
+        )
+        .unwrap();
+
+        let mut fields = field_errors
+            .iter()
+            .map(|e| e.field.as_str())
+            .collect::<Vec<_>>();
+        fields.sort();
+        fields.dedup();
+        assert_eq!(
+            vec!["accountNumber", "accountType", "routingNumber"],
+            fields
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_schemas() {
+        let arrays = json!({
+            "type": "object",
+            "properties": { "names": { "type": "array" } }
+        });
+        assert!(PaymentForm::from_schema(&arrays).is_err());
+
+        let remote = json!({
+            "type": "object",
+            "properties": { "iban": { "$ref": "https://example.com/iban.json" } }
+        });
+        assert!(PaymentForm::from_schema(&remote).is_err());
+    }
+
+    #[test]
+    fn rejects_circular_schemas() {
+        let circular = json!({ "properties": { "child": { "$ref": "#" } } });
+        assert!(matches!(
+            PaymentForm::from_schema(&circular),
+            Err(TbdexError::JsonSchema(_))
+        ));
+
+        let mutual = json!({
+            "properties": { "a": { "$ref": "#/definitions/a" } },
+            "definitions": {
+                "a": { "$ref": "#/definitions/b" },
+                "b": { "$ref": "#/definitions/a" }
+            }
+        });
+        assert!(matches!(
+            PaymentForm::from_schema(&mutual),
+            Err(TbdexError::JsonSchema(_))
+        ));
+
+        // the same definition used by sibling properties is not circular
+        let shared = json!({
+            "properties": {
+                "from": { "$ref": "#/definitions/address" },
+                "to": { "$ref": "#/definitions/address" }
+            },
+            "definitions": {
+                "address": { "type": "object", "properties": { "city": { "type": "string" } } }
+            }
+        });
+        assert_eq!(2, PaymentForm::from_schema(&shared).unwrap().fields.len());
+    }
+
+    #[test]
+    fn rejects_deeply_nested_schemas() {
+        let mut nested = json!({ "type": "string" });
+        for _ in 0..=MAX_DEPTH + 1 {
+            nested = json!({ "type": "object", "properties": { "child": nested } });
+        }
+
+        assert!(matches!(
+            PaymentForm::from_schema(&nested),
+            Err(TbdexError::JsonSchema(_))
+        ));
+    }
+}
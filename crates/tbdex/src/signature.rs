@@ -1,37 +1,71 @@
-use crate::errors::{Result, TbdexError};
+use crate::{
+    digest::{content_hash, ContentHash},
+    errors::{Result, TbdexError},
+};
 use base64::{engine::general_purpose, Engine};
-use serde_json::{Map, Value};
-use sha2::{Digest, Sha256};
+use serde_json::Value;
 use web5::{dids::bearer_did::BearerDid, jose::Jws};
 
-fn compute_digest(value: &Value) -> Result<Vec<u8>> {
-    let canonical_string = serde_jcs::to_string(value)?;
-    let mut hasher = Sha256::new();
-    hasher.update(canonical_string.as_bytes());
-    Ok(hasher.finalize().to_vec())
-}
-
 pub fn sign(bearer_did: &BearerDid, metadata: &Value, data: &Value) -> Result<String> {
-    let mut combined = Map::new();
-    combined.insert("metadata".to_string(), metadata.clone());
-    combined.insert("data".to_string(), data.clone());
-
-    let digest = compute_digest(&Value::Object(combined))?;
+    sign_digest(bearer_did, &content_hash(metadata, data)?)
+}
 
+/// Signs the digest, returning a detached compact JWS.
+pub(crate) fn sign_digest(bearer_did: &BearerDid, digest: &ContentHash) -> Result<String> {
     // TODO verification method defaults to first
-    let jws = Jws::from_payload(&digest, bearer_did, None)?;
+    let jws = Jws::from_payload(digest.as_bytes(), bearer_did, None)?;
 
     Ok(jws.detached_compact_jws)
 }
 
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(
+        kind = metadata["kind"].as_str(),
+        exchange_id = metadata["exchangeId"].as_str(),
+        pfi_did = crate::messages::pfi_did_of(metadata)
+    )
+)]
 pub async fn verify(metadata: &Value, data: &Value, detached_compact_jws: &str) -> Result<()> {
+    let digest = content_hash(metadata, data)?;
+
+    if let Err(e) = verify_digest(&digest, detached_compact_jws).await {
+        tracing::warn!(error = %e, "signature verification failed");
+        return Err(e);
+    }
+
+    tracing::debug!("signature verified");
+    Ok(())
+}
+
+/// Verifies a detached compact JWS over the digest.
+pub(crate) async fn verify_digest(digest: &ContentHash, detached_compact_jws: &str) -> Result<()> {
+    let parts = jws_parts(detached_compact_jws)?;
+
     // re-attach the payload
-    let mut combined = Map::new();
-    combined.insert("metadata".to_string(), metadata.clone());
-    combined.insert("data".to_string(), data.clone());
-    let digest = compute_digest(&Value::Object(combined))?;
-    let payload = general_purpose::URL_SAFE_NO_PAD.encode(digest);
+    let compact_jws = format!("{}.{}.{}", parts[0], digest.to_base64url(), parts[2]);
+    Jws::from_compact_jws(&compact_jws, true).await?;
 
+    Ok(())
+}
+
+/// The DID whose key the JWS claims to be signed with, from the `kid` of its header.
+pub(crate) fn signer_did(detached_compact_jws: &str) -> Result<String> {
+    let parts = jws_parts(detached_compact_jws)?;
+
+    let header = general_purpose::URL_SAFE_NO_PAD
+        .decode(parts[0])
+        .map_err(|e| TbdexError::Jose(format!("jws header is not base64url {}", e)))?;
+    let header = serde_json::from_slice::<Value>(&header)?;
+    let kid = header["kid"]
+        .as_str()
+        .ok_or_else(|| TbdexError::Jose("jws header missing kid".to_string()))?;
+
+    Ok(kid.split('#').next().unwrap_or(kid).to_string())
+}
+
+fn jws_parts(detached_compact_jws: &str) -> Result<Vec<&str>> {
     let parts: Vec<&str> = detached_compact_jws.split('.').collect();
     if parts.len() != 3 {
         return Err(TbdexError::Jose(format!(
@@ -39,9 +73,5 @@ pub async fn verify(metadata: &Value, data: &Value, detached_compact_jws: &str)
             parts.len()
         )));
     }
-    let compact_jws = format!("{}.{}.{}", parts[0], payload, parts[2]);
-
-    let _ = Jws::from_compact_jws(&compact_jws, true).await?;
-
-    Ok(())
+    Ok(parts)
 }
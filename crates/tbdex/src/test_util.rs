@@ -0,0 +1,87 @@
+//! Fixtures shared by the unit tests.
+
+use crate::{
+    messages::{
+        close::{Close, CloseData},
+        MessageKind,
+    },
+    resources::offering::{
+        CancellationDetails, Offering, OfferingData, PayinDetails, PayinMethod, PayoutDetails,
+        PayoutMethod,
+    },
+};
+use serde_json::json;
+use web5::dids::bearer_did::BearerDid;
+
+/// A new exchange id.
+pub(crate) fn exchange_id() -> String {
+    MessageKind::Rfq.typesafe_id().unwrap()
+}
+
+/// A successful Close sent by `from` to a wallet, signed by `from`.
+pub(crate) fn signed_close(from: &BearerDid, exchange_id: &str, reason: &str) -> Close {
+    let mut close = Close::create(
+        "did:test:wallet",
+        &from.did.uri,
+        exchange_id,
+        &CloseData {
+            reason: Some(reason.to_string()),
+            success: Some(true),
+        },
+        None,
+        None,
+    )
+    .unwrap();
+    close.sign(from).unwrap();
+    close
+}
+
+/// A payout method of the given kind which settles within a minute.
+pub(crate) fn payout_method(kind: &str) -> PayoutMethod {
+    PayoutMethod {
+        kind: kind.to_string(),
+        name: None,
+        description: None,
+        group: None,
+        required_payment_details: None,
+        fee: None,
+        min: None,
+        max: None,
+        estimated_settlement_time: 60,
+    }
+}
+
+/// An Offering selling BTC for 10 to 1000 USD, paid in by a debit card whose number is required.
+pub(crate) fn offering() -> Offering {
+    Offering::create(
+        "did:test:pfi",
+        &OfferingData {
+            description: "Selling BTC for USD".to_string(),
+            payout_units_per_payin_unit: "0.00003".to_string(),
+            payin: PayinDetails {
+                currency_code: "USD".to_string(),
+                min: Some("10".to_string()),
+                max: Some("1000".to_string()),
+                methods: vec![PayinMethod {
+                    kind: "DEBIT_CARD".to_string(),
+                    required_payment_details: Some(json!({
+                        "$schema": "http://json-schema.org/draft-07/schema#",
+                        "type": "object",
+                        "properties": { "cardNumber": { "type": "string" } },
+                        "required": ["cardNumber"]
+                    })),
+                    ..Default::default()
+                }],
+            },
+            payout: PayoutDetails {
+                currency_code: "BTC".to_string(),
+                methods: vec![payout_method("BTC_ADDRESS")],
+                ..Default::default()
+            },
+            required_claims: None,
+            cancellation: CancellationDetails::default(),
+        },
+        None,
+    )
+    .unwrap()
+}
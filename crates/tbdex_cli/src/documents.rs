@@ -0,0 +1,144 @@
+use crate::errors::{CliError, Result};
+use serde_json::Value;
+use std::io::Read;
+use tbdex::{
+    json::{FromJson, ToJson},
+    messages::{Message, TbdexMessage},
+    resources::{balance::Balance, offering::Offering},
+};
+use web5::{
+    dids::{bearer_did::BearerDid, portable_did::PortableDid},
+    json::FromJson as _,
+};
+
+/// Any message or resource, as read from a file.
+#[derive(Debug)]
+pub enum Document {
+    Message(Message),
+    Offering(Offering),
+    Balance(Balance),
+}
+
+impl Document {
+    /// Parses a message or resource, choosing its type by `metadata.kind`.
+    pub fn parse(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        let kind = value
+            .pointer("/metadata/kind")
+            .and_then(Value::as_str)
+            .ok_or_else(|| CliError::Usage("input has no metadata.kind".to_string()))?;
+
+        Ok(match kind {
+            "offering" => Document::Offering(Offering::from_json_string(json)?),
+            "balance" => Document::Balance(Balance::from_json_string(json)?),
+            _ => Document::Message(Message::from_json_string(json)?),
+        })
+    }
+
+    pub fn kind(&self) -> String {
+        match self {
+            Document::Message(message) => message.kind().to_string(),
+            Document::Offering(offering) => offering.metadata.kind.to_string(),
+            Document::Balance(balance) => balance.metadata.kind.to_string(),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            Document::Message(message) => &message.metadata().id,
+            Document::Offering(offering) => &offering.metadata.id,
+            Document::Balance(balance) => &balance.metadata.id,
+        }
+    }
+
+    pub fn sign(&mut self, bearer_did: &BearerDid) -> Result<()> {
+        match self {
+            Document::Message(message) => message.sign(bearer_did)?,
+            Document::Offering(offering) => offering.sign(bearer_did)?,
+            Document::Balance(balance) => balance.sign(bearer_did)?,
+        }
+        Ok(())
+    }
+
+    pub async fn verify(&self) -> Result<()> {
+        match self {
+            Document::Message(message) => message.verify().await?,
+            Document::Offering(offering) => offering.verify().await?,
+            Document::Balance(balance) => balance.verify().await?,
+        }
+        Ok(())
+    }
+
+    pub fn to_value(&self) -> Result<Value> {
+        let json = match self {
+            Document::Message(message) => message.to_json_string()?,
+            Document::Offering(offering) => offering.to_json_string()?,
+            Document::Balance(balance) => balance.to_json_string()?,
+        };
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// Reads the file at `path`, or standard input if `path` is `-`.
+pub fn read_input(path: &str) -> Result<String> {
+    let read_error = |e: std::io::Error| CliError::Read {
+        path: path.to_string(),
+        reason: e.to_string(),
+    };
+
+    if path == "-" {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .map_err(read_error)?;
+        Ok(input)
+    } else {
+        std::fs::read_to_string(path).map_err(read_error)
+    }
+}
+
+/// Loads a signer from a portable DID file, which carries the DID document and private keys.
+pub fn load_bearer_did(path: &str) -> Result<BearerDid> {
+    let portable_did = PortableDid::from_json_string(&read_input(path)?)?;
+    Ok(BearerDid::from_portable_did(portable_did)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tbdex::resources::balance::BalanceData;
+    use web5::dids::methods::did_jwk::DidJwk;
+
+    #[test]
+    fn parses_by_kind_and_round_trips() {
+        let bearer_did = DidJwk::create(None).unwrap();
+        let mut balance = Balance::create(
+            &bearer_did.did.uri,
+            &BalanceData {
+                currency_code: "USD".to_string(),
+                available: "100.00".to_string(),
+            },
+            None,
+        )
+        .unwrap();
+        balance.sign(&bearer_did).unwrap();
+
+        let document = Document::parse(&balance.to_json_string().unwrap()).unwrap();
+        assert!(matches!(&document, Document::Balance(parsed) if *parsed == balance));
+        assert_eq!("balance", document.kind());
+        assert_eq!(balance.metadata.id, document.id());
+        assert_eq!(
+            serde_json::to_value(&balance).unwrap(),
+            document.to_value().unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_input_without_a_kind() {
+        assert!(matches!(
+            Document::parse(r#"{"metadata": {}}"#),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(Document::parse("nope"), Err(CliError::Json(_))));
+    }
+}
@@ -0,0 +1,30 @@
+use serde_json::Error as SerdeJsonError;
+use tbdex::errors::TbdexError;
+use thiserror::Error;
+use web5::errors::Web5Error;
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error(transparent)]
+    Tbdex(#[from] TbdexError),
+    #[error("json error {0}")]
+    Json(String),
+    #[error("unable to read {path}: {reason}")]
+    Read { path: String, reason: String },
+    #[error("{0}")]
+    Usage(String),
+}
+
+impl From<SerdeJsonError> for CliError {
+    fn from(error: SerdeJsonError) -> Self {
+        CliError::Json(error.to_string())
+    }
+}
+
+impl From<Web5Error> for CliError {
+    fn from(error: Web5Error) -> Self {
+        CliError::Tbdex(TbdexError::from(error))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, CliError>;
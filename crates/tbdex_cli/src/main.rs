@@ -0,0 +1,198 @@
+mod documents;
+mod errors;
+mod offline;
+mod pfi;
+
+use clap::{Parser, Subcommand};
+use errors::Result;
+use offline::{CreateArgs, CreateKind};
+use serde_json::Value;
+use std::process::ExitCode;
+use tbdex::http_client::exchanges::GetExchangeIdsQueryParams;
+use tracing_subscriber::EnvFilter;
+
+/// Create, inspect and verify tbDEX messages and resources, and talk to PFIs.
+///
+/// Every command writes JSON to stdout. Files may be given as `-` to read standard input.
+/// Logs go to stderr, filtered by `RUST_LOG` (e.g. `RUST_LOG=tbdex=debug`).
+#[derive(Parser)]
+#[command(name = "tbdex", version)]
+struct Cli {
+    /// Pretty-print the JSON output.
+    #[arg(long, global = true)]
+    pretty: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a message or resource from a data file and sign it.
+    Create {
+        kind: CreateKind,
+        /// The portable DID file of the sender.
+        #[arg(long)]
+        did: String,
+        /// The DID of the recipient, for messages.
+        #[arg(long)]
+        to: Option<String>,
+        /// The exchange the message belongs to, for messages other than RFQs.
+        #[arg(long)]
+        exchange_id: Option<String>,
+        /// The data file: `CreateRfqData` for RFQs, otherwise the `data` of the message or resource.
+        #[arg(long)]
+        data: Option<String>,
+        #[arg(long)]
+        protocol: Option<String>,
+        #[arg(long)]
+        external_id: Option<String>,
+    },
+    /// Sign a message or resource, replacing any existing signature.
+    Sign {
+        file: String,
+        /// The portable DID file of the signer.
+        #[arg(long)]
+        did: String,
+    },
+    /// Verify the schema and signature of a message or resource.
+    Verify { file: String },
+    /// Parse a message or resource and print it.
+    Print { file: String },
+    /// Verify that an RFQ's private data matches the hashes in its data.
+    VerifyPrivateData { file: String },
+    /// Verify that an RFQ satisfies the requirements of an offering.
+    CheckOffering {
+        #[arg(long)]
+        rfq: String,
+        #[arg(long)]
+        offering: String,
+    },
+    /// Resolve a PFI's service endpoint.
+    Resolve { pfi: String },
+    /// List a PFI's offerings.
+    Offerings { pfi: String },
+    /// List the balances a PFI holds for you.
+    Balances {
+        pfi: String,
+        /// Your portable DID file.
+        #[arg(long)]
+        did: String,
+    },
+    /// Fetch an exchange, or list your exchange ids if no exchange is given.
+    Exchanges {
+        pfi: String,
+        /// Your portable DID file.
+        #[arg(long)]
+        did: String,
+        #[arg(long)]
+        exchange_id: Option<String>,
+        #[arg(long)]
+        offset: Option<i64>,
+        #[arg(long)]
+        limit: Option<i64>,
+    },
+    /// Create, sign and submit an Order for an exchange.
+    SubmitOrder {
+        pfi: String,
+        /// Your portable DID file.
+        #[arg(long)]
+        did: String,
+        #[arg(long)]
+        exchange_id: String,
+        #[arg(long)]
+        external_id: Option<String>,
+    },
+}
+
+async fn run(command: Command) -> Result<Value> {
+    match command {
+        Command::Create {
+            kind,
+            did,
+            to,
+            exchange_id,
+            data,
+            protocol,
+            external_id,
+        } => offline::create(CreateArgs {
+            kind,
+            did_path: &did,
+            to: to.as_deref(),
+            exchange_id: exchange_id.as_deref(),
+            data_path: data.as_deref(),
+            protocol,
+            external_id,
+        }),
+        Command::Sign { file, did } => offline::sign(&file, &did),
+        Command::Verify { file } => offline::verify(&file).await,
+        Command::Print { file } => offline::print(&file),
+        Command::VerifyPrivateData { file } => offline::verify_private_data(&file),
+        Command::CheckOffering { rfq, offering } => offline::check_offering(&rfq, &offering).await,
+        Command::Resolve { pfi } => pfi::resolve(&pfi).await,
+        Command::Offerings { pfi } => pfi::offerings(&pfi).await,
+        Command::Balances { pfi, did } => pfi::balances(&pfi, &did).await,
+        Command::Exchanges {
+            pfi,
+            did,
+            exchange_id,
+            offset,
+            limit,
+        } => {
+            let query_params = GetExchangeIdsQueryParams {
+                pagination_offset: offset,
+                pagination_limit: limit,
+            };
+            pfi::exchanges(&pfi, &did, exchange_id.as_deref(), query_params).await
+        }
+        Command::SubmitOrder {
+            pfi,
+            did,
+            exchange_id,
+            external_id,
+        } => pfi::submit_order_for(&pfi, &did, &exchange_id, external_id).await,
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    // stdout is reserved for the JSON output
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+    let pretty = cli.pretty || matches!(cli.command, Command::Print { .. });
+
+    let (output, success) = match run(cli.command).await {
+        // checks report their own failure on stdout, but still exit unsuccessfully
+        Ok(output) => {
+            let success = output.get("valid") != Some(&Value::Bool(false));
+            (output, success)
+        }
+        Err(e) => {
+            eprintln!("{}", serde_json::json!({ "error": e.to_string() }));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rendered = if pretty {
+        serde_json::to_string_pretty(&output)
+    } else {
+        serde_json::to_string(&output)
+    };
+    match rendered {
+        Ok(rendered) => println!("{}", rendered),
+        Err(e) => {
+            eprintln!("{}", serde_json::json!({ "error": e.to_string() }));
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    documents::{load_bearer_did, read_input, Document},
+    errors::{CliError, Result},
+};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tbdex::{
+    json::FromJson,
+    messages::{
+        cancel::{Cancel, CancelData},
+        close::{Close, CloseData},
+        order::Order,
+        order_instructions::{OrderInstructions, OrderInstructionsData},
+        order_status::{OrderStatus, OrderStatusData},
+        quote::{Quote, QuoteData},
+        rfq::{CreateRfqData, Rfq},
+        Message,
+    },
+    resources::{
+        balance::{Balance, BalanceData},
+        offering::{Offering, OfferingData},
+    },
+};
+
+/// The kinds of message and resource which can be created.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CreateKind {
+    Rfq,
+    Quote,
+    Order,
+    OrderInstructions,
+    Cancel,
+    OrderStatus,
+    Close,
+    Offering,
+    Balance,
+}
+
+/// The arguments to [`create`].
+pub struct CreateArgs<'a> {
+    pub kind: CreateKind,
+    pub did_path: &'a str,
+    pub to: Option<&'a str>,
+    pub exchange_id: Option<&'a str>,
+    pub data_path: Option<&'a str>,
+    pub protocol: Option<String>,
+    pub external_id: Option<String>,
+}
+
+/// Creates a message or resource from the data file, signed by the portable DID.
+pub fn create(args: CreateArgs) -> Result<Value> {
+    let bearer_did = load_bearer_did(args.did_path)?;
+    let from = bearer_did.did.uri.as_str();
+
+    let required = |value: Option<&str>, name: &str| -> Result<String> {
+        value
+            .map(str::to_string)
+            .ok_or_else(|| CliError::Usage(format!("--{} is required", name)))
+    };
+    let to = || required(args.to, "to");
+    let exchange_id = || required(args.exchange_id, "exchange-id");
+    let data = || read_input(&required(args.data_path, "data")?);
+    let (protocol, external_id) = (args.protocol, args.external_id);
+
+    let mut document = match args.kind {
+        CreateKind::Rfq => Document::Message(Message::Rfq(Arc::new(Rfq::create(
+            &to()?,
+            from,
+            &CreateRfqData::from_json_string(&data()?)?,
+            protocol,
+            external_id,
+        )?))),
+        CreateKind::Quote => Document::Message(Message::Quote(Arc::new(Quote::create(
+            &to()?,
+            from,
+            &exchange_id()?,
+            &QuoteData::from_json_string(&data()?)?,
+            protocol,
+            external_id,
+        )?))),
+        CreateKind::Order => Document::Message(Message::Order(Arc::new(Order::create(
+            &to()?,
+            from,
+            &exchange_id()?,
+            protocol,
+            external_id,
+        )?))),
+        CreateKind::OrderInstructions => Document::Message(Message::OrderInstructions(Arc::new(
+            OrderInstructions::create(
+                &to()?,
+                from,
+                &exchange_id()?,
+                &OrderInstructionsData::from_json_string(&data()?)?,
+                protocol,
+                external_id,
+            )?,
+        ))),
+        CreateKind::Cancel => Document::Message(Message::Cancel(Arc::new(Cancel::create(
+            &to()?,
+            from,
+            &exchange_id()?,
+            &CancelData::from_json_string(&data()?)?,
+            protocol,
+            external_id,
+        )?))),
+        CreateKind::OrderStatus => {
+            Document::Message(Message::OrderStatus(Arc::new(OrderStatus::create(
+                &to()?,
+                from,
+                &exchange_id()?,
+                &OrderStatusData::from_json_string(&data()?)?,
+                protocol,
+                external_id,
+            )?)))
+        }
+        CreateKind::Close => Document::Message(Message::Close(Arc::new(Close::create(
+            &to()?,
+            from,
+            &exchange_id()?,
+            &CloseData::from_json_string(&data()?)?,
+            protocol,
+            external_id,
+        )?))),
+        CreateKind::Offering => Document::Offering(Offering::create(
+            from,
+            &OfferingData::from_json_string(&data()?)?,
+            protocol,
+        )?),
+        CreateKind::Balance => Document::Balance(Balance::create(
+            from,
+            &BalanceData::from_json_string(&data()?)?,
+            protocol,
+        )?),
+    };
+
+    document.sign(&bearer_did)?;
+    document.to_value()
+}
+
+/// Re-signs a message or resource with the portable DID.
+pub fn sign(path: &str, did_path: &str) -> Result<Value> {
+    let bearer_did = load_bearer_did(did_path)?;
+    let mut document = Document::parse(&read_input(path)?)?;
+    document.sign(&bearer_did)?;
+    document.to_value()
+}
+
+/// Verifies the schema and signature of a message or resource.
+pub async fn verify(path: &str) -> Result<Value> {
+    let document = Document::parse(&read_input(path)?)?;
+    let result = document.verify().await;
+    Ok(outcome(&document, result))
+}
+
+/// Parses a message or resource and returns it as JSON, for pretty-printing.
+pub fn print(path: &str) -> Result<Value> {
+    Document::parse(&read_input(path)?)?.to_value()
+}
+
+/// Verifies that all private data of an RFQ matches the hashes in its data.
+pub fn verify_private_data(path: &str) -> Result<Value> {
+    let rfq = Rfq::from_json_string(&read_input(path)?)?;
+    let result = rfq.verify_all_private_data().map_err(CliError::from);
+    Ok(outcome(
+        &Document::Message(Message::Rfq(Arc::new(rfq))),
+        result,
+    ))
+}
+
+/// Verifies that an RFQ satisfies the requirements of the offering it was made against.
+pub async fn check_offering(rfq_path: &str, offering_path: &str) -> Result<Value> {
+    let rfq = Rfq::from_json_string(&read_input(rfq_path)?)?;
+    let offering = Offering::from_json_string(&read_input(offering_path)?)?;
+
+    let result = rfq
+        .verify_offering_requirements(&offering)
+        .await
+        .map_err(CliError::from);
+    Ok(outcome(
+        &Document::Message(Message::Rfq(Arc::new(rfq))),
+        result,
+    ))
+}
+
+/// Reports whether a check of the document passed, and why not if it did not.
+fn outcome(document: &Document, result: Result<()>) -> Value {
+    let mut outcome = json!({
+        "kind": document.kind(),
+        "id": document.id(),
+        "valid": result.is_ok(),
+    });
+    if let Err(e) = result {
+        outcome["error"] = Value::String(e.to_string());
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tbdex::json::ToJson;
+    use web5::dids::methods::did_jwk::DidJwk;
+
+    #[test]
+    fn reports_failed_checks() {
+        let bearer_did = DidJwk::create(None).unwrap();
+        let balance = Balance::create(
+            &bearer_did.did.uri,
+            &BalanceData {
+                currency_code: "USD".to_string(),
+                available: "100.00".to_string(),
+            },
+            None,
+        )
+        .unwrap();
+        let document = Document::parse(&balance.to_json_string().unwrap()).unwrap();
+
+        let passed = outcome(&document, Ok(()));
+        assert_eq!(json!(true), passed["valid"]);
+        assert!(passed.get("error").is_none());
+
+        let failed = outcome(&document, Err(CliError::Usage("bad".to_string())));
+        assert_eq!(json!(false), failed["valid"]);
+        assert_eq!(json!("bad"), failed["error"]);
+        assert_eq!(json!(balance.metadata.id), failed["id"]);
+    }
+}
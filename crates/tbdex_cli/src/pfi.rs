@@ -0,0 +1,78 @@
+use crate::{documents::load_bearer_did, errors::Result};
+use serde_json::{json, Value};
+use tbdex::{
+    http_client::{
+        balances::get_balances,
+        exchanges::{get_exchange, get_exchange_ids, submit_order, GetExchangeIdsQueryParams},
+        get_service_endpoint,
+        offerings::get_offerings,
+    },
+    messages::order::Order,
+};
+
+/// Resolves the PFI's DID to its service endpoint.
+pub async fn resolve(pfi_did_uri: &str) -> Result<Value> {
+    let service_endpoint = get_service_endpoint(pfi_did_uri).await?;
+    Ok(json!({
+        "did": pfi_did_uri,
+        "serviceEndpoint": service_endpoint,
+    }))
+}
+
+/// Fetches the PFI's offerings, each of which is verified.
+pub async fn offerings(pfi_did_uri: &str) -> Result<Value> {
+    let offerings = get_offerings(pfi_did_uri).await?;
+    Ok(serde_json::to_value(offerings)?)
+}
+
+/// Fetches the balances the PFI holds for the requester.
+pub async fn balances(pfi_did_uri: &str, did_path: &str) -> Result<Value> {
+    let bearer_did = load_bearer_did(did_path)?;
+    let balances = get_balances(pfi_did_uri, &bearer_did).await?;
+    Ok(serde_json::to_value(balances)?)
+}
+
+/// Fetches a single exchange, or the ids of all of the requester's exchanges if no id is given.
+pub async fn exchanges(
+    pfi_did_uri: &str,
+    did_path: &str,
+    exchange_id: Option<&str>,
+    query_params: GetExchangeIdsQueryParams,
+) -> Result<Value> {
+    let bearer_did = load_bearer_did(did_path)?;
+
+    match exchange_id {
+        Some(exchange_id) => {
+            let exchange = get_exchange(pfi_did_uri, &bearer_did, exchange_id).await?;
+            Ok(serde_json::to_value(exchange)?)
+        }
+        None => {
+            let exchange_ids =
+                get_exchange_ids(pfi_did_uri, &bearer_did, Some(query_params)).await?;
+            Ok(serde_json::to_value(exchange_ids)?)
+        }
+    }
+}
+
+/// Creates, signs and submits an Order for the exchange, returning the Order sent.
+pub async fn submit_order_for(
+    pfi_did_uri: &str,
+    did_path: &str,
+    exchange_id: &str,
+    external_id: Option<String>,
+) -> Result<Value> {
+    let bearer_did = load_bearer_did(did_path)?;
+
+    let mut order = Order::create(
+        pfi_did_uri,
+        &bearer_did.did.uri,
+        exchange_id,
+        None,
+        external_id,
+    )?;
+    order.sign(&bearer_did)?;
+
+    submit_order(&order).await?;
+
+    Ok(serde_json::to_value(order)?)
+}
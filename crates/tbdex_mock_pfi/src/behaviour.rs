@@ -0,0 +1,42 @@
+use std::time::Duration;
+use tbdex::messages::{close::CloseData, order_status::OrderStatusData, quote::QuoteData};
+
+/// How a [`MockPfi`](crate::MockPfi) responds to wallets.
+///
+/// The default accepts valid RFQs and Orders without replying to them.
+#[derive(Clone, Debug, Default)]
+pub struct Behaviour {
+    /// Reply to each accepted RFQ with a Quote carrying this data.
+    pub auto_quote: Option<QuoteData>,
+
+    /// How long to wait before handling each request. The wait blocks the handling thread.
+    pub delay: Duration,
+
+    /// Refuse every RFQ with this response instead of opening an exchange.
+    pub reject_rfq: Option<Rejection>,
+
+    /// Refuse every Order with this response.
+    pub reject_order: Option<Rejection>,
+
+    /// Reply to each accepted Order with an OrderStatus for each of these, in order.
+    pub order_statuses: Vec<OrderStatusData>,
+
+    /// Close each exchange with this data once its Order statuses have been sent.
+    pub close: Option<CloseData>,
+}
+
+/// An error response the mock PFI sends in place of accepting a message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rejection {
+    pub status_code: u16,
+    pub message: String,
+}
+
+impl Rejection {
+    pub fn new(status_code: u16, message: &str) -> Self {
+        Self {
+            status_code,
+            message: message.to_string(),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use crate::pfi::{MockPfi, Request};
+use async_trait::async_trait;
+use http_std::{Client, FetchOptions, HttpStdError, Method, Response};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Once, RwLock,
+    },
+};
+use tbdex::http_client::{remove_service_endpoint, set_service_endpoint};
+
+lazy_static::lazy_static! {
+    static ref MOCK_PFIS: RwLock<HashMap<String, Arc<MockPfi>>> = RwLock::new(HashMap::new());
+}
+
+static INSTALL_CLIENT: Once = Once::new();
+static NEXT_HOST: AtomicUsize = AtomicUsize::new(0);
+
+/// An `http_std` client which hands requests straight to registered mock PFIs, without any
+/// network. Requests to any other URL fail.
+struct InProcessClient;
+
+#[async_trait]
+impl Client for InProcessClient {
+    async fn fetch(&self, url: &str, options: Option<FetchOptions>) -> http_std::Result<Response> {
+        let (pfi, path) = {
+            let mock_pfis = MOCK_PFIS.read().unwrap_or_else(|e| e.into_inner());
+            mock_pfis
+                .iter()
+                .find_map(|(endpoint, pfi)| {
+                    url.strip_prefix(endpoint.as_str())
+                        .filter(|path| path.is_empty() || path.starts_with('/'))
+                        .map(|path| (pfi.clone(), path.to_string()))
+                })
+                .ok_or_else(|| HttpStdError::Unknown(format!("no mock pfi serves {}", url)))?
+        };
+
+        let options = options.unwrap_or_default();
+        let request = Request {
+            method: options.method.unwrap_or(Method::Get),
+            path,
+            headers: options.headers.unwrap_or_default(),
+            body: options.body.unwrap_or_default(),
+        };
+        Ok(pfi.handle(request).await)
+    }
+}
+
+/// Serves the PFI to this process's `tbdex::http_client` without a network.
+///
+/// The first call installs an in-process client as the global `http_std` client, so afterwards
+/// only mock PFIs can be reached over HTTP; DIDs which need resolving over the network, such as
+/// `did:dht`, will no longer resolve. The registration lasts until the returned guard is dropped.
+pub fn serve_in_process(pfi: Arc<MockPfi>) -> InProcessRegistration {
+    INSTALL_CLIENT.call_once(|| http_std::set_client(Arc::new(InProcessClient)));
+
+    let endpoint = format!(
+        "http://mock-pfi-{}.invalid",
+        NEXT_HOST.fetch_add(1, Ordering::Relaxed)
+    );
+    let did_uri = pfi.did_uri().to_string();
+
+    MOCK_PFIS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(endpoint.clone(), pfi);
+    set_service_endpoint(&did_uri, &endpoint);
+
+    InProcessRegistration { endpoint, did_uri }
+}
+
+/// Keeps a mock PFI reachable in-process until dropped.
+pub struct InProcessRegistration {
+    endpoint: String,
+    did_uri: String,
+}
+
+impl InProcessRegistration {
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+impl Drop for InProcessRegistration {
+    fn drop(&mut self) {
+        remove_service_endpoint(&self.did_uri);
+        MOCK_PFIS
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.endpoint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::offering_data, Behaviour, Rejection};
+    use futures::executor::block_on;
+    use serde_json::json;
+    use tbdex::{
+        http_client::{
+            balances::get_balances,
+            exchanges::{create_exchange, get_exchange, get_exchange_ids, submit_order},
+            offerings::get_offerings,
+        },
+        messages::{
+            close::CloseData,
+            order::Order,
+            order_status::{OrderStatusData, Status},
+            quote::{QuoteData, QuoteDetails},
+            rfq::{CreateRfqData, CreateSelectedPayinMethod, CreateSelectedPayoutMethod, Rfq},
+        },
+        resources::balance::BalanceData,
+    };
+    use web5::dids::methods::did_jwk::DidJwk;
+
+    fn quote_data() -> QuoteData {
+        QuoteData {
+            expires_at: "2030-01-01T00:00:00Z".to_string(),
+            payout_units_per_payin_unit: "0.00003".to_string(),
+            payin: QuoteDetails {
+                currency_code: "USD".to_string(),
+                subtotal: "100".to_string(),
+                total: "100".to_string(),
+                fee: None,
+            },
+            payout: QuoteDetails {
+                currency_code: "BTC".to_string(),
+                subtotal: "0.003".to_string(),
+                total: "0.003".to_string(),
+                fee: None,
+            },
+        }
+    }
+
+    fn rfq_data(pfi: &MockPfi) -> CreateRfqData {
+        CreateRfqData {
+            offering_id: pfi.offerings()[0].metadata.id.clone(),
+            payin: CreateSelectedPayinMethod {
+                kind: "DEBIT_CARD".to_string(),
+                payment_details: Some(json!({"cardNumber": "4242"}).into()),
+                amount: "100".to_string(),
+            },
+            payout: CreateSelectedPayoutMethod {
+                kind: "BTC_ADDRESS".to_string(),
+                payment_details: None,
+            },
+            claims: vec![].into(),
+        }
+    }
+
+    #[test]
+    fn wallet_flow() {
+        let behaviour = Behaviour {
+            auto_quote: Some(quote_data()),
+            order_statuses: vec![
+                OrderStatusData {
+                    status: Status::PayinSettled,
+                    details: None,
+                },
+                OrderStatusData {
+                    status: Status::PayoutSettled,
+                    details: None,
+                },
+            ],
+            close: Some(CloseData {
+                reason: None,
+                success: Some(true),
+            }),
+            ..Default::default()
+        };
+        let balances = vec![BalanceData {
+            currency_code: "USD".to_string(),
+            available: "100.00".to_string(),
+        }];
+        let pfi = Arc::new(MockPfi::new(vec![offering_data()], balances, behaviour).unwrap());
+        let _registration = serve_in_process(pfi.clone());
+        let alice = DidJwk::create(None).unwrap();
+
+        block_on(async {
+            let offerings = get_offerings(pfi.did_uri()).await.unwrap();
+            assert_eq!(pfi.offerings()[0].metadata.id, offerings[0].metadata.id);
+
+            let balances = get_balances(pfi.did_uri(), &alice).await.unwrap();
+            assert_eq!(pfi.balances()[0].metadata.id, balances[0].metadata.id);
+
+            let mut rfq =
+                Rfq::create(pfi.did_uri(), &alice.did.uri, &rfq_data(&pfi), None, None).unwrap();
+            rfq.sign(&alice).unwrap();
+            create_exchange(&rfq, None).await.unwrap();
+
+            let exchange_id = rfq.metadata.exchange_id.clone();
+            let exchange = get_exchange(pfi.did_uri(), &alice, &exchange_id)
+                .await
+                .unwrap();
+            assert!(exchange.quote.is_some());
+
+            let mut order =
+                Order::create(pfi.did_uri(), &alice.did.uri, &exchange_id, None, None).unwrap();
+            order.sign(&alice).unwrap();
+            submit_order(&order).await.unwrap();
+
+            let exchange = get_exchange(pfi.did_uri(), &alice, &exchange_id)
+                .await
+                .unwrap();
+            assert_eq!(2, exchange.order_statuses.map_or(0, |s| s.len()));
+            assert_eq!(Some(true), exchange.close.unwrap().data.success);
+
+            let exchange_ids = get_exchange_ids(pfi.did_uri(), &alice, None).await.unwrap();
+            assert_eq!(vec![exchange_id], exchange_ids);
+        });
+    }
+
+    #[test]
+    fn rejects_as_configured() {
+        let behaviour = Behaviour {
+            reject_rfq: Some(Rejection::new(400, "no liquidity")),
+            ..Default::default()
+        };
+        let pfi = Arc::new(MockPfi::new(vec![offering_data()], vec![], behaviour).unwrap());
+        let _registration = serve_in_process(pfi.clone());
+        let alice = DidJwk::create(None).unwrap();
+
+        let mut rfq =
+            Rfq::create(pfi.did_uri(), &alice.did.uri, &rfq_data(&pfi), None, None).unwrap();
+        rfq.sign(&alice).unwrap();
+
+        assert!(block_on(create_exchange(&rfq, None)).is_err());
+        assert!(pfi.exchange(&rfq.metadata.exchange_id).is_none());
+    }
+}
@@ -0,0 +1,63 @@
+//! A local PFI for integration testing wallets, without a network.
+//!
+//! A [`MockPfi`] serves scripted offerings and balances and answers exchanges as its
+//! [`Behaviour`] dictates, speaking the same HTTP bodies as `tbdex::http`. It is reached either
+//! in-process through [`serve_in_process`], which routes `tbdex::http_client` straight to it, or
+//! over a loopback port through [`LoopbackServer`].
+
+mod behaviour;
+mod in_process;
+mod loopback;
+mod pfi;
+
+pub use behaviour::{Behaviour, Rejection};
+pub use in_process::{serve_in_process, InProcessRegistration};
+pub use loopback::LoopbackServer;
+pub use pfi::{MockPfi, Request};
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tbdex::resources::offering::{
+        CancellationDetails, OfferingData, PayinDetails, PayinMethod, PayoutDetails, PayoutMethod,
+    };
+
+    pub(crate) fn offering_data() -> OfferingData {
+        OfferingData {
+            description: "Selling BTC for USD".to_string(),
+            payout_units_per_payin_unit: "0.00003".to_string(),
+            payin: PayinDetails {
+                currency_code: "USD".to_string(),
+                min: Some("10".to_string()),
+                max: Some("1000".to_string()),
+                methods: vec![PayinMethod {
+                    kind: "DEBIT_CARD".to_string(),
+                    required_payment_details: Some(json!({
+                        "$schema": "http://json-schema.org/draft-07/schema#",
+                        "type": "object",
+                        "properties": { "cardNumber": { "type": "string" } },
+                        "required": ["cardNumber"]
+                    })),
+                    ..Default::default()
+                }],
+            },
+            payout: PayoutDetails {
+                currency_code: "BTC".to_string(),
+                methods: vec![PayoutMethod {
+                    kind: "BTC_ADDRESS".to_string(),
+                    name: None,
+                    description: None,
+                    group: None,
+                    required_payment_details: None,
+                    fee: None,
+                    min: None,
+                    max: None,
+                    estimated_settlement_time: 60,
+                }],
+                ..Default::default()
+            },
+            required_claims: None,
+            cancellation: CancellationDetails::default(),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::pfi::{MockPfi, Request};
+use http_std::{Method, Response};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+use tbdex::http_client::{remove_service_endpoint, set_service_endpoint};
+
+/// Serves a mock PFI over HTTP/1.1 on a loopback port, for clients in other processes or
+/// languages. Each connection carries a single request.
+///
+/// The PFI's service endpoint is set to the server's address until the server is dropped.
+pub struct LoopbackServer {
+    address: SocketAddr,
+    endpoint: String,
+    did_uri: String,
+    shutdown: Arc<AtomicBool>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl LoopbackServer {
+    /// Binds an unused port on `127.0.0.1` and starts serving the PFI on it.
+    pub fn start(pfi: Arc<MockPfi>) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let endpoint = format!("http://{}", address);
+        let did_uri = pfi.did_uri().to_string();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let accept_thread = {
+            let shutdown = shutdown.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let pfi = pfi.clone();
+                        thread::spawn(move || serve_connection(&pfi, stream));
+                    }
+                }
+            })
+        };
+
+        set_service_endpoint(&did_uri, &endpoint);
+
+        Ok(Self {
+            address,
+            endpoint,
+            did_uri,
+            shutdown,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+impl Drop for LoopbackServer {
+    fn drop(&mut self) {
+        remove_service_endpoint(&self.did_uri);
+
+        // wake the accept loop so that it sees the shutdown flag
+        self.shutdown.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect(self.address);
+        if let Some(accept_thread) = self.accept_thread.take() {
+            let _ = accept_thread.join();
+        }
+    }
+}
+
+fn serve_connection(pfi: &MockPfi, stream: TcpStream) {
+    let response = match read_request(&stream) {
+        Ok(request) => futures::executor::block_on(pfi.handle(request)),
+        Err(e) => Response {
+            status_code: 400,
+            headers: HashMap::new(),
+            body: e.to_string().into_bytes(),
+        },
+    };
+    let _ = write_response(&stream, &response);
+}
+
+fn read_request(stream: &TcpStream) -> io::Result<Request> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts
+        .next()
+        .and_then(|method| Method::from_str(method).ok())
+        .ok_or_else(|| invalid("unsupported method"))?;
+    let path = parts
+        .next()
+        .ok_or_else(|| invalid("missing request target"))?
+        .to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid("malformed header"))?;
+        headers.insert(name.trim().to_string(), value.trim().to_string());
+    }
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.parse::<usize>())
+        .transpose()
+        .map_err(|_| invalid("invalid content-length"))?
+        .unwrap_or(0);
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status_code, reason(response));
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn reason(response: &Response) -> &'static str {
+    match response.status_code {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::offering_data, Behaviour};
+    use tbdex::{http::offerings::GetOfferingsResponseBody, json::FromJson};
+
+    fn request(address: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_offerings_over_loopback() {
+        let pfi =
+            Arc::new(MockPfi::new(vec![offering_data()], vec![], Behaviour::default()).unwrap());
+        let server = LoopbackServer::start(pfi.clone()).unwrap();
+
+        let response = request(
+            server.address,
+            "GET /offerings HTTP/1.1\r\nHost: pfi\r\n\r\n",
+        );
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+
+        let offerings = GetOfferingsResponseBody::from_json_string(body).unwrap();
+        assert_eq!(
+            pfi.offerings()[0].metadata.id,
+            offerings.data[0].metadata.id
+        );
+    }
+
+    #[test]
+    fn requires_an_access_token() {
+        let pfi = Arc::new(MockPfi::new(vec![], vec![], Behaviour::default()).unwrap());
+        let server = LoopbackServer::start(pfi).unwrap();
+
+        let response = request(server.address, "GET /exchanges HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized"));
+    }
+}
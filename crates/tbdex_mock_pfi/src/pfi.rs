@@ -0,0 +1,447 @@
+use crate::behaviour::{Behaviour, Rejection};
+use base64::{engine::general_purpose, Engine as _};
+use http_std::{Method, Response};
+use std::{collections::HashMap, sync::Mutex};
+use tbdex::{
+    errors::Result,
+    http::{
+        balances::GetBalancesResponseBody,
+        exchanges::{
+            CreateExchangeRequestBody, GetExchangeResponseBody, GetExchangesResponseBody,
+            UpdateExchangeRequestBody, WalletUpdateMessage,
+        },
+        offerings::GetOfferingsResponseBody,
+        ErrorResponseBody,
+    },
+    json::{FromJson, ToJson},
+    messages::{
+        cancel::cancel_eligibility,
+        close::{Close, CloseData},
+        order_status::OrderStatus,
+        quote::Quote,
+        Message, MessageKind, TbdexMessage,
+    },
+    resources::{
+        balance::{Balance, BalanceData},
+        offering::{Offering, OfferingData},
+    },
+};
+use web5::dids::{bearer_did::BearerDid, methods::did_jwk::DidJwk};
+
+/// An HTTP request as received by the mock PFI, with the path relative to its service endpoint.
+pub struct Request {
+    pub method: Method,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+struct MockExchange {
+    id: String,
+    requester: String,
+    messages: Vec<Message>,
+}
+
+/// A PFI serving scripted offerings and balances and answering exchanges as its [`Behaviour`]
+/// dictates.
+///
+/// The PFI is identified by a freshly created `did:jwk` and signs everything it sends with it.
+/// It keeps exchanges in memory only.
+pub struct MockPfi {
+    bearer_did: BearerDid,
+    offerings: Vec<Offering>,
+    balances: Vec<Balance>,
+    behaviour: Behaviour,
+    exchanges: Mutex<Vec<MockExchange>>,
+}
+
+impl MockPfi {
+    /// Creates a PFI with a new DID, creating and signing an offering and a balance for each of
+    /// the given data.
+    pub fn new(
+        offerings: Vec<OfferingData>,
+        balances: Vec<BalanceData>,
+        behaviour: Behaviour,
+    ) -> Result<Self> {
+        let bearer_did = DidJwk::create(None)?;
+
+        let offerings = offerings
+            .iter()
+            .map(|data| {
+                let mut offering = Offering::create(&bearer_did.did.uri, data, None)?;
+                offering.sign(&bearer_did)?;
+                Ok(offering)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let balances = balances
+            .iter()
+            .map(|data| {
+                let mut balance = Balance::create(&bearer_did.did.uri, data, None)?;
+                balance.sign(&bearer_did)?;
+                Ok(balance)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            bearer_did,
+            offerings,
+            balances,
+            behaviour,
+            exchanges: Mutex::new(vec![]),
+        })
+    }
+
+    pub fn did_uri(&self) -> &str {
+        &self.bearer_did.did.uri
+    }
+
+    pub fn offerings(&self) -> &[Offering] {
+        &self.offerings
+    }
+
+    pub fn balances(&self) -> &[Balance] {
+        &self.balances
+    }
+
+    /// Returns every message of the exchange, as the PFI holds it.
+    pub fn exchange(&self, exchange_id: &str) -> Option<Vec<Message>> {
+        self.lock_exchanges()
+            .iter()
+            .find(|exchange| exchange.id == exchange_id)
+            .map(|exchange| exchange.messages.clone())
+    }
+
+    /// Answers a request made to the PFI's service endpoint.
+    pub async fn handle(&self, request: Request) -> Response {
+        if !self.behaviour.delay.is_zero() {
+            std::thread::sleep(self.behaviour.delay);
+        }
+
+        let (path, query) = match request.path.split_once('?') {
+            Some((path, query)) => (path, query),
+            None => (request.path.as_str(), ""),
+        };
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        match (&request.method, segments.as_slice()) {
+            (Method::Get, ["offerings"]) => json_response(
+                200,
+                &GetOfferingsResponseBody {
+                    data: self.offerings.clone(),
+                },
+            ),
+            (Method::Get, ["balances"]) => match requester(&request.headers) {
+                Some(_) => json_response(
+                    200,
+                    &GetBalancesResponseBody {
+                        data: self.balances.clone(),
+                    },
+                ),
+                None => error_response(401, "missing access token"),
+            },
+            (Method::Get, ["exchanges"]) => match requester(&request.headers) {
+                Some(requester) => self.get_exchange_ids(&requester, query),
+                None => error_response(401, "missing access token"),
+            },
+            (Method::Get, ["exchanges", exchange_id]) => match requester(&request.headers) {
+                Some(requester) => self.get_exchange(&requester, exchange_id),
+                None => error_response(401, "missing access token"),
+            },
+            (Method::Post, ["exchanges"]) => self.create_exchange(&request.body).await,
+            (Method::Put, ["exchanges", exchange_id]) => {
+                self.update_exchange(exchange_id, &request.body).await
+            }
+            _ => error_response(404, &format!("no route for {}", request.path)),
+        }
+    }
+
+    fn get_exchange_ids(&self, requester: &str, query: &str) -> Response {
+        let mut offset = 0;
+        let mut limit = usize::MAX;
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            let value = match value.parse::<usize>() {
+                Ok(value) => value,
+                Err(_) => return error_response(400, &format!("invalid {}", key)),
+            };
+            match key {
+                "page[offset]" => offset = value,
+                "page[limit]" => limit = value,
+                _ => {}
+            }
+        }
+
+        let ids = self
+            .lock_exchanges()
+            .iter()
+            .filter(|exchange| exchange.requester == requester)
+            .map(|exchange| exchange.id.clone())
+            .skip(offset)
+            .take(limit)
+            .collect();
+        json_response(200, &GetExchangesResponseBody { data: ids })
+    }
+
+    fn get_exchange(&self, requester: &str, exchange_id: &str) -> Response {
+        match self
+            .lock_exchanges()
+            .iter()
+            .find(|exchange| exchange.id == exchange_id && exchange.requester == requester)
+        {
+            Some(exchange) => json_response(
+                200,
+                &GetExchangeResponseBody {
+                    data: exchange.messages.clone(),
+                },
+            ),
+            None => error_response(404, &format!("exchange {} not found", exchange_id)),
+        }
+    }
+
+    async fn create_exchange(&self, body: &[u8]) -> Response {
+        let rfq = match parse_body::<CreateExchangeRequestBody>(body) {
+            Ok(body) => body.message,
+            Err(response) => return response,
+        };
+
+        if let Some(rejection) = &self.behaviour.reject_rfq {
+            return rejection_response(rejection);
+        }
+        if rfq.metadata.to != self.did_uri() {
+            return error_response(400, "rfq is not addressed to this pfi");
+        }
+        if let Err(e) = rfq.verify().await {
+            return error_response(400, &e.to_string());
+        }
+        let offering = match self
+            .offerings
+            .iter()
+            .find(|offering| offering.metadata.id == rfq.data.offering_id)
+        {
+            Some(offering) => offering,
+            None => return error_response(400, "rfq names an unknown offering"),
+        };
+        if let Err(e) = rfq.verify_offering_requirements(offering).await {
+            return error_response(400, &e.to_string());
+        }
+
+        let exchange_id = rfq.metadata.exchange_id.clone();
+        let requester = rfq.metadata.from.clone();
+        let protocol = rfq.metadata.protocol.clone();
+
+        let mut messages = vec![Message::Rfq(rfq.into())];
+        if let Some(quote_data) = &self.behaviour.auto_quote {
+            let quote = Quote::create(
+                &requester,
+                self.did_uri(),
+                &exchange_id,
+                quote_data,
+                Some(protocol),
+                None,
+            )
+            .and_then(|mut quote| quote.sign(&self.bearer_did).map(|_| quote));
+            match quote {
+                Ok(quote) => messages.push(Message::Quote(quote.into())),
+                Err(e) => return error_response(500, &e.to_string()),
+            }
+        }
+
+        let mut exchanges = self.lock_exchanges();
+        if exchanges.iter().any(|exchange| exchange.id == exchange_id) {
+            return error_response(409, &format!("exchange {} already exists", exchange_id));
+        }
+        exchanges.push(MockExchange {
+            id: exchange_id,
+            requester,
+            messages,
+        });
+
+        empty_response(202)
+    }
+
+    async fn update_exchange(&self, exchange_id: &str, body: &[u8]) -> Response {
+        let message = match parse_body::<UpdateExchangeRequestBody>(body) {
+            Ok(body) => body.message,
+            Err(response) => return response,
+        };
+
+        if message.metadata().exchange_id != exchange_id {
+            return error_response(400, "message belongs to a different exchange");
+        }
+        if let WalletUpdateMessage::Order(_) = &message {
+            if let Some(rejection) = &self.behaviour.reject_order {
+                return rejection_response(rejection);
+            }
+        }
+        if let Err(e) = message.verify().await {
+            return error_response(400, &e.to_string());
+        }
+
+        let mut exchanges = self.lock_exchanges();
+        let exchange = match exchanges
+            .iter_mut()
+            .find(|exchange| exchange.id == exchange_id)
+        {
+            Some(exchange) => exchange,
+            None => return error_response(404, &format!("exchange {} not found", exchange_id)),
+        };
+        if message.metadata().from != exchange.requester {
+            return error_response(400, "message is not from the exchange's requester");
+        }
+        if has_kind(&exchange.messages, MessageKind::Close) {
+            return error_response(409, "exchange is closed");
+        }
+
+        let replies = match &message {
+            WalletUpdateMessage::Order(order) => {
+                if !has_kind(&exchange.messages, MessageKind::Quote) {
+                    return error_response(409, "exchange has no quote");
+                }
+                if has_kind(&exchange.messages, MessageKind::Order) {
+                    return error_response(409, "exchange already has an order");
+                }
+                exchange.messages.push(Message::Order(order.clone()));
+                self.order_replies(exchange)
+            }
+            WalletUpdateMessage::Cancel(cancel) => {
+                let eligibility = match self.offering_of(exchange) {
+                    Some(offering) => cancel_eligibility(offering, &exchange.messages),
+                    None => return error_response(409, "exchange's offering not found"),
+                };
+                if let Err(e) = eligibility.check() {
+                    return error_response(409, &e.to_string());
+                }
+                exchange.messages.push(Message::Cancel(cancel.clone()));
+                let close_data = CloseData {
+                    reason: Some("cancelled".to_string()),
+                    success: Some(false),
+                };
+                self.close(exchange, &close_data)
+                    .map(|close| vec![Message::Close(close.into())])
+            }
+        };
+
+        match replies {
+            Ok(replies) => {
+                exchange.messages.extend(replies);
+                empty_response(202)
+            }
+            Err(e) => error_response(500, &e.to_string()),
+        }
+    }
+
+    fn order_replies(&self, exchange: &MockExchange) -> Result<Vec<Message>> {
+        let protocol = exchange.messages[0].metadata().protocol.clone();
+
+        let mut replies = vec![];
+        for status in &self.behaviour.order_statuses {
+            let mut order_status = OrderStatus::create(
+                &exchange.requester,
+                self.did_uri(),
+                &exchange.id,
+                status,
+                Some(protocol.clone()),
+                None,
+            )?;
+            order_status.sign(&self.bearer_did)?;
+            replies.push(Message::OrderStatus(order_status.into()));
+        }
+        if let Some(close_data) = &self.behaviour.close {
+            replies.push(Message::Close(self.close(exchange, close_data)?.into()));
+        }
+        Ok(replies)
+    }
+
+    fn close(&self, exchange: &MockExchange, close_data: &CloseData) -> Result<Close> {
+        let mut close = Close::create(
+            &exchange.requester,
+            self.did_uri(),
+            &exchange.id,
+            close_data,
+            Some(exchange.messages[0].metadata().protocol.clone()),
+            None,
+        )?;
+        close.sign(&self.bearer_did)?;
+        Ok(close)
+    }
+
+    fn offering_of(&self, exchange: &MockExchange) -> Option<&Offering> {
+        match &exchange.messages[0] {
+            Message::Rfq(rfq) => self
+                .offerings
+                .iter()
+                .find(|offering| offering.metadata.id == rfq.data.offering_id),
+            _ => None,
+        }
+    }
+
+    fn lock_exchanges(&self) -> std::sync::MutexGuard<'_, Vec<MockExchange>> {
+        self.exchanges.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn has_kind(messages: &[Message], kind: MessageKind) -> bool {
+    messages.iter().any(|message| message.kind() == kind)
+}
+
+/// Returns the `iss` of the bearer token, the DID of the requester.
+///
+/// The token's signature is not checked: the mock PFI trusts whoever it is talking to.
+fn requester(headers: &HashMap<String, String>) -> Option<String> {
+    let token = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))?
+        .1
+        .strip_prefix("Bearer ")?;
+    let payload = general_purpose::URL_SAFE_NO_PAD
+        .decode(token.split('.').nth(1)?)
+        .ok()?;
+    let claims = serde_json::from_slice::<serde_json::Value>(&payload).ok()?;
+    claims.get("iss")?.as_str().map(str::to_string)
+}
+
+fn parse_body<T: FromJson>(body: &[u8]) -> std::result::Result<T, Response> {
+    let json = std::str::from_utf8(body).map_err(|e| error_response(400, &e.to_string()))?;
+    T::from_json_string(json).map_err(|e| error_response(400, &e.to_string()))
+}
+
+fn json_response<T: ToJson>(status_code: u16, body: &T) -> Response {
+    match body.to_json_string() {
+        Ok(json) => Response {
+            status_code,
+            headers: [("Content-Type".to_string(), "application/json".to_string())]
+                .into_iter()
+                .collect(),
+            body: json.into_bytes(),
+        },
+        Err(e) => error_response(500, &e.to_string()),
+    }
+}
+
+fn empty_response(status_code: u16) -> Response {
+    Response {
+        status_code,
+        headers: HashMap::new(),
+        body: vec![],
+    }
+}
+
+fn error_response(status_code: u16, message: &str) -> Response {
+    let body = ErrorResponseBody {
+        message: message.to_string(),
+        details: None,
+    };
+    Response {
+        status_code,
+        headers: [("Content-Type".to_string(), "application/json".to_string())]
+            .into_iter()
+            .collect(),
+        body: body.to_json_string().unwrap_or_default().into_bytes(),
+    }
+}
+
+fn rejection_response(rejection: &Rejection) -> Response {
+    error_response(rejection.status_code, &rejection.message)
+}